@@ -0,0 +1,38 @@
+use std::{hint::black_box, path::PathBuf};
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use quetite::{
+    evaluator::{Evaluator, resolver::Resolver},
+    lexer::Lexer,
+    parser::Parser,
+    src::Src,
+    vm::Vm,
+};
+
+/// Lexes, parses and resolves a script of `benches/scripts`.
+fn load(name: &str) -> Src {
+    let file = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("benches/scripts")
+        .join(name);
+    let mut src = Src::new(file);
+    src.tokens = Lexer::new(src.text.clone()).tokenize().tokens;
+    src.ast = Parser::new(&src).parse().ast;
+    src.ast = Resolver::new(&src).resolve().ast;
+    src
+}
+
+/// Pieces dropped on a board, most of the time is spent calling methods on `self`.
+fn board(c: &mut Criterion) {
+    let src = load("board.qte");
+    let mut group = c.benchmark_group("board");
+    group.bench_function("evaluator", |b| {
+        b.iter(|| black_box(Evaluator::new(&src).eval().is_ok()))
+    });
+    group.bench_function("vm", |b| {
+        b.iter(|| black_box(Vm::eval(&mut Evaluator::new(&src)).is_ok()))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, board);
+criterion_main!(benches);
@@ -0,0 +1,79 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    mem::{self, Discriminant},
+    rc::{Rc, Weak},
+};
+
+use crate::evaluator::{
+    object::{Method, Object},
+    prototype::Prototype,
+    value::{Callable, Value},
+};
+
+#[derive(Debug, Clone, Default)]
+enum Entry {
+    #[default]
+    Empty,
+    /// Method of instances of the object owning the method table, the weak pointer keeps the
+    /// address of the table from being reused while the entry exists
+    Method {
+        methods: Weak<HashMap<String, Method>>,
+        method: Method,
+    },
+    /// Prototype method of a primitive type
+    Proto {
+        kind: Discriminant<Value>,
+        method: Rc<dyn Callable>,
+    },
+}
+
+/// Inline cache of a property access. Remembers the method found for the last type of
+/// receiver seen at the access, so calling it again skips walking the method tables.
+#[derive(Debug, Clone, Default)]
+pub struct PropCache {
+    entry: RefCell<Entry>,
+}
+
+impl PropCache {
+    /// Finds a method of instances of `obj`.
+    pub fn method(&self, obj: &Object, name: &str) -> Option<Method> {
+        if let Entry::Method { methods, method } = &*self.entry.borrow()
+            && std::ptr::eq(methods.as_ptr(), Rc::as_ptr(&obj.methods))
+        {
+            return Some(method.clone());
+        }
+
+        let method = obj.find_method(name)?;
+        self.entry.replace(Entry::Method {
+            methods: Rc::downgrade(&obj.methods),
+            method: method.clone(),
+        });
+        Some(method)
+    }
+
+    /// Finds a method of the prototype of `val`.
+    pub fn proto_method(
+        &self,
+        val: &Value,
+        proto: &Prototype,
+        name: &str,
+    ) -> Option<Rc<dyn Callable>> {
+        let kind = mem::discriminant(val);
+        if let Entry::Proto {
+            kind: cached,
+            method,
+        } = &*self.entry.borrow()
+            && *cached == kind
+        {
+            return Some(method.clone());
+        }
+
+        let method = proto.get_method(name)?;
+        self.entry.replace(Entry::Proto {
+            kind,
+            method: method.clone(),
+        });
+        Some(method)
+    }
+}
@@ -12,75 +12,112 @@ use crate::{
 
 pub type EnvPtr = Rc<RefCell<Env>>;
 
+/// Globals and names defined by the REPL are hashed, the locals of a scope are stored in the
+/// slots the resolver gave them and can only be reached through them.
 #[derive(Debug)]
 pub struct Env {
     enclosing: Option<EnvPtr>,
-    values: FxHashMap<String, Value>,
+    /// Hashed bindings of global envs
+    values: Option<FxHashMap<String, Value>>,
+    /// Slotted bindings of scope envs
+    slots: Vec<Value>,
 }
 
 impl Env {
     pub fn new() -> EnvPtr {
         Rc::new(RefCell::new(Self {
             enclosing: None,
-            values: FxHashMap::default(),
+            values: Some(FxHashMap::default()),
+            slots: Vec::new(),
         }))
     }
 
+    /// Global env of a script or the REPL.
+    pub fn globals(enclosing: EnvPtr) -> EnvPtr {
+        Rc::new(RefCell::new(Self {
+            enclosing: Some(enclosing),
+            values: Some(FxHashMap::default()),
+            slots: Vec::new(),
+        }))
+    }
+
+    /// Env of a scope, its locals are defined in the order the resolver gave them slots.
     pub fn enclosed(enclosing: EnvPtr) -> EnvPtr {
+        Self::with_slots(enclosing, Vec::new())
+    }
+
+    /// Env of a scope whose locals are all known up front.
+    pub fn with_slots(enclosing: EnvPtr, slots: Vec<Value>) -> EnvPtr {
         Rc::new(RefCell::new(Self {
             enclosing: Some(enclosing),
-            values: FxHashMap::default(),
+            values: None,
+            slots,
         }))
     }
 
+    /// Defines a binding, locals take the next slot since each declaration has its own.
     pub fn define(&mut self, name: String, val: Value) {
-        self.values.insert(name, val);
+        match &mut self.values {
+            Some(values) => {
+                values.insert(name, val);
+            }
+            None => self.slots.push(val),
+        }
     }
 
+    /// Assigns a hashed binding, locals are only assigned through their slot.
     pub fn assign(&mut self, name: &str, val: Value, cursor: Cursor) -> EvalResult<()> {
-        if self.values.contains_key(name) {
-            self.values.insert(name.to_string(), val);
+        if let Some(values) = &mut self.values
+            && let Some(entry) = values.get_mut(name)
+        {
+            *entry = val;
             return Ok(());
         }
         if let Some(ref parent) = self.enclosing {
             return parent.borrow_mut().assign(name, val, cursor);
         }
-        Err(RuntimeEvent::error(
-            ErrKind::Name,
-            format!("undefined variable '{}'", name),
-            cursor,
-        ))
+        Err(Self::undefined(name, cursor))
     }
 
+    /// Gets a hashed binding, scopes in between are skipped as their locals have no names.
     pub fn get(&self, name: &str, cursor: Cursor) -> EvalResult<Value> {
-        if let Some(val) = self.values.get(name) {
-            return Ok(val.clone());
+        if let Some(val) = self.get_local(name) {
+            return Ok(val);
         }
         if let Some(ref parent) = self.enclosing {
             return parent.borrow().get(name, cursor);
         }
-        Err(RuntimeEvent::error(
-            ErrKind::Name,
-            format!("undefined variable '{}'", name),
-            cursor,
-        ))
+        Err(Self::undefined(name, cursor))
+    }
+
+    /// Get a hashed binding from the current env only (no enclosing envs).
+    pub fn get_local(&self, name: &str) -> Option<Value> {
+        self.values.as_ref()?.get(name).cloned()
     }
 
-    pub fn assign_at(env_ptr: &EnvPtr, name: &str, val: Value, dist: usize) -> EvalResult<()> {
+    pub fn assign_at(
+        env_ptr: &EnvPtr,
+        name: &str,
+        val: Value,
+        dist: usize,
+        slot: usize,
+        cursor: Cursor,
+    ) -> EvalResult<()> {
         let ancestor = Self::ancestor(env_ptr.clone(), dist);
-        ancestor.borrow_mut().values.insert(name.to_string(), val);
+        let mut env = ancestor.borrow_mut();
+        let local = env
+            .slots
+            .get_mut(slot)
+            .ok_or_else(|| Self::undefined(name, cursor))?;
+        *local = val;
         Ok(())
     }
 
-    pub fn get_at(env_ptr: &EnvPtr, name: &str, dist: usize, cursor: Cursor) -> EvalResult<Value> {
+    /// Gets a local, `None` if its declaration hasn't run yet.
+    pub fn get_at(env_ptr: &EnvPtr, dist: usize, slot: usize) -> Option<Value> {
         let ancestor = Self::ancestor(env_ptr.clone(), dist);
-        ancestor.borrow().values.get(name).cloned().ok_or_else(|| {
-            RuntimeEvent::error(
-                ErrKind::Name,
-                format!("undefined variable '{}'", name),
-                cursor,
-            )
-        })
+        let env = ancestor.borrow();
+        env.slots.get(slot).cloned()
     }
 
     pub fn ancestor(env_ptr: EnvPtr, dist: usize) -> EnvPtr {
@@ -92,11 +129,21 @@ impl Env {
         current
     }
 
-    /// Clone all bindings in the current scope (no enclosing scopes).
+    /// Clone all hashed bindings in the current env (no enclosing envs).
     pub fn entries(&self) -> Vec<(String, Value)> {
         self.values
             .iter()
+            .flatten()
             .map(|(k, v)| (k.clone(), v.clone()))
             .collect()
     }
+
+    /// NameErr for a variable that isn't defined.
+    pub fn undefined(name: &str, cursor: Cursor) -> RuntimeEvent {
+        RuntimeEvent::error(
+            ErrKind::Name,
+            format!("undefined variable '{}'", name),
+            cursor,
+        )
+    }
 }
@@ -1,62 +1,117 @@
+use std::{cell::RefCell, path::PathBuf, rc::Rc};
+
 use crate::{
     evaluator::{
         Evaluator,
         env::{Env, EnvPtr},
         runtime_err::{EvalResult, RuntimeEvent},
-        value::{Callable, Value},
+        value::{Arity, Callable, Value},
     },
     lexer::cursor::Cursor,
-    parser::stmt::{Stmt, StmtKind},
+    parser::stmt::FnDecl,
 };
 
 #[derive(Debug, Clone)]
 pub struct Function {
-    pub declr: Stmt,
+    /// Declaration shared with every function created from it
+    pub declr: Rc<FnDecl>,
     pub closure: EnvPtr,
     pub bound: bool,
+    /// Script the function was declared in
+    pub file: Rc<PathBuf>,
 }
 
 impl Function {
-    pub fn new(declr: Stmt, closure: EnvPtr, bound: bool) -> Self {
-        if let StmtKind::Fn { .. } = declr.clone().kind {
-            return Self {
-                declr,
-                closure,
-                bound,
-            };
+    pub fn new(declr: Rc<FnDecl>, closure: EnvPtr, bound: bool, file: Rc<PathBuf>) -> Self {
+        Self {
+            declr,
+            closure,
+            bound,
+            file,
         }
-        unreachable!("Non-fn statement passed as declaration to Function::new(declr)");
     }
 
     pub fn bind_method(self, val: Value) -> Function {
         if let Value::ObjInstance(_) = val {
-            if let StmtKind::Fn { name, bound, .. } = self.declr.kind.clone() {
-                let env = Env::enclosed(self.closure.clone());
-                if bound || name == "init" {
-                    env.borrow_mut().define("self".to_string(), val);
-                }
-                return Function::new(self.declr, env, bound);
+            let bound = self.declr.bound;
+            let env = Env::enclosed(self.closure.clone());
+            if bound || self.declr.name == "init" {
+                env.borrow_mut().define("self".to_string(), val);
             }
+            return Function::new(self.declr, env, bound, self.file);
         }
         unreachable!("Non-obj value passed to Function::bind(val)");
     }
-}
 
-impl Callable for Function {
-    fn name(&self) -> &str {
-        if let StmtKind::Fn { name, .. } = &self.declr.kind {
-            return name;
+    /// Runs the function in the script it was declared in, errors leaving it record the call
+    /// stack before the caller's script is restored.
+    fn call_args(
+        &self,
+        evaluator: &mut Evaluator,
+        args: impl Iterator<Item = Option<Value>>,
+    ) -> EvalResult<Value> {
+        let caller_file = std::mem::replace(&mut evaluator.file, self.file.clone());
+        let mut res = self.run(evaluator, args);
+
+        // tail calls run in place of the body that returned them, reusing the frame
+        while let Err(RuntimeEvent::TailCall { callee, args }) = res {
+            let func = callee.as_function().expect("expected a user function");
+            evaluator.replace_frame(func.name());
+            evaluator.file = func.file.clone();
+            res = func.run(evaluator, args.into_iter());
         }
 
-        unreachable!("Non-fn statement passed as declaration to Function::new(declr)");
+        let res = res.map_err(|err| evaluator.trace_err(err));
+        evaluator.file = caller_file;
+        res
     }
 
-    fn arity(&self) -> usize {
-        if let StmtKind::Fn { params, .. } = &self.declr.kind {
-            return params.len();
+    /// Binds the arguments to the parameters and runs the body, skipped arguments (`None`) take
+    /// their default value.
+    fn run(
+        &self,
+        evaluator: &mut Evaluator,
+        mut args: impl Iterator<Item = Option<Value>>,
+    ) -> EvalResult<Value> {
+        let env = Env::enclosed(self.closure.clone());
+
+        for param in &self.declr.params {
+            let val = if param.variadic {
+                Value::List(Rc::new(RefCell::new(args.by_ref().flatten().collect())))
+            } else if let Some(arg) = args.next().flatten() {
+                arg
+            } else if let Some(default) = &param.default {
+                // defaults are evaluated on each call and can refer to earlier parameters
+                evaluator.eval_expr_in(default, env.clone())?
+            } else {
+                Value::Null
+            };
+            env.borrow_mut().define(param.name.clone(), val);
         }
 
-        unreachable!("Non-fn statement passed as declaration to Function::new(declr)");
+        match evaluator.eval_stmt_block(&self.declr.body, env) {
+            Ok(()) => Ok(Value::Null),
+            Err(RuntimeEvent::Return(v)) => Ok(v), // function return
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl Callable for Function {
+    fn name(&self) -> &str {
+        &self.declr.name
+    }
+
+    fn arity(&self) -> Arity {
+        let params = &self.declr.params;
+        let min = params
+            .iter()
+            .filter(|p| p.default.is_none() && !p.variadic)
+            .count();
+        if params.last().is_some_and(|p| p.variadic) {
+            return Arity::new(min, None);
+        }
+        Arity::new(min, Some(params.len()))
     }
 
     fn call(
@@ -65,20 +120,29 @@ impl Callable for Function {
         args: Vec<Value>,
         _cursor: Cursor,
     ) -> EvalResult<Value> {
-        if let StmtKind::Fn { params, body, .. } = &self.declr.kind {
-            let env = Env::enclosed(self.closure.clone());
+        self.call_args(evaluator, args.into_iter().map(Some))
+    }
 
-            for (i, param) in params.iter().enumerate() {
-                env.borrow_mut().define(param.clone(), args[i].clone());
-            }
+    fn as_function(&self) -> Option<&Function> {
+        Some(self)
+    }
 
-            return match evaluator.eval_stmt_block(body, env) {
-                Ok(()) => Ok(Value::Null),
-                Err(RuntimeEvent::Return(v)) => Ok(v), // function return
-                Err(e) => Err(e),
-            };
-        }
+    fn param_names(&self) -> Vec<String> {
+        // rest parameters only collect positional arguments
+        self.declr
+            .params
+            .iter()
+            .filter(|p| !p.variadic)
+            .map(|p| p.name.clone())
+            .collect()
+    }
 
-        unreachable!("Non-fn statement passed as declaration to Function::new(declr)");
+    fn call_named(
+        &self,
+        evaluator: &mut Evaluator,
+        args: Vec<Option<Value>>,
+        _cursor: Cursor,
+    ) -> EvalResult<Value> {
+        self.call_args(evaluator, args.into_iter())
     }
 }
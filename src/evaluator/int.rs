@@ -0,0 +1,453 @@
+use std::{
+    cmp::Ordering,
+    fmt::Display,
+    ops::{Add, BitAnd, BitOr, BitXor, Mul, Neg, Not, Shl, Shr, Sub},
+    rc::Rc,
+};
+
+use num_bigint::{BigInt, ParseBigIntError};
+use num_integer::Integer;
+use num_traits::{FromPrimitive, Num, Pow, Signed, ToPrimitive};
+
+/// An integer of any size. Integers that fit in an i64 are stored inline, operations that
+/// overflow move to a BigInt and results that fit again move back.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Int {
+    Small(i64),
+    /// Always outside the i64 range
+    Big(Rc<BigInt>),
+}
+
+impl Int {
+    pub fn from_big(big: BigInt) -> Self {
+        match big.to_i64() {
+            Some(n) => Int::Small(n),
+            None => Int::Big(Rc::new(big)),
+        }
+    }
+
+    /// The integer part of a finite float.
+    pub fn from_f64(f: f64) -> Option<Self> {
+        if !f.is_finite() {
+            return None;
+        }
+        let f = f.trunc();
+        if f >= i64::MIN as f64 && f < i64::MAX as f64 {
+            return Some(Int::Small(f as i64));
+        }
+        BigInt::from_f64(f).map(Int::from_big)
+    }
+
+    /// Parses an Int literal, which can be prefixed with `0x`, `0b` or `0o` for hex, binary and
+    /// octal digits.
+    pub fn from_literal(s: &str) -> Result<Self, ParseBigIntError> {
+        let (digits, radix) = match s.get(..2) {
+            Some("0x") => (&s[2..], 16),
+            Some("0b") => (&s[2..], 2),
+            Some("0o") => (&s[2..], 8),
+            _ => return s.parse(),
+        };
+        match i64::from_str_radix(digits, radix) {
+            Ok(n) => Ok(Int::Small(n)),
+            Err(_) => BigInt::from_str_radix(digits, radix).map(Int::from_big),
+        }
+    }
+
+    pub fn to_big(&self) -> BigInt {
+        match self {
+            Int::Small(n) => BigInt::from(*n),
+            Int::Big(big) => (**big).clone(),
+        }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            Int::Small(n) => *n as f64,
+            Int::Big(big) => big.to_f64().unwrap_or(f64::NAN),
+        }
+    }
+
+    pub fn to_i64(&self) -> Option<i64> {
+        match self {
+            Int::Small(n) => Some(*n),
+            Int::Big(_) => None,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        matches!(self, Int::Small(0))
+    }
+
+    pub fn is_negative(&self) -> bool {
+        match self {
+            Int::Small(n) => *n < 0,
+            Int::Big(big) => big.is_negative(),
+        }
+    }
+
+    pub fn abs(&self) -> Int {
+        match self {
+            Int::Small(n) => n
+                .checked_abs()
+                .map(Int::Small)
+                .unwrap_or_else(|| Int::from_big(BigInt::from(*n).abs())),
+            Int::Big(big) => Int::from_big(big.abs()),
+        }
+    }
+
+    /// Division rounding towards negative infinity, `None` when dividing by zero.
+    pub fn checked_div_floor(&self, rhs: &Int) -> Option<Int> {
+        if rhs.is_zero() {
+            return None;
+        }
+        if let (Int::Small(a), Int::Small(b)) = (self, rhs)
+            && (*a, *b) != (i64::MIN, -1)
+        {
+            return Some(Int::Small(a.div_floor(b)));
+        }
+        Some(Int::from_big(self.to_big().div_floor(&rhs.to_big())))
+    }
+
+    /// Remainder of the floor division, it has the sign of the divisor. `None` when dividing by
+    /// zero.
+    pub fn checked_mod_floor(&self, rhs: &Int) -> Option<Int> {
+        if rhs.is_zero() {
+            return None;
+        }
+        if let (Int::Small(a), Int::Small(b)) = (self, rhs)
+            && (*a, *b) != (i64::MIN, -1)
+        {
+            return Some(Int::Small(a.mod_floor(b)));
+        }
+        Some(Int::from_big(self.to_big().mod_floor(&rhs.to_big())))
+    }
+
+    pub fn pow(&self, exp: u32) -> Int {
+        if let Int::Small(n) = self
+            && let Some(res) = n.checked_pow(exp)
+        {
+            return Int::Small(res);
+        }
+        Int::from_big(Pow::pow(self.to_big(), exp))
+    }
+}
+
+impl From<i64> for Int {
+    fn from(n: i64) -> Self {
+        Int::Small(n)
+    }
+}
+
+impl From<usize> for Int {
+    fn from(n: usize) -> Self {
+        match i64::try_from(n) {
+            Ok(n) => Int::Small(n),
+            Err(_) => Int::from_big(BigInt::from(n)),
+        }
+    }
+}
+
+macro_rules! int_op {
+    ($trait:ident, $method:ident, $checked:ident) => {
+        impl $trait<&Int> for &Int {
+            type Output = Int;
+
+            fn $method(self, rhs: &Int) -> Int {
+                if let (Int::Small(a), Int::Small(b)) = (self, rhs)
+                    && let Some(n) = a.$checked(*b)
+                {
+                    return Int::Small(n);
+                }
+                Int::from_big(self.to_big().$method(rhs.to_big()))
+            }
+        }
+    };
+}
+
+int_op!(Add, add, checked_add);
+int_op!(Sub, sub, checked_sub);
+int_op!(Mul, mul, checked_mul);
+
+macro_rules! int_bit_op {
+    ($trait:ident, $method:ident) => {
+        impl $trait<&Int> for &Int {
+            type Output = Int;
+
+            fn $method(self, rhs: &Int) -> Int {
+                if let (Int::Small(a), Int::Small(b)) = (self, rhs) {
+                    return Int::Small(a.$method(b));
+                }
+                Int::from_big(self.to_big().$method(rhs.to_big()))
+            }
+        }
+    };
+}
+
+// bitwise operators act as if the integers were in two's complement with infinite sign bits
+int_bit_op!(BitAnd, bitand);
+int_bit_op!(BitOr, bitor);
+int_bit_op!(BitXor, bitxor);
+
+impl Not for &Int {
+    type Output = Int;
+
+    fn not(self) -> Int {
+        match self {
+            Int::Small(n) => Int::Small(!n),
+            Int::Big(big) => Int::from_big(!&**big),
+        }
+    }
+}
+
+impl Shl<u32> for &Int {
+    type Output = Int;
+
+    fn shl(self, rhs: u32) -> Int {
+        if let Int::Small(n) = self
+            && rhs < i64::BITS
+            && (n << rhs) >> rhs == *n
+        {
+            return Int::Small(n << rhs);
+        }
+        Int::from_big(self.to_big() << rhs)
+    }
+}
+
+/// Shifts right rounding towards negative infinity.
+impl Shr<u32> for &Int {
+    type Output = Int;
+
+    fn shr(self, rhs: u32) -> Int {
+        match self {
+            Int::Small(n) => Int::Small(n >> rhs.min(i64::BITS - 1)),
+            Int::Big(big) => Int::from_big(&**big >> rhs),
+        }
+    }
+}
+
+impl Neg for &Int {
+    type Output = Int;
+
+    fn neg(self) -> Int {
+        match self {
+            Int::Small(n) => n
+                .checked_neg()
+                .map(Int::Small)
+                .unwrap_or_else(|| Int::from_big(-BigInt::from(*n))),
+            Int::Big(big) => Int::from_big(-(**big).clone()),
+        }
+    }
+}
+
+impl PartialOrd for Int {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Int {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Int::Small(a), Int::Small(b)) => a.cmp(b),
+            _ => self.to_big().cmp(&other.to_big()),
+        }
+    }
+}
+
+impl Display for Int {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Int::Small(n) => write!(f, "{}", n),
+            Int::Big(big) => write!(f, "{}", big),
+        }
+    }
+}
+
+impl std::str::FromStr for Int {
+    type Err = ParseBigIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse::<i64>() {
+            Ok(n) => Ok(Int::Small(n)),
+            Err(_) => s.parse::<BigInt>().map(Int::from_big),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        evaluator::{
+            range::{Bounds, Range},
+            value::{Value, ValueKey},
+        },
+        lexer::cursor::Cursor,
+    };
+    use ordered_float::OrderedFloat;
+
+    fn big(s: &str) -> Int {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn overflow_promotes_to_big_and_back() {
+        let max = Int::Small(i64::MAX);
+        let one = Int::Small(1);
+        let sum = &max + &one;
+        assert!(matches!(sum, Int::Big(_)));
+        assert_eq!(sum, big("9223372036854775808"));
+        assert_eq!(&sum - &one, Int::Small(i64::MAX));
+
+        let product = &max * &Int::Small(2);
+        assert!(matches!(product, Int::Big(_)));
+        assert_eq!(product.checked_div_floor(&Int::Small(2)), Some(max));
+
+        assert!(matches!(-&Int::Small(i64::MIN), Int::Big(_)));
+        assert!(matches!(Int::Small(i64::MIN).abs(), Int::Big(_)));
+        assert_eq!(-&-&Int::Small(i64::MIN), Int::Small(i64::MIN));
+        assert_eq!(Int::Small(2).pow(64), big("18446744073709551616"));
+    }
+
+    #[test]
+    fn min_floor_div_minus_one() {
+        let min = Int::Small(i64::MIN);
+        let minus_one = Int::Small(-1);
+        assert_eq!(
+            min.checked_div_floor(&minus_one),
+            Some(big("9223372036854775808"))
+        );
+        assert_eq!(
+            Int::Small(-7).checked_div_floor(&Int::Small(2)),
+            Some(Int::Small(-4))
+        );
+        assert_eq!(Int::Small(1).checked_div_floor(&Int::Small(0)), None);
+    }
+
+    #[test]
+    fn mod_has_sign_of_divisor() {
+        let check = |a: i64, b: i64, m: i64| {
+            let (a, b) = (Int::Small(a), Int::Small(b));
+            let rem = a.checked_mod_floor(&b).unwrap();
+            assert_eq!(rem, Int::Small(m));
+            // the remainder completes the floor division
+            let div = a.checked_div_floor(&b).unwrap();
+            assert_eq!(&(&div * &b) + &rem, a);
+        };
+        check(7, 3, 1);
+        check(-7, 3, 2);
+        check(7, -3, -2);
+        check(-7, -3, -1);
+        check(-7, 2, 1);
+        check(i64::MIN, -1, 0);
+        assert_eq!(Int::Small(7).checked_mod_floor(&Int::Small(0)), None);
+
+        let huge = big("-100000000000000000000");
+        assert_eq!(huge.checked_mod_floor(&Int::Small(7)), Some(Int::Small(5)));
+        assert_eq!(
+            Int::Small(-5).checked_mod_floor(&huge),
+            Some(Int::Small(-5))
+        );
+        assert_eq!(
+            Int::Small(5).checked_mod_floor(&huge),
+            Some(&huge + &Int::Small(5))
+        );
+    }
+
+    #[test]
+    fn shifts() {
+        let one = Int::Small(1);
+        assert_eq!(&one << 62, Int::Small(1 << 62));
+        assert_eq!(&one << 63, big("9223372036854775808"));
+        assert_eq!(&one << 64, big("18446744073709551616"));
+        assert_eq!(&Int::Small(-1) << 63, Int::Small(i64::MIN));
+        assert_eq!(&Int::Small(0) << 200, Int::Small(0));
+
+        assert_eq!(&Int::Small(-5) >> 1, Int::Small(-3));
+        assert_eq!(&Int::Small(5) >> 64, Int::Small(0));
+        assert_eq!(&Int::Small(-1) >> 100, Int::Small(-1));
+        assert_eq!(&(&one << 100) >> 100, Int::Small(1));
+    }
+
+    #[test]
+    fn int_equals_whole_num() {
+        let int = Value::Int(Int::Small(3));
+        assert_eq!(int, Value::Num(OrderedFloat(3.0)));
+        assert_ne!(int, Value::Num(OrderedFloat(3.5)));
+
+        // 2^60 + 1 rounds to 2^60 as a Num, so only 2^60 equals it
+        let n = 2f64.powi(60);
+        assert_ne!(
+            Value::Int(&Int::Small(1 << 60) + &Int::Small(1)),
+            Value::Num(OrderedFloat(n))
+        );
+        assert_eq!(Value::Num(OrderedFloat(n)), Value::Int(Int::Small(1 << 60)));
+        assert_ne!(
+            Value::Int(Int::Small(i64::MAX)),
+            Value::Num(OrderedFloat(i64::MAX as f64))
+        );
+        assert_ne!(int, Value::Num(OrderedFloat(f64::NAN)));
+
+        let key = |v: &Value| ValueKey::try_from(v).unwrap();
+        assert_eq!(key(&Value::Num(OrderedFloat(3.0))), key(&int));
+        assert_ne!(key(&Value::Num(OrderedFloat(3.5))), key(&int));
+        assert_eq!(
+            key(&Value::Num(OrderedFloat(1e20))),
+            ValueKey::Int(big("100000000000000000000"))
+        );
+    }
+
+    #[test]
+    fn ranges_above_f64_precision() {
+        let range = |start: &Int, end: &Int, step: i64, inclusive: bool| {
+            let bounds = Bounds::Int {
+                start: start.clone(),
+                end: Some(end.clone()),
+                step: Int::Small(step),
+            };
+            Range::new(bounds, inclusive, Cursor::new()).unwrap()
+        };
+        let int = |n: &Int, offset: i64| Value::Int(n + &Int::Small(offset));
+
+        // 2^53 + 1 is the first Int a Num can't hold
+        for base in [
+            Int::Small(1 << 53),
+            Int::Small(1 << 60),
+            big("1267650600228229401496703205376"),
+        ] {
+            let r = range(&base, &(&base + &Int::Small(3)), 1, false);
+            assert_eq!(r.count(), Some(3));
+            assert_eq!(r.nth_value(1), int(&base, 1));
+            assert!(r.contains(&int(&base, 1)));
+            assert!(!r.contains(&int(&base, 3)));
+            assert!(range(&base, &(&base + &Int::Small(3)), 1, true).contains(&int(&base, 3)));
+
+            let stepped = range(&base, &(&base + &Int::Small(4)), 2, false);
+            assert_eq!(stepped.count(), Some(2));
+            assert!(stepped.contains(&int(&base, 2)));
+            assert!(!stepped.contains(&int(&base, 1)));
+
+            let down = range(&(&base + &Int::Small(3)), &base, -1, false);
+            assert_eq!(down.count(), Some(3));
+            assert_eq!(down.nth_value(2), int(&base, 1));
+            assert!(!down.contains(&int(&base, 0)));
+        }
+    }
+
+    #[test]
+    fn range_steps_count_towards_the_end() {
+        let range = |start: i64, end: i64, step: i64| {
+            let bounds = Bounds::Int {
+                start: Int::Small(start),
+                end: Some(Int::Small(end)),
+                step: Int::Small(step),
+            };
+            Range::new(bounds, false, Cursor::new())
+        };
+        assert_eq!(range(3, 0, -1).unwrap().count(), Some(3));
+        assert_eq!(range(0, 0, -1).unwrap().count(), Some(0));
+        assert!(range(0, 3, -1).is_err());
+        assert!(range(3, 0, 1).is_err());
+        assert!(range(0, 3, 0).is_err());
+    }
+}
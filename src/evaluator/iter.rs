@@ -0,0 +1,134 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    evaluator::{
+        Evaluator,
+        int::Int,
+        object::Instance,
+        range::Range,
+        runtime_err::{ErrKind, EvalResult, RuntimeEvent},
+        string::Str,
+        value::{Value, ValueKey},
+    },
+    lexer::cursor::Cursor,
+};
+
+/// State of a value iterated by a `for` loop, yields the item and index of every iteration.
+#[derive(Debug)]
+pub enum ValueIter {
+    List {
+        list: Rc<RefCell<Vec<Value>>>,
+        len: usize,
+        i: usize,
+    },
+    Str {
+        chars: Vec<char>,
+        i: usize,
+    },
+    /// Ranges are iterated lazily, open-ended ranges only end with a break
+    Range {
+        range: Rc<Range>,
+        i: usize,
+    },
+    /// Keys are yielded as the item and values as the index
+    Dict {
+        entries: std::vec::IntoIter<(ValueKey, Value)>,
+    },
+    /// Instances are iterated by calling `next()` until it returns `Null`
+    Instance {
+        iterator: Rc<RefCell<Instance>>,
+        i: usize,
+    },
+}
+
+impl ValueIter {
+    pub fn new(evaluator: &mut Evaluator, val: Value, cursor: Cursor) -> EvalResult<Self> {
+        match val {
+            Value::List(list) => {
+                let len = list.borrow().len();
+                Ok(ValueIter::List { list, len, i: 0 })
+            }
+            Value::Str(s) => Ok(ValueIter::Str {
+                chars: s.borrow().chars().collect(),
+                i: 0,
+            }),
+            Value::Range(range) => Ok(ValueIter::Range { range, i: 0 }),
+            Value::Dict(dict) => {
+                let entries: Vec<(ValueKey, Value)> = dict
+                    .borrow()
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect();
+                Ok(ValueIter::Dict {
+                    entries: entries.into_iter(),
+                })
+            }
+            Value::ObjInstance(inst) => {
+                // iter() is optional, instances defining only next() are their own iterator
+                match evaluator.call_operator(&inst, "iter", vec![], cursor)? {
+                    Some(Value::ObjInstance(iterator)) => {
+                        Ok(ValueIter::Instance { iterator, i: 0 })
+                    }
+                    Some(other) => Self::new(evaluator, other, cursor),
+                    None => Ok(ValueIter::Instance {
+                        iterator: inst,
+                        i: 0,
+                    }),
+                }
+            }
+            _ => Err(RuntimeEvent::error(
+                ErrKind::Type,
+                format!("value of type {} is not iterable", val.get_type()),
+                cursor,
+            )),
+        }
+    }
+
+    /// The item and index of the next iteration, `None` once the value is exhausted.
+    pub fn next(
+        &mut self,
+        evaluator: &mut Evaluator,
+        cursor: Cursor,
+    ) -> EvalResult<Option<(Value, Value)>> {
+        let num = |i: usize| Value::Int(Int::from(i));
+        let item = match self {
+            ValueIter::List { list, len, i } => {
+                if *i >= *len {
+                    return Ok(None);
+                }
+                let Some(elem) = list.borrow().get(*i).cloned() else {
+                    return Ok(None);
+                };
+                *i += 1;
+                (elem, num(*i - 1))
+            }
+            ValueIter::Str { chars, i } => {
+                let Some(ch) = chars.get(*i) else {
+                    return Ok(None);
+                };
+                *i += 1;
+                (Value::Str(Str::new(ch.to_string())), num(*i - 1))
+            }
+            ValueIter::Range { range, i } => {
+                if range.count().is_some_and(|len| *i >= len) {
+                    return Ok(None);
+                }
+                *i += 1;
+                (range.nth_value(*i - 1), num(*i - 1))
+            }
+            ValueIter::Dict { entries } => match entries.next() {
+                Some((key, val)) => (key.into(), val),
+                None => return Ok(None),
+            },
+            ValueIter::Instance { iterator, i } => {
+                let elem = evaluator.operator_method(iterator, "next", vec![], cursor)?;
+                if let Value::Null = elem {
+                    return Ok(None);
+                }
+                *i += 1;
+                (elem, num(*i - 1))
+            }
+        };
+        Ok(Some(item))
+    }
+}
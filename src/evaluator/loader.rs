@@ -1,6 +1,7 @@
 use std::{
     cell::RefCell,
     collections::{HashMap, HashSet},
+    env,
     path::{Path, PathBuf},
     rc::Rc,
 };
@@ -10,33 +11,125 @@ use crate::{
     evaluator::{
         Evaluator,
         env::EnvPtr,
+        optimizer::Optimizer,
         resolver::Resolver,
         runtime_err::{ErrKind, EvalResult, RuntimeEvent},
+        value::Value,
     },
     lexer::Lexer,
     parser::Parser,
     reporter::Reporter,
     src::Src,
+    vm::Vm,
 };
 
 pub type LoaderPtr = Rc<RefCell<Loader>>;
 
-#[derive(Default)]
+/// A loaded script bound to a name with `use "path" as name`.
+#[derive(Debug)]
+pub struct Module {
+    pub name: String,
+    /// Global environment of the script, shared with the loader cache
+    pub env: EnvPtr,
+}
+
+impl Module {
+    pub fn new(name: String, env: EnvPtr) -> Self {
+        Self { name, env }
+    }
+
+    pub fn get(&self, member: &str, cursor: Cursor) -> EvalResult<Value> {
+        self.env.borrow().get_local(member).ok_or_else(|| {
+            RuntimeEvent::error(
+                ErrKind::Name,
+                format!("module '{}' has no member '{}'", self.name, member),
+                cursor,
+            )
+        })
+    }
+}
+
+/// Scripts of the bundled standard library, importable with `use "std/<name>"`.
+const STD_MODULES: &[(&str, &str)] = &[(
+    "std/collections.qte",
+    include_str!("../../std/collections.qte"),
+)];
+
 pub struct Loader {
     loaded: HashMap<PathBuf, EnvPtr>,
     visiting: HashSet<PathBuf>,
+    /// Sources of the loaded scripts, used to report errors raised in their functions
+    sources: HashMap<PathBuf, Rc<Src>>,
+    /// Directories searched after the caller's directory
+    lib_dirs: Vec<PathBuf>,
+    /// Whether scripts are run on the VM
+    pub vm: bool,
+    /// Whether scripts are optimized before they are run
+    pub optimize: bool,
+}
+
+impl Default for Loader {
+    fn default() -> Self {
+        Self::new(vec![])
+    }
 }
 
 impl Loader {
-    pub fn load(self_ptr: LoaderPtr, file: PathBuf, caller_dir: &Path) -> EvalResult<EnvPtr> {
-        // Resolve path relative to caller and canonicalize for caching/cycle detection.
-        let resolved = if file.is_absolute() {
-            file
+    /// Creates a loader searching `lib_dirs` and then the directories in `QUETITE_PATH`.
+    pub fn new(lib_dirs: Vec<PathBuf>) -> Self {
+        let mut lib_dirs = lib_dirs;
+        if let Some(paths) = env::var_os("QUETITE_PATH") {
+            lib_dirs.extend(env::split_paths(&paths).filter(|p| !p.as_os_str().is_empty()));
+        }
+
+        Self {
+            loaded: HashMap::new(),
+            visiting: HashSet::new(),
+            sources: HashMap::new(),
+            lib_dirs,
+            vm: false,
+            optimize: false,
+        }
+    }
+
+    /// Source of a loaded script, by the path returned by `Loader::resolve`.
+    pub fn source(&self, file: &Path) -> Option<Rc<Src>> {
+        self.sources.get(file).cloned()
+    }
+
+    /// Finds the script a `use` path refers to. Relative paths are looked up in the caller's
+    /// directory, the library directories and finally the bundled standard library. The `.qte`
+    /// extension can be omitted. Returns the canonical path of the script, or its name for
+    /// standard library scripts.
+    pub fn resolve(&self, file: &Path, caller_dir: &Path) -> Option<PathBuf> {
+        let file = if file.extension().is_none() {
+            file.with_extension("qte")
         } else {
-            caller_dir.join(file)
+            file.to_path_buf()
         };
-        let canonical = resolved.canonicalize().map_err(RuntimeEvent::from)?;
 
+        if file.is_absolute() {
+            return file.canonicalize().ok();
+        }
+
+        let found = std::iter::once(caller_dir)
+            .chain(self.lib_dirs.iter().map(PathBuf::as_path))
+            .map(|dir| dir.join(&file))
+            .find(|path| path.is_file());
+        if let Some(path) = found {
+            return path.canonicalize().ok();
+        }
+
+        let name = file.to_str()?.replace('\\', "/");
+        STD_MODULES
+            .iter()
+            .find(|(std_name, _)| *std_name == name)
+            .map(|(std_name, _)| PathBuf::from(std_name))
+    }
+
+    /// Loads and evaluates a script returned by `Loader::resolve`, each script is only
+    /// evaluated once.
+    pub fn load(self_ptr: LoaderPtr, canonical: PathBuf) -> EvalResult<EnvPtr> {
         // Fast path: already loaded.
         if let Some(env) = self_ptr.borrow().loaded.get(&canonical) {
             return Ok(env.clone());
@@ -55,8 +148,20 @@ impl Loader {
         self_ptr.borrow_mut().visiting.insert(canonical.clone());
 
         // Run the full pipeline (lex → parse → resolve → eval).
-        let result = (|| -> EvalResult<EnvPtr> {
-            let mut src = Src::new(canonical.clone());
+        let result = (|| -> EvalResult<(EnvPtr, Rc<Src>)> {
+            // standard library scripts are the only ones with relative paths
+            let mut src = if canonical.is_relative() {
+                let text = STD_MODULES
+                    .iter()
+                    .find(|(name, _)| Path::new(name) == canonical)
+                    .map(|(_, text)| text.to_string())
+                    .expect("expected a standard library script");
+                let mut src = Src::from_text(text);
+                src.file = canonical.clone();
+                src
+            } else {
+                Src::new(canonical.clone())
+            };
 
             let mut lexer = Lexer::new(src.text.clone());
             let lex_out = lexer.tokenize();
@@ -121,19 +226,29 @@ impl Loader {
                 }
             };
 
+            if self_ptr.borrow().optimize {
+                src.ast = src.ast.map(Optimizer::optimize);
+            }
+
+            let src = Rc::new(src);
             let mut evaluator = Evaluator::with_loader(&src, self_ptr.clone());
-            evaluator.eval()?;
+            let vm = self_ptr.borrow().vm;
+            if vm {
+                Vm::eval(&mut evaluator)?;
+            } else {
+                evaluator.eval()?;
+            }
 
-            Ok(evaluator.globals.clone())
+            Ok((evaluator.globals.clone(), src.clone()))
         })();
 
         // Unmark visiting and cache on success.
         let mut loader = self_ptr.borrow_mut();
         loader.visiting.remove(&canonical);
-        if let Ok(ref env) = result {
-            loader.loaded.insert(canonical, env.clone());
-        }
+        let (env, src) = result?;
+        loader.loaded.insert(canonical.clone(), env.clone());
+        loader.sources.insert(canonical, src);
 
-        result
+        Ok(env)
     }
 }
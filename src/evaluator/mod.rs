@@ -1,11 +1,17 @@
+pub mod cache;
 pub mod env;
 pub mod function;
+pub mod int;
+pub mod iter;
 pub mod loader;
 pub mod natives;
 pub mod object;
+pub mod optimizer;
 pub mod prototype;
+pub mod range;
 pub mod resolver;
 pub mod runtime_err;
+pub mod string;
 pub mod value;
 
 use std::{
@@ -20,36 +26,56 @@ use ordered_float::OrderedFloat;
 
 use crate::{
     evaluator::{
+        cache::PropCache,
         env::{Env, EnvPtr},
         function::Function,
-        loader::{Loader, LoaderPtr},
+        int::Int,
+        iter::ValueIter,
+        loader::{Loader, LoaderPtr, Module},
         natives::Natives,
         object::{Instance, Method, Object},
         prototype::{BoundMethod, ValuePrototypes},
-        runtime_err::{ErrKind, EvalResult, RuntimeErr, RuntimeEvent},
+        range::{Bounds, Range},
+        runtime_err::{ErrKind, EvalResult, Frame, RuntimeErr, RuntimeEvent, Trace},
+        string::Str,
         value::{Callable, Value, ValueKey},
     },
-    lexer::token::KeywordKind,
+    lexer::{cursor::Cursor, token::KeywordKind},
     parser::{
-        expr::{AssignOp, BinaryOp, Expr, ExprKind, LiteralType, LogicalOp, UnaryOp},
-        stmt::{Stmt, StmtKind},
+        expr::{AssignOp, BinaryOp, Expr, ExprKind, LogicalOp, UnaryOp},
+        pattern::{Pattern, PatternKind},
+        stmt::{CatchClause, Stmt, StmtKind},
     },
     reporter::Reporter,
     src::Src,
 };
 
+/// A named argument of a call: its name, location and value.
+pub type NamedValue = (String, Cursor, Value);
+
+/// Calls that can be nested before a `RecursionErr` is raised, unless set otherwise.
+pub const DEFAULT_RECURSION_LIMIT: usize = 10_000;
+
 pub struct Evaluator<'a> {
     pub src: &'a Src,
-    ast: Vec<Stmt>,
     globals: EnvPtr,
     pub env: EnvPtr,
     prototypes: ValuePrototypes,
     pub loader: LoaderPtr,
+    /// Script of the code being run, functions switch it to the script they were declared in
+    pub file: Rc<PathBuf>,
+    /// Calls being run, outermost first
+    call_stack: Vec<Frame>,
+    /// Maximum length of the call stack, VMs started by calls count against it too
+    pub recursion_limit: usize,
+    /// VMs running closures called from natives and operators, nested on the native stack
+    vm_calls: usize,
 }
 
 impl<'a> Evaluator<'a> {
     pub fn new(src: &'a Src) -> Self {
-        let globals = Natives::get_natives();
+        // keep the natives in their own scope so a script's globals can be exported on their own
+        let globals = Env::globals(Natives::get_natives());
 
         Self::with_state(src, globals, Rc::new(RefCell::new(Loader::default())))
     }
@@ -61,33 +87,25 @@ impl<'a> Evaluator<'a> {
     }
 
     pub fn with_state(src: &'a Src, globals: EnvPtr, loader: LoaderPtr) -> Self {
-        let ast = src.ast.clone().expect("expected ast");
-
         Self {
             src,
-            ast,
             env: globals.clone(),
             globals,
             prototypes: ValuePrototypes::new(),
             loader,
+            file: Rc::new(src.file.clone()),
+            call_stack: Vec::new(),
+            recursion_limit: DEFAULT_RECURSION_LIMIT,
+            vm_calls: 0,
         }
     }
 
     pub fn eval(&mut self) -> EvalResult<()> {
-        for stmt in self.ast.clone().iter() {
+        for stmt in self.src.ast.iter().flatten() {
             match self.eval_stmt(stmt) {
                 Ok(_) => {}
                 Err(err) => {
-                    if let RuntimeEvent::Err(RuntimeErr {
-                        kind, msg, cursor, ..
-                    }) = &err
-                    {
-                        Reporter::error_at(msg, kind.to_string(), self.src, *cursor);
-                    }
-                    if let RuntimeEvent::UserErr { val, cursor } = &err {
-                        let msg = format!("user error: {}", val);
-                        Reporter::error_at(msg.as_str(), "UserErr".into(), self.src, *cursor);
-                    }
+                    self.report_err(&err);
                     return Err(err);
                 }
             }
@@ -95,40 +113,151 @@ impl<'a> Evaluator<'a> {
         Ok(())
     }
 
-    /// Evaluate and return the last expression value (used by the REPL).
-    pub fn eval_with_result(&mut self) -> EvalResult<Option<Value>> {
-        let mut last_expr: Option<Value> = None;
+    /// Evaluate and return the display string of the last expression value (used by the REPL).
+    pub fn eval_with_result(&mut self) -> EvalResult<Option<String>> {
+        let mut last_expr: Option<(Value, Cursor)> = None;
 
-        for stmt in self.ast.clone().iter() {
+        for stmt in self.src.ast.iter().flatten() {
             let res = match &stmt.kind {
                 StmtKind::Expr(expr) => self.eval_expr(expr).map(|v| {
                     if let ExprKind::Call { .. } = expr.kind {
                         if v != Value::Null {
-                            last_expr = Some(v);
+                            last_expr = Some((v, expr.cursor));
                         }
                     } else {
-                        last_expr = Some(v);
+                        last_expr = Some((v, expr.cursor));
                     }
                 }),
                 _ => self.eval_stmt(stmt),
             };
 
             if let Err(err) = res {
-                if let RuntimeEvent::Err(RuntimeErr {
-                    kind, msg, cursor, ..
-                }) = &err
-                {
-                    Reporter::error_at(msg, kind.to_string(), self.src, *cursor);
-                }
-                if let RuntimeEvent::UserErr { val, cursor } = &err {
-                    let msg = format!("user error: {}", val);
-                    Reporter::error_at(msg.as_str(), "UserErr".into(), self.src, *cursor);
-                }
+                self.report_err(&err);
                 return Err(err);
             }
         }
 
-        Ok(last_expr)
+        match last_expr {
+            Some((val, cursor)) => match self.stringify(&val, cursor) {
+                Ok(s) => Ok(Some(s)),
+                Err(err) => {
+                    self.report_err(&err);
+                    Err(err)
+                }
+            },
+            None => Ok(None),
+        }
+    }
+
+    pub(crate) fn report_err(&self, err: &RuntimeEvent) {
+        let (kind, msg, cursor) = match err {
+            RuntimeEvent::Err(RuntimeErr {
+                kind, msg, cursor, ..
+            }) => (kind.to_string(), msg.clone(), *cursor),
+            RuntimeEvent::UserErr { val, cursor, .. } => (
+                "UserErr".to_string(),
+                format!("user error: {}", val),
+                *cursor,
+            ),
+            _ => return,
+        };
+
+        let Some(trace) = err.trace() else {
+            Reporter::error_at(&msg, kind, self.src, cursor);
+            return;
+        };
+
+        // errors raised in a script loaded with `use` are shown in that script
+        let loaded = if trace.file.as_path() == self.src.file {
+            None
+        } else {
+            self.loader.borrow().source(&trace.file)
+        };
+
+        let entries: Vec<_> = trace
+            .entries(cursor)
+            .into_iter()
+            .map(|entry| {
+                let line = if entry.file.as_path() == self.src.file {
+                    self.src.lines.get(entry.cursor.line).cloned()
+                } else {
+                    self.loader
+                        .borrow()
+                        .source(&entry.file)
+                        .and_then(|src| src.lines.get(entry.cursor.line).cloned())
+                };
+                (
+                    entry.name,
+                    entry.file.display().to_string(),
+                    entry.cursor,
+                    line,
+                )
+            })
+            .collect();
+        Reporter::traceback(&entries);
+        Reporter::error_at(&msg, kind, loaded.as_deref().unwrap_or(self.src), cursor);
+    }
+
+    /// Pushes a frame for a call made at `cursor` in the current script, errors if the call
+    /// stack is already at the recursion limit.
+    pub(crate) fn push_frame(&mut self, name: &str, cursor: Cursor) -> EvalResult<()> {
+        self.check_depth(cursor)?;
+        self.call_stack.push(Frame {
+            name: name.to_string(),
+            file: self.file.clone(),
+            cursor,
+        });
+        Ok(())
+    }
+
+    /// Counts a VM started to run a closure called from outside of one. It takes up more native
+    /// stack than a call, so it counts as another level of recursion until `leave_vm`.
+    pub(crate) fn enter_vm(&mut self, cursor: Cursor) -> EvalResult<()> {
+        self.check_depth(cursor)?;
+        self.vm_calls += 1;
+        Ok(())
+    }
+
+    pub(crate) fn leave_vm(&mut self) {
+        self.vm_calls -= 1;
+    }
+
+    fn check_depth(&self, cursor: Cursor) -> EvalResult<()> {
+        if self.call_stack.len() + self.vm_calls >= self.recursion_limit {
+            let err = RuntimeEvent::error(
+                ErrKind::Recursion,
+                format!(
+                    "maximum recursion depth of {} exceeded",
+                    self.recursion_limit
+                ),
+                cursor,
+            );
+            return Err(self.trace_err(err));
+        }
+        Ok(())
+    }
+
+    /// Hands the innermost frame over to a tail called function, the call site stays the one
+    /// of the replaced call.
+    pub(crate) fn replace_frame(&mut self, name: &str) {
+        if let Some(frame) = self.call_stack.last_mut() {
+            frame.name = name.to_string();
+        }
+    }
+
+    /// Pops the frame of a finished call, errors leaving it record the call stack first.
+    pub(crate) fn pop_frame<T>(&mut self, res: EvalResult<T>) -> EvalResult<T> {
+        let res = res.map_err(|err| self.trace_err(err));
+        self.call_stack.pop();
+        res
+    }
+
+    /// Records the current call stack and script on errors that don't have a trace yet.
+    pub fn trace_err(&self, err: RuntimeEvent) -> RuntimeEvent {
+        err.with_trace(|| Trace {
+            frames: self.call_stack.clone(),
+            file: self.file.clone(),
+        })
     }
 
     // Statement functions
@@ -137,8 +266,8 @@ impl<'a> Evaluator<'a> {
         match &stmt.kind {
             StmtKind::Expr(_) => self.eval_stmt_expr(stmt),
             StmtKind::Throw(_) => self.eval_stmt_throw(stmt),
-            StmtKind::Use(_) => self.eval_stmt_use(stmt),
-            StmtKind::Return(_) => self.eval_stmt_return(stmt),
+            StmtKind::Use { .. } => self.eval_stmt_use(stmt),
+            StmtKind::Return { .. } => self.eval_stmt_return(stmt),
             StmtKind::Break => self.eval_stmt_break(stmt),
             StmtKind::Continue => self.eval_stmt_continue(stmt),
             StmtKind::Var { .. } => self.eval_stmt_var(stmt),
@@ -162,40 +291,82 @@ impl<'a> Evaluator<'a> {
     }
 
     fn eval_stmt_use(&mut self, stmt: &Stmt) -> EvalResult<()> {
-        if let StmtKind::Use(expr) = &stmt.kind {
-            let val = self.eval_expr(expr)?;
-            let path_rc = val.check_str(stmt.cursor, Some("use path".into()))?;
-            let path_str = path_rc.borrow().clone();
-
-            // Resolve relative to current source file.
-            let caller_dir = self.src.file.parent().unwrap_or_else(|| Path::new("."));
-
-            match Loader::load(self.loader.clone(), PathBuf::from(path_str), caller_dir) {
-                Ok(env) => {
-                    // Merge imported globals into our globals.
-                    for (name, value) in env.borrow().entries() {
-                        self.globals.borrow_mut().define(name, value);
-                    }
+        if let StmtKind::Use { path, alias, names } = &stmt.kind {
+            let val = self.eval_expr(path)?;
+            let (path_str, env) = self.load_use(&val, stmt.cursor)?;
+
+            // Bind the whole module to a single name.
+            if let Some(alias) = alias {
+                let module = Module::new(alias.clone(), env);
+                self.env
+                    .borrow_mut()
+                    .define(alias.clone(), Value::Module(Rc::new(module)));
+                return Ok(());
+            }
 
-                    return Ok(());
-                }
-                Err(_) => {
-                    return Err(RuntimeEvent::error(
-                        ErrKind::IO,
-                        "failed to load file".into(),
-                        stmt.cursor,
-                    ));
+            // Bind only the selected names.
+            if let Some(names) = names {
+                let module = Module::new(path_str, env);
+                for name in names {
+                    let member = module.get(name, stmt.cursor)?;
+                    self.env.borrow_mut().define(name.clone(), member);
                 }
+                return Ok(());
+            }
+
+            // Merge imported globals into our globals.
+            for (name, value) in env.borrow().entries() {
+                self.globals.borrow_mut().define(name, value);
             }
+            return Ok(());
+        }
+        unreachable!("Non-use statement passed to Evaluator::eval_stmt_use");
+    }
+
+    /// Loads the script a `use` path refers to, returns the path as written along with the
+    /// global environment of the script.
+    pub(crate) fn load_use(
+        &mut self,
+        path: &Value,
+        cursor: Cursor,
+    ) -> EvalResult<(String, EnvPtr)> {
+        let path_rc = path.check_str(cursor, Some("use path".into()))?;
+        let path_str = path_rc.borrow().to_string();
+
+        // Resolve relative to current source file.
+        let caller_dir = self.src.file.parent().unwrap_or_else(|| Path::new("."));
+
+        let Some(file) = self
+            .loader
+            .borrow()
+            .resolve(Path::new(&path_str), caller_dir)
+        else {
+            return Err(RuntimeEvent::error(
+                ErrKind::IO,
+                format!("couldn't find script '{}' in the search path", path_str),
+                cursor,
+            ));
+        };
+
+        match Loader::load(self.loader.clone(), file) {
+            Ok(env) => Ok((path_str, env)),
+            Err(_) => Err(RuntimeEvent::error(
+                ErrKind::IO,
+                "failed to load file".into(),
+                cursor,
+            )),
         }
-        unreachable!("Non-continue statement passed to Evaluator::eval_stmt_continue");
     }
 
     fn eval_stmt_return(&mut self, stmt: &Stmt) -> EvalResult<()> {
-        if let StmtKind::Return(expr) = &stmt.kind {
+        if let StmtKind::Return { val: expr, tail } = &stmt.kind {
             let mut val = Value::Null;
             if let Some(expr) = expr {
-                val = self.eval_expr(expr)?;
+                val = if *tail {
+                    self.eval_call(expr, true)?
+                } else {
+                    self.eval_expr(expr)?
+                };
             }
             return Err(RuntimeEvent::Return(val));
         }
@@ -242,10 +413,34 @@ impl<'a> Evaluator<'a> {
         {
             let val = self.eval_expr(val)?;
 
-            for (e, s) in arms.iter() {
-                let arm_val = self.eval_expr(e)?;
-                if val.is_equal(&arm_val) {
-                    return self.eval_stmt(s);
+            for arm in arms.iter() {
+                let mut bindings: Vec<(usize, Value)> = Vec::new();
+                if !self.match_pattern(&arm.pattern, &val, &mut bindings)? {
+                    continue;
+                }
+
+                // every name of the pattern is defined so the arm env matches the resolver scope
+                let mut slots = vec![Value::Null; arm.binds];
+                for (slot, val) in bindings {
+                    slots[slot] = val;
+                }
+                let arm_env = Env::with_slots(self.env.clone(), slots);
+
+                let prev = self.env.clone();
+                self.env = arm_env;
+                let result = (|| -> EvalResult<bool> {
+                    if let Some(guard) = &arm.guard
+                        && !self.eval_expr(guard)?.is_truthy()
+                    {
+                        return Ok(false);
+                    }
+                    self.eval_stmt(&arm.body)?;
+                    Ok(true)
+                })();
+                self.env = prev;
+
+                if result? {
+                    return Ok(());
                 }
             }
 
@@ -257,6 +452,110 @@ impl<'a> Evaluator<'a> {
         unreachable!("Non-match statement passed to Evaluator::eval_stmt_match");
     }
 
+    /// Matches a value against a pattern, collecting the values of bound names in `bindings` by
+    /// their slot.
+    fn match_pattern(
+        &mut self,
+        pattern: &Pattern,
+        val: &Value,
+        bindings: &mut Vec<(usize, Value)>,
+    ) -> EvalResult<bool> {
+        match &pattern.kind {
+            PatternKind::Wildcard => Ok(true),
+            PatternKind::Bind(_) => {
+                bindings.push((pattern.slot.get(), val.clone()));
+                Ok(true)
+            }
+            PatternKind::Value(expr) => {
+                let pattern_val = self.eval_expr(expr)?;
+                self.value_matches(val, &pattern_val, expr.cursor)
+            }
+            PatternKind::Type { name, bind } => {
+                if !val.is_type(name) {
+                    return Ok(false);
+                }
+                if bind.is_some() {
+                    bindings.push((pattern.slot.get(), val.clone()));
+                }
+                Ok(true)
+            }
+            PatternKind::List { items, rest } => {
+                let Value::List(list) = val else {
+                    return Ok(false);
+                };
+                let list = list.borrow().clone();
+                if list.len() < items.len() || (rest.is_none() && list.len() != items.len()) {
+                    return Ok(false);
+                }
+
+                for (item, elem) in items.iter().zip(list.iter()) {
+                    if !self.match_pattern(item, elem, bindings)? {
+                        return Ok(false);
+                    }
+                }
+                if let Some(rest) = rest {
+                    let rest_val = Value::List(Rc::new(RefCell::new(list[items.len()..].to_vec())));
+                    return self.match_pattern(rest, &rest_val, bindings);
+                }
+                Ok(true)
+            }
+            PatternKind::Dict(entries) => {
+                let Value::Dict(dict) = val else {
+                    return Ok(false);
+                };
+
+                for (key, value_pattern) in entries {
+                    let key_val = self.eval_expr(key)?;
+                    let key = Self::pattern_key(&key_val, key.cursor)?;
+                    let entry = dict.borrow().get(&key).cloned();
+                    match entry {
+                        Some(entry) => {
+                            if !self.match_pattern(value_pattern, &entry, bindings)? {
+                                return Ok(false);
+                            }
+                        }
+                        None => return Ok(false),
+                    }
+                }
+                Ok(true)
+            }
+            PatternKind::Or(alts) => {
+                for alt in alts {
+                    let mark = bindings.len();
+                    if self.match_pattern(alt, val, bindings)? {
+                        return Ok(true);
+                    }
+                    bindings.truncate(mark);
+                }
+                Ok(false)
+            }
+        }
+    }
+
+    /// Whether a value matches the value of a pattern, range patterns match the numbers they
+    /// contain.
+    pub(crate) fn value_matches(
+        &mut self,
+        val: &Value,
+        pattern_val: &Value,
+        cursor: Cursor,
+    ) -> EvalResult<bool> {
+        if let (Value::Range(range), Value::Int(_) | Value::Num(_)) = (pattern_val, val) {
+            return Ok(range.contains(val));
+        }
+        self.values_equal(val, pattern_val, cursor)
+    }
+
+    pub(crate) fn pattern_key(key: &Value, cursor: Cursor) -> EvalResult<ValueKey> {
+        ValueKey::try_from(key).map_err(|_| {
+            RuntimeEvent::error(
+                ErrKind::Type,
+                "dict pattern key must be Null, Bool, Int, Num or Str".into(),
+                cursor,
+            )
+        })
+    }
+
     fn eval_stmt_for(&mut self, stmt: &Stmt) -> EvalResult<()> {
         if let StmtKind::For {
             item,
@@ -266,64 +565,51 @@ impl<'a> Evaluator<'a> {
         } = &stmt.kind
         {
             let iter = self.eval_expr(&iter)?;
+            return self.eval_for_iter(iter, item, index, body, stmt.cursor);
+        }
+        unreachable!("Non-for statement passed to Evaluator::eval_stmt_for");
+    }
 
-            match iter {
-                Value::List(rc_list) => {
-                    let len = rc_list.borrow().len();
-
-                    for i in 0..len {
-                        let elem = rc_list.borrow()[i].clone();
-
-                        let loop_env = Env::enclosed(self.env.clone());
-                        loop_env.borrow_mut().define(item.clone(), elem);
-
-                        if let Some(idx_name) = index {
-                            loop_env
-                                .borrow_mut()
-                                .define(idx_name.clone(), Value::Num(OrderedFloat(i as f64)));
-                        }
-
-                        match self.eval_stmt_block(body, loop_env) {
-                            Ok(_) => {}
-                            Err(err) if err.is_continue() => continue,
-                            Err(err) if err.is_break() => break,
-                            Err(err) => return Err(err),
-                        }
-                    }
-                }
-                Value::Str(rc_str) => {
-                    let chars: Vec<char> = rc_str.borrow().chars().collect();
-                    for (i, ch) in chars.into_iter().enumerate() {
-                        let loop_env = Env::enclosed(self.env.clone());
-                        loop_env.borrow_mut().define(
-                            item.clone(),
-                            Value::Str(Rc::new(RefCell::new(ch.to_string()))),
-                        );
-                        if let Some(idx_name) = index {
-                            loop_env
-                                .borrow_mut()
-                                .define(idx_name.clone(), Value::Num(OrderedFloat(i as f64)));
-                        }
-                        match self.eval_stmt_block(body, loop_env) {
-                            Ok(_) => {}
-                            Err(err) if err.is_continue() => continue,
-                            Err(err) if err.is_break() => break,
-                            Err(err) => return Err(err),
-                        }
-                    }
-                }
-                _ => {
-                    return Err(RuntimeEvent::error(
-                        ErrKind::Type,
-                        "only List and Str values are iterable".into(),
-                        stmt.cursor,
-                    ));
-                }
+    /// Runs a for loop body for every item yielded by an iterable value.
+    fn eval_for_iter(
+        &mut self,
+        iter: Value,
+        item: &str,
+        index: &Option<String>,
+        body: &Stmt,
+        cursor: Cursor,
+    ) -> EvalResult<()> {
+        let mut iter = ValueIter::new(self, iter, cursor)?;
+        while let Some((elem, idx)) = iter.next(self, cursor)? {
+            if !self.eval_for_body(item, index, elem, idx, body)? {
+                break;
             }
+        }
+        Ok(())
+    }
 
-            return Ok(());
+    /// Runs a single for loop iteration, returns false if the loop was broken out of.
+    fn eval_for_body(
+        &mut self,
+        item: &str,
+        index: &Option<String>,
+        item_val: Value,
+        index_val: Value,
+        body: &Stmt,
+    ) -> EvalResult<bool> {
+        let loop_env = Env::enclosed(self.env.clone());
+        loop_env.borrow_mut().define(item.to_string(), item_val);
+
+        if let Some(idx_name) = index {
+            loop_env.borrow_mut().define(idx_name.clone(), index_val);
+        }
+
+        match self.eval_stmt_block(body, loop_env) {
+            Ok(_) => Ok(true),
+            Err(err) if err.is_continue() => Ok(true),
+            Err(err) if err.is_break() => Ok(false),
+            Err(err) => Err(err),
         }
-        unreachable!("Non-if statement passed to Evaluator::eval_stmt_if");
     }
 
     fn eval_stmt_while(&mut self, stmt: &Stmt) -> EvalResult<()> {
@@ -364,47 +650,15 @@ impl<'a> Evaluator<'a> {
     fn eval_stmt_try(&mut self, stmt: &Stmt) -> EvalResult<()> {
         if let StmtKind::Try {
             body,
-            err_kind,
-            err_val,
-            catch,
+            catches,
             ensure,
         } = &stmt.kind
         {
             let out = match self.eval_stmt(body) {
-                Err(e) => match e {
-                    RuntimeEvent::UserErr { val, .. } => {
-                        let catch_env = Env::enclosed(self.env.clone());
-                        if let Some(kind) = err_kind {
-                            catch_env.borrow_mut().define(
-                                kind.clone(),
-                                Value::Str(Rc::new(RefCell::new("UserErr".into()))),
-                            );
-                        }
-                        if let Some(eval) = err_val {
-                            catch_env.borrow_mut().define(eval.clone(), val);
-                        }
-
-                        self.eval_stmt_block(catch, catch_env)
-                    }
-                    RuntimeEvent::Err(err) => {
-                        let catch_env = Env::enclosed(self.env.clone());
-                        if let Some(kind) = err_kind {
-                            catch_env.borrow_mut().define(
-                                kind.clone(),
-                                Value::Str(Rc::new(RefCell::new("RuntimeErr".into()))),
-                            );
-                        }
-                        if let Some(eval) = err_val {
-                            catch_env
-                                .borrow_mut()
-                                .define(eval.clone(), Value::Str(Rc::new(RefCell::new(err.msg))));
-                        }
-
-                        self.eval_stmt_block(catch, catch_env)
-                    }
-                    other => Err(other),
-                },
-                Ok(()) => Ok(()),
+                Err(err @ (RuntimeEvent::Err(_) | RuntimeEvent::UserErr { .. })) => {
+                    self.eval_catch(catches, err)
+                }
+                other => other,
             };
 
             if let Some(ensure_body) = ensure {
@@ -418,6 +672,130 @@ impl<'a> Evaluator<'a> {
         unreachable!("Non-try statement passed to Evaluator::eval_stmt_try");
     }
 
+    /// Runs the first catch clause matching the error, unmatched errors are rethrown.
+    fn eval_catch(&mut self, catches: &[CatchClause], err: RuntimeEvent) -> EvalResult<()> {
+        for clause in catches {
+            let Some(by_obj) = Self::catch_matches(&clause.kinds, &err) else {
+                continue;
+            };
+
+            let catch_env = Env::enclosed(self.env.clone());
+            if let Some(name) = &clause.err {
+                let val = self.caught_err(&err, by_obj)?;
+                catch_env.borrow_mut().define(name.clone(), val);
+            }
+            if let Some(name) = &clause.val {
+                catch_env
+                    .borrow_mut()
+                    .define(name.clone(), Self::caught_val(&err));
+            }
+
+            return self.eval_stmt_block(&clause.body, catch_env);
+        }
+
+        Err(err)
+    }
+
+    /// Value bound to the error name of a catch clause, the thrown instance itself if it was
+    /// caught by its object name.
+    pub(crate) fn caught_err(&mut self, err: &RuntimeEvent, by_obj: bool) -> EvalResult<Value> {
+        match err {
+            RuntimeEvent::UserErr { val, .. } if by_obj => Ok(val.clone()),
+            _ => self.error_value(err),
+        }
+    }
+
+    /// Value bound to the value name of a catch clause, the message for internal errors.
+    pub(crate) fn caught_val(err: &RuntimeEvent) -> Value {
+        match err {
+            RuntimeEvent::UserErr { val, .. } => val.clone(),
+            RuntimeEvent::Err(e) => Value::Str(Str::new(e.msg.clone())),
+            _ => unreachable!("only errors are caught"),
+        }
+    }
+
+    /// Whether a catch clause with the given kinds catches the error, `Some(true)` if it was
+    /// caught by the object name of a thrown instance.
+    pub(crate) fn catch_matches(kinds: &[String], err: &RuntimeEvent) -> Option<bool> {
+        if kinds.is_empty() {
+            return Some(false);
+        }
+        for kind in kinds {
+            match err {
+                RuntimeEvent::Err(e) if e.kind.to_string() == *kind => return Some(false),
+                RuntimeEvent::UserErr { .. } if kind == "UserErr" => return Some(false),
+                RuntimeEvent::UserErr { val, .. } if val.is_type(kind) => return Some(true),
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Builds the structured `Error` instance bound by catch clauses.
+    pub(crate) fn error_value(&mut self, err: &RuntimeEvent) -> EvalResult<Value> {
+        let (kind, msg, val, cursor, note) = match err {
+            RuntimeEvent::Err(e) => {
+                let msg = Value::Str(Str::new(e.msg.clone()));
+                (
+                    e.kind.to_string(),
+                    msg.clone(),
+                    msg,
+                    e.cursor,
+                    e.note.clone(),
+                )
+            }
+            RuntimeEvent::UserErr { val, cursor, .. } => {
+                let msg = self.stringify(val, *cursor)?;
+                let msg = Value::Str(Str::new(msg));
+                ("UserErr".to_string(), msg, val.clone(), *cursor, None)
+            }
+            _ => unreachable!("only errors have error values"),
+        };
+
+        // errors raised without leaving a call happened in the current script
+        let trace = match err.trace() {
+            Some(trace) => trace.clone(),
+            None => Trace {
+                frames: self.call_stack.clone(),
+                file: self.file.clone(),
+            },
+        };
+
+        let str_val = |s: String| Value::Str(Str::new(s));
+        let num_val = |n: usize| Value::Int(Int::from(n));
+        let frames = trace
+            .entries(cursor)
+            .into_iter()
+            .map(|entry| {
+                let frame = HashMap::from([
+                    (ValueKey::Str("name".into()), str_val(entry.name)),
+                    (
+                        ValueKey::Str("file".into()),
+                        str_val(entry.file.to_string_lossy().to_string()),
+                    ),
+                    (ValueKey::Str("line".into()), num_val(entry.cursor.line + 1)),
+                    (ValueKey::Str("col".into()), num_val(entry.cursor.col)),
+                ]);
+                Value::Dict(Rc::new(RefCell::new(frame)))
+            })
+            .collect();
+
+        let mut inst = Instance::new(Natives::error_obj());
+        inst.set("kind".into(), str_val(kind));
+        inst.set("msg".into(), msg);
+        inst.set("val".into(), val);
+        inst.set("line".into(), num_val(cursor.line + 1));
+        inst.set("col".into(), num_val(cursor.col));
+        inst.set(
+            "file".into(),
+            str_val(trace.file.to_string_lossy().to_string()),
+        );
+        inst.set("note".into(), note.map_or(Value::Null, str_val));
+        inst.set("trace".into(), Value::List(Rc::new(RefCell::new(frames))));
+
+        Ok(Value::ObjInstance(Rc::new(RefCell::new(inst))))
+    }
+
     fn eval_stmt_expr(&mut self, stmt: &Stmt) -> EvalResult<()> {
         if let StmtKind::Expr(expr) = &stmt.kind {
             self.eval_expr(expr)?;
@@ -439,40 +817,87 @@ impl<'a> Evaluator<'a> {
     }
 
     fn eval_stmt_fn(&mut self, stmt: &Stmt) -> EvalResult<()> {
-        if let StmtKind::Fn { name, bound, .. } = &stmt.kind {
+        if let StmtKind::Fn(declr) = &stmt.kind {
             let func = Value::Callable(Rc::new(Function::new(
-                stmt.clone(),
+                declr.clone(),
                 self.env.clone(),
-                *bound,
+                declr.bound,
+                self.file.clone(),
             )));
-            self.env.borrow_mut().define(name.clone(), func);
+            self.env.borrow_mut().define(declr.name.clone(), func);
             return Ok(());
         }
         unreachable!("Non-fn statement passed to Evaluator::eval_stmt_fn");
     }
 
     fn eval_stmt_obj(&mut self, stmt: &Stmt) -> EvalResult<()> {
-        if let StmtKind::Obj { name, methods } = &stmt.kind {
-            self.env.borrow_mut().define(name.clone(), Value::Null);
+        if let StmtKind::Obj {
+            name,
+            parent,
+            methods,
+        } = &stmt.kind
+        {
+            let parent_obj = match parent {
+                Some(expr) => {
+                    let val = self.eval_expr(expr)?;
+                    Some(Self::parent_obj(name, val, expr.cursor)?)
+                }
+                None => None,
+            };
+
+            // Methods of child objects close over an extra scope that holds `super`.
+            let closure = match &parent_obj {
+                Some(parent) => {
+                    let env = Env::enclosed(self.env.clone());
+                    env.borrow_mut()
+                        .define(KeywordKind::Super.to_string(), Value::Obj(parent.clone()));
+                    env
+                }
+                None => self.env.clone(),
+            };
 
             let mut obj_methods: HashMap<String, Method> = HashMap::new();
-            for method in methods.to_owned() {
-                if let StmtKind::Fn { bound, .. } = &method.kind {
-                    let func: Function = Function::new(method.clone(), self.env.clone(), *bound);
+            for method in methods {
+                if let StmtKind::Fn(declr) = &method.kind {
+                    let func: Function = Function::new(
+                        declr.clone(),
+                        closure.clone(),
+                        declr.bound,
+                        self.file.clone(),
+                    );
                     obj_methods.insert(func.name().to_string(), Method::User(func));
                 }
             }
 
-            self.env.borrow_mut().assign(
-                name.as_str(),
-                Value::Obj(Rc::new(Object::new(name.clone(), obj_methods))),
-                stmt.cursor,
-            )?;
+            let obj = match parent_obj {
+                Some(parent) => Object::with_parent(name.clone(), obj_methods, parent),
+                None => Object::new(name.clone(), obj_methods),
+            };
+            // nothing is declared between the methods and here, so the object keeps its slot
+            self.env
+                .borrow_mut()
+                .define(name.clone(), Value::Obj(Rc::new(obj)));
             return Ok(());
         }
         unreachable!("Non-obj statement passed to Evaluator::eval_stmt_obj");
     }
 
+    /// Checks the parent of an object declaration.
+    pub(crate) fn parent_obj(name: &str, parent: Value, cursor: Cursor) -> EvalResult<Rc<Object>> {
+        match parent {
+            Value::Obj(obj) => Ok(obj),
+            other => Err(RuntimeEvent::error(
+                ErrKind::Type,
+                format!(
+                    "object '{}' can only inherit from an Obj, found {}",
+                    name,
+                    other.get_type()
+                ),
+                cursor,
+            )),
+        }
+    }
+
     fn eval_stmt_block(&mut self, stmt: &Stmt, env: EnvPtr) -> EvalResult<()> {
         if let StmtKind::Block(statements) = &stmt.kind {
             let prev = self.env.clone();
@@ -492,6 +917,14 @@ impl<'a> Evaluator<'a> {
         unreachable!("Non-block statement passed to Evaluator::eval_stmt_block");
     }
 
+    /// Evaluates an expression in the given environment.
+    fn eval_expr_in(&mut self, expr: &Expr, env: EnvPtr) -> EvalResult<Value> {
+        let prev = std::mem::replace(&mut self.env, env);
+        let result = self.eval_expr(expr);
+        self.env = prev;
+        result
+    }
+
     // Expression functions
 
     fn eval_expr(&mut self, expr: &Expr) -> EvalResult<Value> {
@@ -501,18 +934,21 @@ impl<'a> Evaluator<'a> {
             ExprKind::Grouping { .. } => self.eval_expr_grouping(expr),
             ExprKind::Unary { .. } => self.eval_expr_unary(expr),
             ExprKind::Literal(_) => self.eval_expr_literal(expr),
+            ExprKind::Interp(_) => self.eval_expr_interp(expr),
             ExprKind::List(_) => self.eval_expr_list(expr),
             ExprKind::Dict(_) => self.eval_expr_dict(expr),
             ExprKind::Range { .. } => self.eval_expr_range(expr),
             ExprKind::Index { .. } => self.eval_expr_index(expr),
             ExprKind::IndexSet { .. } => self.eval_expr_index_set(expr),
             ExprKind::Call { .. } => self.eval_expr_call(expr),
+            ExprKind::Lambda { .. } => self.eval_expr_lambda(expr),
             ExprKind::Var(_) => self.eval_expr_var(expr),
             ExprKind::Assign { .. } => self.eval_expr_assign(expr),
             ExprKind::Logical { .. } => self.eval_expr_logical(expr),
             ExprKind::Get { .. } => self.eval_expr_get(expr),
             ExprKind::Set { .. } => self.eval_expr_set(expr),
             ExprKind::ESelf => self.lookup_var(KeywordKind::KSelf.to_string().as_str(), expr),
+            ExprKind::Super { .. } => self.eval_expr_super(expr),
         }
     }
 
@@ -526,18 +962,12 @@ impl<'a> Evaluator<'a> {
             // compute new value
             let new_val = match op {
                 AssignOp::Value => rhs_val.clone(),
-                AssignOp::Add => current.add_assign(rhs_val, expr.cursor)?,
-                AssignOp::Sub => current.sub_assign(rhs_val, expr.cursor)?,
+                AssignOp::Add => current.add_assign(self, rhs_val, expr.cursor)?,
+                AssignOp::Sub => current.sub_assign(self, rhs_val, expr.cursor)?,
             };
 
             // write back
-            if let Some(d) = expr.get_resolved_dist() {
-                Env::assign_at(&self.env, name, new_val.clone(), d)?;
-            } else {
-                self.globals
-                    .borrow_mut()
-                    .assign(name, new_val.clone(), expr.cursor)?;
-            }
+            self.assign_var(name, new_val.clone(), expr)?;
 
             return Ok(new_val);
         }
@@ -587,16 +1017,23 @@ impl<'a> Evaluator<'a> {
 
     fn eval_expr_literal(&mut self, expr: &Expr) -> EvalResult<Value> {
         if let ExprKind::Literal(literal) = &expr.kind {
-            return match literal {
-                LiteralType::Null => Ok(Value::Null),
-                LiteralType::Num(i) => Ok(Value::Num(*i)),
-                LiteralType::Bool(b) => Ok(Value::Bool(*b)),
-                LiteralType::Str(s) => Ok(Value::Str(Rc::new(RefCell::new(s.clone())))),
-            };
+            return Ok(Value::from(literal));
         }
         unreachable!("Non-literal passed to Evaluator::eval_expr_literal");
     }
 
+    fn eval_expr_interp(&mut self, expr: &Expr) -> EvalResult<Value> {
+        if let ExprKind::Interp(parts) = &expr.kind {
+            let mut out = String::new();
+            for part in parts {
+                let val = self.eval_expr(part)?;
+                out.push_str(self.stringify(&val, part.cursor)?.as_str());
+            }
+            return Ok(Value::Str(Str::new(out)));
+        }
+        unreachable!("Non-interp passed to Evaluator::eval_expr_interp");
+    }
+
     fn eval_expr_list(&mut self, expr: &Expr) -> EvalResult<Value> {
         if let ExprKind::List(list) = &expr.kind {
             let mut values: Vec<Value> = vec![];
@@ -615,14 +1052,7 @@ impl<'a> Evaluator<'a> {
             let mut map: HashMap<ValueKey, Value> = HashMap::new();
 
             for (key, value) in dict {
-                let key_val = match ValueKey::try_from(&self.eval_expr(key)?) {
-                    Ok(val) => Ok(val),
-                    Err(_) => Err(RuntimeEvent::error(
-                        ErrKind::Type,
-                        "only Null, Bool, Num or Str values can be Dict keys".into(),
-                        expr.cursor,
-                    )),
-                }?;
+                let key_val = Self::dict_key(&self.eval_expr(key)?, expr.cursor)?;
                 map.insert(key_val, self.eval_expr(value)?);
             }
 
@@ -631,6 +1061,16 @@ impl<'a> Evaluator<'a> {
         unreachable!("Non-dict passed to Evaluator::eval_expr_dict");
     }
 
+    pub(crate) fn dict_key(key: &Value, cursor: Cursor) -> EvalResult<ValueKey> {
+        ValueKey::try_from(key).map_err(|_| {
+            RuntimeEvent::error(
+                ErrKind::Type,
+                "only Null, Bool, Int, Num or Str values can be Dict keys".into(),
+                cursor,
+            )
+        })
+    }
+
     fn eval_expr_range(&mut self, expr: &Expr) -> EvalResult<Value> {
         if let ExprKind::Range {
             start,
@@ -639,238 +1079,220 @@ impl<'a> Evaluator<'a> {
             step,
         } = &expr.kind
         {
-            let mut values: Vec<Value> = vec![];
-
-            let nstart: f64;
-            let val = self.eval_expr(start)?;
-            if let Value::Num(n) = val {
-                nstart = n.0;
-            } else {
-                return Err(RuntimeEvent::error(
-                    ErrKind::Type,
-                    "range start must be a Num".into(),
-                    expr.cursor,
-                ));
-            }
+            let start = self.eval_expr(start)?;
+            let end = match end {
+                Some(end) => Some(self.eval_expr(end)?),
+                None => None,
+            };
+            let (step, step_cursor) = match step {
+                Some(step) => (Some(self.eval_expr(step)?), step.cursor),
+                None => (None, expr.cursor),
+            };
+            return Self::range_value(start, end, step, *inclusive, expr.cursor, step_cursor);
+        }
+        unreachable!("Non-range passed to Evaluator::eval_expr_range");
+    }
 
-            let nend: f64;
-            let val = self.eval_expr(end)?;
-            if let Value::Num(n) = val {
-                nend = n.0;
-            } else {
-                return Err(RuntimeEvent::error(
-                    ErrKind::Type,
-                    "range end must be a Num".into(),
-                    expr.cursor,
-                ));
+    pub(crate) fn range_value(
+        start: Value,
+        end: Option<Value>,
+        step: Option<Value>,
+        inclusive: bool,
+        cursor: Cursor,
+        step_cursor: Cursor,
+    ) -> EvalResult<Value> {
+        // the range yields Ints if every bound is one
+        let mut int = true;
+        let mut check = |val: Value, name: &str, cursor: Cursor| match val {
+            Value::Int(_) => Ok(val),
+            Value::Num(_) => {
+                int = false;
+                Ok(val)
             }
-
-            let mut nstep: f64 = 1.0;
-            if let Some(expr) = step {
-                let val = self.eval_expr(expr)?;
-                if let Value::Num(n) = val {
-                    nstep = n.0;
-                } else {
-                    return Err(RuntimeEvent::error(
-                        ErrKind::Type,
-                        "range step must be a Num".into(),
-                        expr.cursor,
-                    ));
-                }
+            _ => Err(RuntimeEvent::error(
+                ErrKind::Type,
+                format!("range {} must be a Num", name),
+                cursor,
+            )),
+        };
+
+        let start = check(start, "start", cursor)?;
+        let end = match end {
+            Some(end) => Some(check(end, "end", cursor)?),
+            None => None,
+        };
+        let step = match step {
+            Some(step) => Some(check(step, "step", step_cursor)?),
+            None => None,
+        };
+
+        // without a step, ranges count towards their end
+        let bounds = if int {
+            let int = |val: Value| match val {
+                Value::Int(n) => n,
+                _ => unreachable!("Int range with a non-Int bound"),
+            };
+            let (start, end) = (int(start), end.map(int));
+            let down = end.as_ref().is_some_and(|end| *end < start);
+            Bounds::Int {
+                step: step.map_or(Int::Small(Range::default_step(down)), int),
+                start,
+                end,
             }
-
-            let incr = nstart < nend;
-            let mut i = nstart;
-            if *inclusive {
-                while i <= nend {
-                    values.push(Value::Num(OrderedFloat(i)));
-                    if incr { i += nstep } else { i -= nstep }
-                }
-            } else {
-                while i < nend {
-                    values.push(Value::Num(OrderedFloat(i)));
-                    if incr { i += nstep } else { i -= nstep }
-                }
+        } else {
+            let num = |val: Value| match val {
+                Value::Int(n) => n.to_f64(),
+                Value::Num(n) => n.0,
+                _ => unreachable!("range bounds are checked to be numbers"),
+            };
+            let (start, end) = (num(start), end.map(num));
+            let down = end.is_some_and(|end| end < start);
+            Bounds::Num {
+                step: step.map_or(Range::default_step(down) as f64, num),
+                start,
+                end,
             }
+        };
 
-            return Ok(Value::List(Rc::new(RefCell::new(values))));
-        }
-        unreachable!("Non-range passed to Evaluator::eval_expr_range");
+        let range = Range::new(bounds, inclusive, step_cursor)?;
+        Ok(Value::Range(Rc::new(range)))
     }
 
     fn eval_expr_index(&mut self, expr: &Expr) -> EvalResult<Value> {
         if let ExprKind::Index { obj, index } = &expr.kind {
             let base_val = self.eval_expr(obj)?;
             let index_val = self.eval_expr(index)?;
+            return self.index_value(base_val, index_val, expr.cursor, index.cursor);
+        }
+        unreachable!("Non-index passed to eval_expr_index");
+    }
 
-            return match base_val {
-                Value::Dict(map) => match index_val {
-                    Value::List(idx_list) => {
-                        let keys: Vec<ValueKey> = idx_list
-                            .borrow()
-                            .iter()
-                            .map(|v| {
-                                ValueKey::try_from(v).map_err(|_| {
-                                    RuntimeEvent::error(
-                                        ErrKind::Type,
-                                        "dict index list must be Null/Bool/Num/Str".into(),
-                                        index.cursor,
-                                    )
-                                })
+    /// Indexes a value, `cursor` is the location of the index expression and `index_cursor` the
+    /// location of the index itself.
+    pub(crate) fn index_value(
+        &mut self,
+        base_val: Value,
+        mut index_val: Value,
+        cursor: Cursor,
+        index_cursor: Cursor,
+    ) -> EvalResult<Value> {
+        // range indices slice the value, open-ended ranges run until its end
+        if let Value::Range(range) = &index_val
+            && !matches!(base_val, Value::ObjInstance(_))
+        {
+            index_val = self.range_indices(range, &base_val, index_cursor)?;
+        }
+
+        match base_val {
+            Value::ObjInstance(inst) => {
+                self.operator_method(&inst, "index", vec![index_val], cursor)
+            }
+            Value::Dict(map) => match index_val {
+                Value::List(idx_list) => {
+                    let keys: Vec<ValueKey> = idx_list
+                        .borrow()
+                        .iter()
+                        .map(|v| {
+                            ValueKey::try_from(v).map_err(|_| {
+                                RuntimeEvent::error(
+                                    ErrKind::Type,
+                                    "dict index list must be Null/Bool/Int/Num/Str".into(),
+                                    index_cursor,
+                                )
                             })
-                            .collect::<Result<_, _>>()?;
-                        let map_ref = map.borrow();
-                        let mut out = Vec::with_capacity(keys.len());
-                        for key in keys.iter() {
-                            if let Some(v) = map_ref.get(key) {
-                                out.push(v.clone());
-                            } else {
-                                return Err(RuntimeEvent::error(
-                                    ErrKind::Value,
-                                    "dict index not found".into(),
-                                    expr.cursor,
-                                ));
-                            }
-                        }
-                        Ok(Value::List(Rc::new(RefCell::new(out))))
-                    }
-                    _ => {
-                        let key = ValueKey::try_from(&index_val).map_err(|_| {
-                            RuntimeEvent::error(
-                                ErrKind::Type,
-                                "dict index must be Null, Bool, Num or Str".into(),
-                                index.cursor,
-                            )
-                        })?;
-                        if let Some(v) = map.borrow().get(&key) {
-                            Ok(v.clone())
+                        })
+                        .collect::<Result<_, _>>()?;
+                    let map_ref = map.borrow();
+                    let mut out = Vec::with_capacity(keys.len());
+                    for key in keys.iter() {
+                        if let Some(v) = map_ref.get(key) {
+                            out.push(v.clone());
                         } else {
-                            Err(RuntimeEvent::error(
-                                ErrKind::Value,
-                                "dict index not found".into(),
-                                expr.cursor,
-                            ))
-                        }
-                    }
-                },
-                Value::List(rc_items) => match index_val {
-                    Value::Num(n) => {
-                        let idx = n.0 as usize;
-                        let items = rc_items.borrow();
-                        if idx >= items.len() {
                             return Err(RuntimeEvent::error(
                                 ErrKind::Value,
-                                format!("list index {} out of bounds (len = {})", idx, items.len()),
-                                expr.cursor,
+                                "dict index not found".into(),
+                                cursor,
                             ));
                         }
-                        Ok(items[idx].clone())
                     }
+                    Ok(Value::List(Rc::new(RefCell::new(out))))
+                }
+                _ => {
+                    let key = ValueKey::try_from(&index_val).map_err(|_| {
+                        RuntimeEvent::error(
+                            ErrKind::Type,
+                            "dict index must be Null, Bool, Int, Num or Str".into(),
+                            index_cursor,
+                        )
+                    })?;
+                    if let Some(v) = map.borrow().get(&key) {
+                        Ok(v.clone())
+                    } else {
+                        Err(RuntimeEvent::error(
+                            ErrKind::Value,
+                            "dict index not found".into(),
+                            cursor,
+                        ))
+                    }
+                }
+            },
+            Value::List(rc_items) => match index_val {
+                Value::List(idx_list) => {
+                    let items = rc_items.borrow();
+                    let mut out = Vec::with_capacity(idx_list.borrow().len());
+                    for i in idx_list.borrow().iter() {
+                        let i = Self::seq_index(i, "list", items.len(), cursor, index_cursor)?;
+                        out.push(items[i].clone());
+                    }
+                    Ok(Value::List(Rc::new(RefCell::new(out))))
+                }
+                _ => {
+                    let items = rc_items.borrow();
+                    let i = Self::seq_index(&index_val, "list", items.len(), cursor, index_cursor)?;
+                    Ok(items[i].clone())
+                }
+            },
+            Value::Str(s) => match index_val {
+                Value::List(idx_list) => {
+                    let chars: Vec<char> = s.borrow().chars().collect();
+                    let mut out = String::new();
+                    for i in idx_list.borrow().iter() {
+                        let i = Self::seq_index(i, "string", chars.len(), cursor, index_cursor)?;
+                        out.push(chars[i]);
+                    }
+                    Ok(Value::Str(Str::new(out)))
+                }
+                _ => {
+                    let chars: Vec<char> = s.borrow().chars().collect();
+                    let i =
+                        Self::seq_index(&index_val, "string", chars.len(), cursor, index_cursor)?;
+                    Ok(Value::Str(Str::new(chars[i].to_string())))
+                }
+            },
+            // ranges are indexed like the List they yield, without building it
+            Value::Range(range) => {
+                let len = range.count().unwrap_or(usize::MAX);
+                match index_val {
                     Value::List(idx_list) => {
-                        let indices: Vec<usize> = idx_list
-                            .borrow()
-                            .iter()
-                            .map(|v| {
-                                if let Value::Num(n) = v {
-                                    Ok(n.0 as usize)
-                                } else {
-                                    Err(RuntimeEvent::error(
-                                        ErrKind::Type,
-                                        "index list must contain only Num values".into(),
-                                        index.cursor,
-                                    ))
-                                }
-                            })
-                            .collect::<Result<_, _>>()?;
-                        let items = rc_items.borrow();
-                        let mut out = Vec::with_capacity(indices.len());
-                        for i in indices.iter() {
-                            if *i >= items.len() {
-                                return Err(RuntimeEvent::error(
-                                    ErrKind::Value,
-                                    format!(
-                                        "list index {} out of bounds (len = {})",
-                                        i,
-                                        items.len()
-                                    ),
-                                    expr.cursor,
-                                ));
-                            }
-                            out.push(items[*i].clone());
+                        let mut out = Vec::with_capacity(idx_list.borrow().len());
+                        for i in idx_list.borrow().iter() {
+                            let i = Self::seq_index(i, "range", len, cursor, index_cursor)?;
+                            out.push(range.nth_value(i));
                         }
                         Ok(Value::List(Rc::new(RefCell::new(out))))
                     }
-                    _ => Err(RuntimeEvent::error(
-                        ErrKind::Type,
-                        "list index must be a Num or List of Nums".into(),
-                        index.cursor,
-                    )),
-                },
-                Value::Str(s) => match index_val {
-                    Value::Num(n) => {
-                        let idx = n.0 as usize;
-                        let chars: Vec<char> = s.borrow().chars().collect();
-                        if idx >= chars.len() {
-                            return Err(RuntimeEvent::error(
-                                ErrKind::Value,
-                                format!(
-                                    "string index {} out of bounds (len = {})",
-                                    idx,
-                                    chars.len()
-                                ),
-                                expr.cursor,
-                            ));
-                        }
-                        Ok(Value::Str(Rc::new(RefCell::new(chars[idx].to_string()))))
-                    }
-                    Value::List(idx_list) => {
-                        let indices: Vec<usize> = idx_list
-                            .borrow()
-                            .iter()
-                            .map(|v| {
-                                if let Value::Num(n) = v {
-                                    Ok(n.0 as usize)
-                                } else {
-                                    Err(RuntimeEvent::error(
-                                        ErrKind::Type,
-                                        "string index list must contain only Num values".into(),
-                                        index.cursor,
-                                    ))
-                                }
-                            })
-                            .collect::<Result<_, _>>()?;
-                        let chars: Vec<char> = s.borrow().chars().collect();
-                        let mut out = String::new();
-                        for i in indices.iter() {
-                            if *i >= chars.len() {
-                                return Err(RuntimeEvent::error(
-                                    ErrKind::Value,
-                                    format!(
-                                        "string index {} out of bounds (len = {})",
-                                        i,
-                                        chars.len()
-                                    ),
-                                    expr.cursor,
-                                ));
-                            }
-                            out.push(chars[*i]);
-                        }
-                        Ok(Value::Str(Rc::new(RefCell::new(out))))
+                    _ => {
+                        let i = Self::seq_index(&index_val, "range", len, cursor, index_cursor)?;
+                        Ok(range.nth_value(i))
                     }
-                    _ => Err(RuntimeEvent::error(
-                        ErrKind::Type,
-                        "string index must be a Num or List of Nums".into(),
-                        index.cursor,
-                    )),
-                },
-                _ => Err(RuntimeEvent::error(
-                    ErrKind::Type,
-                    "value is not indexable".into(),
-                    expr.cursor,
-                )),
-            };
+                }
+            }
+            _ => Err(RuntimeEvent::error(
+                ErrKind::Type,
+                "value is not indexable".into(),
+                cursor,
+            )),
         }
-        unreachable!("Non-index passed to eval_expr_index");
     }
 
     fn eval_expr_index_set(&mut self, expr: &Expr) -> EvalResult<Value> {
@@ -887,6 +1309,7 @@ impl<'a> Evaluator<'a> {
                 inclusive,
                 step,
             } = &index.kind
+                && !matches!(base_val, Value::ObjInstance(_))
             {
                 if step.is_some() {
                     return Err(RuntimeEvent::error(
@@ -895,283 +1318,228 @@ impl<'a> Evaluator<'a> {
                         index.cursor,
                     ));
                 }
-                let start_idx = self
-                    .eval_expr(start)?
-                    .check_num(index.cursor, Some("range start".into()))?
-                    as usize;
-                let mut end_idx = self
-                    .eval_expr(end)?
-                    .check_num(index.cursor, Some("range end".into()))?
-                    as usize;
-                if *inclusive {
-                    end_idx = end_idx.saturating_add(1);
-                }
+                let start = self.eval_expr(start)?;
+                let end = match end {
+                    Some(end) => Some(self.eval_expr(end)?),
+                    None => None,
+                };
+                return self.set_slice(
+                    base_val,
+                    (start, end, *inclusive),
+                    |evaluator| evaluator.eval_expr(val),
+                    expr.cursor,
+                    index.cursor,
+                );
+            }
 
-                return match base_val {
-                    Value::List(items) => {
-                        if start_idx > end_idx || end_idx > items.borrow().len() {
-                            return Err(RuntimeEvent::error(
-                                ErrKind::Value,
-                                "invalid list range".into(),
-                                expr.cursor,
-                            ));
-                        }
-                        let repl_val = self.eval_expr(val)?;
-                        let repl_list = if let Value::List(l) = repl_val.clone() {
-                            l.borrow().clone()
-                        } else {
-                            return Err(RuntimeEvent::error(
-                                ErrKind::Type,
-                                "slice assignment to List requires a List value".into(),
-                                expr.cursor,
-                            ));
-                        };
-                        items
-                            .borrow_mut()
-                            .splice(start_idx..end_idx, repl_list.into_iter());
-                        Ok(repl_val)
-                    }
-                    Value::Str(s) => {
-                        let buf: Vec<char> = s.borrow().chars().collect();
-                        if start_idx > end_idx || end_idx > buf.len() {
-                            return Err(RuntimeEvent::error(
-                                ErrKind::Value,
-                                "invalid string range".into(),
-                                expr.cursor,
-                            ));
-                        }
-                        let repl_val = self.eval_expr(val)?;
-                        let repl_str = if let Value::Str(st) = repl_val.clone() {
-                            st.borrow().clone()
-                        } else {
-                            return Err(RuntimeEvent::error(
-                                ErrKind::Type,
-                                "slice assignment to Str requires a Str value".into(),
-                                expr.cursor,
-                            ));
-                        };
-                        let mut new_buf = buf.clone();
-                        new_buf.splice(start_idx..end_idx, repl_str.chars().collect::<Vec<char>>());
-                        s.borrow_mut().clear();
-                        s.borrow_mut().push_str(&new_buf.iter().collect::<String>());
-                        Ok(repl_val)
-                    }
-                    _ => Err(RuntimeEvent::error(
+            let index_val = self.eval_expr(index)?;
+            return self.set_index(
+                base_val,
+                index_val,
+                |evaluator| evaluator.eval_expr(val),
+                expr.cursor,
+                index.cursor,
+            );
+        }
+        unreachable!("Non-index_set passed to Evaluator::eval_index_set");
+    }
+
+    /// Replaces the items of a List or the chars of a Str selected by a range of `start`, `end`
+    /// and `inclusive`. `val` evaluates the replacement once the range is checked.
+    pub(crate) fn set_slice(
+        &mut self,
+        base_val: Value,
+        (start, end, inclusive): (Value, Option<Value>, bool),
+        val: impl FnOnce(&mut Self) -> EvalResult<Value>,
+        cursor: Cursor,
+        index_cursor: Cursor,
+    ) -> EvalResult<Value> {
+        let start_idx = start.check_num(index_cursor, Some("range start".into()))? as usize;
+        let end_idx = match end {
+            Some(end) => {
+                let end_idx = end.check_num(index_cursor, Some("range end".into()))? as usize;
+                if inclusive {
+                    end_idx.saturating_add(1)
+                } else {
+                    end_idx
+                }
+            }
+            // open-ended slices run until the end of the value
+            None => match &base_val {
+                Value::List(items) => items.borrow().len(),
+                _ => 0,
+            },
+        };
+
+        match base_val {
+            Value::List(items) => {
+                if start_idx > end_idx || end_idx > items.borrow().len() {
+                    return Err(RuntimeEvent::error(
+                        ErrKind::Value,
+                        "invalid list range".into(),
+                        cursor,
+                    ));
+                }
+                let repl_val = val(self)?;
+                let repl_list = if let Value::List(l) = repl_val.clone() {
+                    l.borrow().clone()
+                } else {
+                    return Err(RuntimeEvent::error(
                         ErrKind::Type,
-                        "value is not indexable".into(),
-                        expr.cursor,
-                    )),
+                        "slice assignment to List requires a List value".into(),
+                        cursor,
+                    ));
                 };
+                items.borrow_mut().splice(start_idx..end_idx, repl_list);
+                Ok(repl_val)
             }
+            Value::Str(_) => Err(RuntimeEvent::error(
+                ErrKind::Type,
+                "can't assign to a slice of Str, Strs are immutable".into(),
+                cursor,
+            )),
+            _ => Err(RuntimeEvent::error(
+                ErrKind::Type,
+                "value is not indexable".into(),
+                cursor,
+            )),
+        }
+    }
 
-            // regular index assignment
-            let index_val = self.eval_expr(index)?;
-            return match base_val {
-                Value::Dict(map) => match index_val {
-                    Value::List(idx_list) => {
-                        let keys: Vec<ValueKey> = idx_list
-                            .borrow()
-                            .iter()
-                            .map(|v| {
-                                ValueKey::try_from(v).map_err(|_| {
-                                    RuntimeEvent::error(
-                                        ErrKind::Type,
-                                        "dict index list must be Null/Bool/Num/Str".into(),
-                                        index.cursor,
-                                    )
-                                })
+    /// Assigns to an index of a value, range indices set every index they contain. `val`
+    /// evaluates the assigned value once the index is checked.
+    pub(crate) fn set_index(
+        &mut self,
+        base_val: Value,
+        mut index_val: Value,
+        val: impl FnOnce(&mut Self) -> EvalResult<Value>,
+        cursor: Cursor,
+        index_cursor: Cursor,
+    ) -> EvalResult<Value> {
+        if let Value::ObjInstance(inst) = &base_val {
+            let set_val = val(self)?;
+            self.operator_method(inst, "index_set", vec![index_val, set_val.clone()], cursor)?;
+            return Ok(set_val);
+        }
+
+        if let Value::Range(range) = &index_val {
+            index_val = self.range_indices(range, &base_val, index_cursor)?;
+        }
+        match base_val {
+            Value::Dict(map) => match index_val {
+                Value::List(idx_list) => {
+                    let keys: Vec<ValueKey> = idx_list
+                        .borrow()
+                        .iter()
+                        .map(|v| {
+                            ValueKey::try_from(v).map_err(|_| {
+                                RuntimeEvent::error(
+                                    ErrKind::Type,
+                                    "dict index list must be Null/Bool/Int/Num/Str".into(),
+                                    index_cursor,
+                                )
                             })
-                            .collect::<Result<_, _>>()?;
-                        let map_ref = map.borrow();
-                        let mut out = Vec::with_capacity(keys.len());
-                        for key in keys.iter() {
-                            if let Some(v) = map_ref.get(key) {
-                                out.push(v.clone());
-                            } else {
-                                return Err(RuntimeEvent::error(
-                                    ErrKind::Value,
-                                    "dict index not found".into(),
-                                    expr.cursor,
-                                ));
-                            }
-                        }
-                        Ok(Value::List(Rc::new(RefCell::new(out))))
-                    }
-                    _ => {
-                        let key = ValueKey::try_from(&index_val).map_err(|_| {
-                            RuntimeEvent::error(
-                                ErrKind::Type,
-                                "dict index must be Null, Bool, Num or Str".into(),
-                                index.cursor,
-                            )
-                        })?;
-                        let set_val = self.eval_expr(val)?;
-                        if let Some(v) = map.borrow_mut().get_mut(&key) {
-                            *v = set_val.clone();
-                            Ok(set_val)
+                        })
+                        .collect::<Result<_, _>>()?;
+                    let map_ref = map.borrow();
+                    let mut out = Vec::with_capacity(keys.len());
+                    for key in keys.iter() {
+                        if let Some(v) = map_ref.get(key) {
+                            out.push(v.clone());
                         } else {
-                            Err(RuntimeEvent::error(
-                                ErrKind::Value,
-                                "dict index not found".into(),
-                                expr.cursor,
-                            ))
-                        }
-                    }
-                },
-                Value::List(items) => match index_val {
-                    Value::Num(n) => {
-                        let idx = n.0 as usize;
-                        if idx >= items.borrow().len() {
                             return Err(RuntimeEvent::error(
                                 ErrKind::Value,
-                                format!(
-                                    "list index {} out of bounds (len = {})",
-                                    idx,
-                                    items.borrow().len()
-                                ),
-                                expr.cursor,
+                                "dict index not found".into(),
+                                cursor,
                             ));
                         }
-                        let set_val = self.eval_expr(val)?;
-                        items.borrow_mut()[idx] = set_val.clone();
-                        Ok(set_val)
                     }
-                    Value::List(idx_list) => {
-                        let indices: Vec<usize> = idx_list
-                            .borrow()
-                            .iter()
-                            .map(|v| {
-                                if let Value::Num(n) = v {
-                                    Ok(n.0 as usize)
-                                } else {
-                                    Err(RuntimeEvent::error(
-                                        ErrKind::Type,
-                                        "index list must contain only Num values".into(),
-                                        index.cursor,
-                                    ))
-                                }
-                            })
-                            .collect::<Result<_, _>>()?;
-                        let set_val = self.eval_expr(val)?;
-                        for i in indices.iter() {
-                            if *i >= items.borrow().len() {
-                                return Err(RuntimeEvent::error(
-                                    ErrKind::Value,
-                                    format!(
-                                        "list index {} out of bounds (len = {})",
-                                        i,
-                                        items.borrow().len()
-                                    ),
-                                    expr.cursor,
-                                ));
-                            }
-                            items.borrow_mut()[*i] = set_val.clone();
-                        }
+                    Ok(Value::List(Rc::new(RefCell::new(out))))
+                }
+                _ => {
+                    let key = ValueKey::try_from(&index_val).map_err(|_| {
+                        RuntimeEvent::error(
+                            ErrKind::Type,
+                            "dict index must be Null, Bool, Int, Num or Str".into(),
+                            index_cursor,
+                        )
+                    })?;
+                    let set_val = val(self)?;
+                    if let Some(v) = map.borrow_mut().get_mut(&key) {
+                        *v = set_val.clone();
                         Ok(set_val)
+                    } else {
+                        Err(RuntimeEvent::error(
+                            ErrKind::Value,
+                            "dict index not found".into(),
+                            cursor,
+                        ))
                     }
-                    _ => Err(RuntimeEvent::error(
-                        ErrKind::Type,
-                        "list index must be a Num or List of Nums".into(),
-                        index.cursor,
-                    )),
-                },
-                Value::Str(s) => match index_val {
-                    Value::Num(n) => {
-                        let idx = n.0 as usize;
-                        let len = s.borrow().chars().count();
-                        if idx >= len {
-                            return Err(RuntimeEvent::error(
-                                ErrKind::Value,
-                                format!("string index {} out of bounds (len = {})", idx, len),
-                                expr.cursor,
-                            ));
-                        }
-                        let set_val = self.eval_expr(val)?;
-                        if let Value::Str(set_str) = set_val.clone() {
-                            s.borrow_mut()
-                                .replace_range(idx..=idx, set_str.borrow().as_str());
-                            Ok(set_val)
-                        } else {
-                            Err(RuntimeEvent::error(
-                                ErrKind::Type,
-                                "can't set index of Str to non-Str".into(),
-                                expr.cursor,
-                            ))
-                        }
-                    }
-                    Value::List(idx_list) => {
-                        let indices: Vec<usize> = idx_list
-                            .borrow()
-                            .iter()
-                            .map(|v| {
-                                if let Value::Num(n) = v {
-                                    Ok(n.0 as usize)
-                                } else {
-                                    Err(RuntimeEvent::error(
-                                        ErrKind::Type,
-                                        "string index list must contain only Num values".into(),
-                                        index.cursor,
-                                    ))
-                                }
-                            })
-                            .collect::<Result<_, _>>()?;
-                        let set_val = self.eval_expr(val)?;
-                        let repl = if let Value::Str(sv) = set_val.clone() {
-                            sv.borrow().clone()
-                        } else {
-                            return Err(RuntimeEvent::error(
-                                ErrKind::Type,
-                                "can't set index of Str to non-Str".into(),
-                                expr.cursor,
-                            ));
-                        };
-                        let mut buf: Vec<char> = s.borrow().chars().collect();
-                        for i in indices.iter() {
-                            if *i >= buf.len() {
-                                return Err(RuntimeEvent::error(
-                                    ErrKind::Value,
-                                    format!(
-                                        "string index {} out of bounds (len = {})",
-                                        i,
-                                        buf.len()
-                                    ),
-                                    expr.cursor,
-                                ));
-                            }
-                            if let Some(ch) = repl.chars().next() {
-                                buf[*i] = ch;
-                            }
-                        }
-                        s.borrow_mut().clear();
-                        s.borrow_mut().push_str(&buf.iter().collect::<String>());
-                        Ok(set_val)
+                }
+            },
+            Value::List(items) => match index_val {
+                Value::List(idx_list) => {
+                    let len = items.borrow().len();
+                    let indices = idx_list
+                        .borrow()
+                        .iter()
+                        .map(|i| Self::seq_index(i, "list", len, cursor, index_cursor))
+                        .collect::<EvalResult<Vec<_>>>()?;
+                    let set_val = val(self)?;
+                    for i in indices {
+                        items.borrow_mut()[i] = set_val.clone();
                     }
-                    _ => Err(RuntimeEvent::error(
-                        ErrKind::Type,
-                        "string index must be a Num or List of Nums".into(),
-                        index.cursor,
-                    )),
-                },
-                _ => Err(RuntimeEvent::error(
-                    ErrKind::Type,
-                    "value is not indexable".into(),
-                    expr.cursor,
-                )),
-            };
+                    Ok(set_val)
+                }
+                _ => {
+                    let len = items.borrow().len();
+                    let i = Self::seq_index(&index_val, "list", len, cursor, index_cursor)?;
+                    let set_val = val(self)?;
+                    items.borrow_mut()[i] = set_val.clone();
+                    Ok(set_val)
+                }
+            },
+            Value::Str(_) => Err(RuntimeEvent::error(
+                ErrKind::Type,
+                "can't assign to an index of Str, Strs are immutable".into(),
+                cursor,
+            )),
+            _ => Err(RuntimeEvent::error(
+                ErrKind::Type,
+                "value is not indexable".into(),
+                cursor,
+            )),
         }
-        unreachable!("Non-index_set passed to Evaluator::eval_index_set");
     }
 
     fn eval_expr_call(&mut self, expr: &Expr) -> EvalResult<Value> {
-        if let ExprKind::Call { callee, args } = &expr.kind {
+        self.eval_call(expr, false)
+    }
+
+    /// Evaluates a call, tail calls to user functions are returned as a `TailCall` event for
+    /// the running function to make instead.
+    fn eval_call(&mut self, expr: &Expr, tail: bool) -> EvalResult<Value> {
+        if let ExprKind::Call {
+            callee,
+            args,
+            named,
+        } = &expr.kind
+        {
             let callee = self.eval_expr(callee)?;
             let mut args_values = Vec::with_capacity(args.len());
             for arg in args {
                 args_values.push(self.eval_expr(arg)?);
             }
+            let mut named_values = Vec::with_capacity(named.len());
+            for arg in named {
+                named_values.push((arg.name.clone(), arg.cursor, self.eval_expr(&arg.val)?));
+            }
 
-            if let Value::Callable(c) = callee {
-                if args_values.len() != c.arity() {
+            if tail
+                && let Value::Callable(c) = &callee
+                && c.as_function().is_some()
+            {
+                if named_values.is_empty() && !c.arity().accepts(args_values.len()) {
                     return Err(RuntimeEvent::error(
                         ErrKind::Arity,
                         format!(
@@ -1182,151 +1550,355 @@ impl<'a> Evaluator<'a> {
                         expr.cursor,
                     ));
                 }
-                let call_res =
-                    catch_unwind(AssertUnwindSafe(|| c.call(self, args_values, expr.cursor)));
-                let res = match call_res {
-                    Ok(r) => r,
-                    Err(payload) => {
-                        let msg = if let Some(s) = payload.downcast_ref::<&str>() {
-                            s.to_string()
-                        } else if let Some(s) = payload.downcast_ref::<String>() {
-                            s.clone()
-                        } else {
-                            "native panic".to_string()
-                        };
-                        return Err(RuntimeEvent::error(ErrKind::Native, msg, expr.cursor));
-                    }
-                };
-                return Ok(res?);
-            }
-
-            if let Value::Obj(obj) = callee {
-                if args_values.len() != obj.arity() {
-                    return Err(RuntimeEvent::error(
-                        ErrKind::Arity,
-                        format!(
-                            "object initializer expects {} arguments but got {}",
-                            obj.arity(),
-                            args_values.len()
-                        ),
-                        expr.cursor,
-                    ));
-                }
-                let call_res = catch_unwind(AssertUnwindSafe(|| {
-                    obj.call(self, args_values, expr.cursor)
-                }));
-                let res = match call_res {
-                    Ok(r) => r,
-                    Err(payload) => {
-                        let msg = if let Some(s) = payload.downcast_ref::<&str>() {
-                            s.to_string()
-                        } else if let Some(s) = payload.downcast_ref::<String>() {
-                            s.clone()
-                        } else {
-                            "native panic".to_string()
-                        };
-                        return Err(RuntimeEvent::error(ErrKind::Native, msg, expr.cursor));
-                    }
+                let args = if named_values.is_empty() {
+                    args_values.into_iter().map(Some).collect()
+                } else {
+                    self.match_named_args(c.as_ref(), args_values, named_values, expr.cursor)?
                 };
-                return Ok(res?);
+                return Err(RuntimeEvent::TailCall {
+                    callee: c.clone(),
+                    args,
+                });
             }
 
-            return Err(RuntimeEvent::error(
-                ErrKind::Type,
-                "can only call functions or objects".into(),
-                expr.cursor,
-            ));
+            return self.call_value(callee, args_values, named_values, expr.cursor);
         }
-        unreachable!("Non-call passed to Evaluator::eval_expr_call");
+        unreachable!("Non-call passed to Evaluator::eval_call");
     }
 
-    fn eval_expr_get(&mut self, expr: &Expr) -> EvalResult<Value> {
-        if let ExprKind::Get { obj, name } = &expr.kind {
-            let val = self.eval_expr(obj)?;
-
-            // instance methods
-            if let Value::ObjInstance(inst) = val {
-                return Ok(Instance::get_rc(inst.clone(), name.clone(), expr.cursor)?);
+    /// Calls a function or object with the arguments of a call made at `cursor`.
+    pub(crate) fn call_value(
+        &mut self,
+        callee: Value,
+        args_values: Vec<Value>,
+        named_values: Vec<NamedValue>,
+        cursor: Cursor,
+    ) -> EvalResult<Value> {
+        if let Value::Callable(c) = callee {
+            if named_values.is_empty() && !c.arity().accepts(args_values.len()) {
+                return Err(RuntimeEvent::error(
+                    ErrKind::Arity,
+                    format!(
+                        "function expects {} arguments but got {}",
+                        c.arity(),
+                        args_values.len()
+                    ),
+                    cursor,
+                ));
             }
-
-            // static methods
-            if let Value::Obj(obj) = val {
-                if let Some(method) = obj.methods.get(&name.clone()) {
-                    if !method.get_bound() {
-                        return Ok(Value::Callable(method.get_callable()));
+            let (depth, file) = (self.call_stack.len(), self.file.clone());
+            self.push_frame(c.name(), cursor)?;
+            let call_res = catch_unwind(AssertUnwindSafe(|| {
+                if named_values.is_empty() {
+                    return c.call(self, args_values, cursor);
+                }
+                let args = self.match_named_args(c.as_ref(), args_values, named_values, cursor)?;
+                c.call_named(self, args, cursor)
+            }));
+            let res = match call_res {
+                Ok(r) => r,
+                Err(payload) => {
+                    let msg = if let Some(s) = payload.downcast_ref::<&str>() {
+                        s.to_string()
+                    } else if let Some(s) = payload.downcast_ref::<String>() {
+                        s.clone()
                     } else {
-                        return Err(RuntimeEvent::error(
-                            ErrKind::Name,
-                            format!(
-                                "can't call bound method '{}' of object '{}' without an instance",
-                                name, obj.name
-                            ),
-                            expr.cursor,
-                        ));
-                    }
+                        "native panic".to_string()
+                    };
+                    // unwound calls didn't restore the stack and script
+                    self.call_stack.truncate(depth + 1);
+                    self.file = file;
+                    Err(RuntimeEvent::error(ErrKind::Native, msg, cursor))
                 }
+            };
+            return self.pop_frame(res);
+        }
+
+        if let Value::Obj(obj) = callee {
+            if named_values.is_empty() && !obj.arity().accepts(args_values.len()) {
                 return Err(RuntimeEvent::error(
-                    ErrKind::Name,
-                    format!("static method '{}' undefined in object {}", name, obj.name),
-                    expr.cursor,
+                    ErrKind::Arity,
+                    format!(
+                        "object initializer expects {} arguments but got {}",
+                        obj.arity(),
+                        args_values.len()
+                    ),
+                    cursor,
                 ));
             }
-
-            // primitive prototype methods
-            if let Some(proto) = val.prototype(&self.prototypes) {
-                if let Some(method) = proto.get_method(name.clone()) {
-                    let bound = BoundMethod {
-                        receiver: val.clone(),
-                        method,
+            let (depth, file) = (self.call_stack.len(), self.file.clone());
+            self.push_frame(obj.name(), cursor)?;
+            let call_res = catch_unwind(AssertUnwindSafe(|| {
+                if named_values.is_empty() {
+                    return obj.call(self, args_values, cursor);
+                }
+                let args =
+                    self.match_named_args(obj.as_ref(), args_values, named_values, cursor)?;
+                obj.call_named(self, args, cursor)
+            }));
+            let res = match call_res {
+                Ok(r) => r,
+                Err(payload) => {
+                    let msg = if let Some(s) = payload.downcast_ref::<&str>() {
+                        s.to_string()
+                    } else if let Some(s) = payload.downcast_ref::<String>() {
+                        s.clone()
+                    } else {
+                        "native panic".to_string()
                     };
-                    return Ok(Value::Callable(Rc::new(bound)));
+                    // unwound calls didn't restore the stack and script
+                    self.call_stack.truncate(depth + 1);
+                    self.file = file;
+                    Err(RuntimeEvent::error(ErrKind::Native, msg, cursor))
                 }
+            };
+            return self.pop_frame(res);
+        }
+
+        Err(RuntimeEvent::error(
+            ErrKind::Type,
+            "can only call functions or objects".into(),
+            cursor,
+        ))
+    }
+
+    /// Places named arguments in the slots of the callable's parameters after the positional
+    /// ones. Skipped parameters are left as `None`, trailing ones are dropped.
+    pub(crate) fn match_named_args(
+        &self,
+        callable: &dyn Callable,
+        args: Vec<Value>,
+        named: Vec<NamedValue>,
+        cursor: Cursor,
+    ) -> EvalResult<Vec<Option<Value>>> {
+        let names = callable.param_names();
+        if names.is_empty()
+            && let Some((_, arg_cursor, _)) = named.first()
+        {
+            return Err(RuntimeEvent::error(
+                ErrKind::Arity,
+                format!(
+                    "function '{}' doesn't take named arguments",
+                    callable.name()
+                ),
+                *arg_cursor,
+            ));
+        }
+
+        let mut slots: Vec<Option<Value>> = args.into_iter().map(Some).collect();
+        if slots.len() < names.len() {
+            slots.resize(names.len(), None);
+        }
+
+        for (arg_name, arg_cursor, val) in named {
+            let Some(i) = names.iter().position(|name| *name == arg_name) else {
                 return Err(RuntimeEvent::error(
-                    ErrKind::Name,
-                    format!("method '{}' not found in {} prototype", name, proto.name),
-                    expr.cursor,
+                    ErrKind::Arity,
+                    format!(
+                        "function '{}' has no parameter named '{}'",
+                        callable.name(),
+                        arg_name
+                    ),
+                    arg_cursor,
+                ));
+            };
+            if slots[i].is_some() {
+                return Err(RuntimeEvent::error(
+                    ErrKind::Arity,
+                    format!("argument '{}' was passed more than once", arg_name),
+                    arg_cursor,
                 ));
             }
+            slots[i] = Some(val);
+        }
+
+        while let Some(None) = slots.last() {
+            slots.pop();
+        }
 
+        let arity = callable.arity();
+        if let Some(i) = (0..arity.min).find(|i| slots.get(*i).is_none_or(Option::is_none)) {
             return Err(RuntimeEvent::error(
-                ErrKind::Type,
-                "only instances and primitives with prototypes have properties".into(),
-                expr.cursor,
+                ErrKind::Arity,
+                format!("missing argument '{}'", names[i]),
+                cursor,
+            ));
+        }
+        if !arity.accepts(slots.len()) {
+            return Err(RuntimeEvent::error(
+                ErrKind::Arity,
+                format!(
+                    "function expects {} arguments but got {}",
+                    arity,
+                    slots.len()
+                ),
+                cursor,
             ));
         }
+
+        Ok(slots)
+    }
+
+    fn eval_expr_lambda(&mut self, expr: &Expr) -> EvalResult<Value> {
+        if let ExprKind::Lambda(declr) = &expr.kind {
+            return Ok(Value::Callable(Rc::new(Function::new(
+                declr.clone(),
+                self.env.clone(),
+                false,
+                self.file.clone(),
+            ))));
+        }
+        unreachable!("Non-lambda passed to Evaluator::eval_expr_lambda");
+    }
+
+    fn eval_expr_get(&mut self, expr: &Expr) -> EvalResult<Value> {
+        if let ExprKind::Get { obj, name, cache } = &expr.kind {
+            let val = self.eval_expr(obj)?;
+            return self.get_property(val, name, Some(cache), expr.cursor);
+        }
         unreachable!("Non-get passed to Evaluator::eval_expr_get");
     }
 
-    fn eval_expr_set(&mut self, expr: &Expr) -> EvalResult<Value> {
-        if let ExprKind::Set { obj, name, op, val } = &expr.kind {
-            let obj = self.eval_expr(obj)?;
+    /// Gets a property of a value: fields and methods of instances, static methods of objects,
+    /// members of modules and prototype methods of primitives. Methods of instances and
+    /// primitives are looked up through the inline cache of the access if there is one.
+    pub(crate) fn get_property(
+        &mut self,
+        val: Value,
+        name: &str,
+        cache: Option<&PropCache>,
+        cursor: Cursor,
+    ) -> EvalResult<Value> {
+        // instance methods
+        if let Value::ObjInstance(inst) = val {
+            return Instance::get_rc(inst, name, cache, cursor);
+        }
 
-            if let Value::ObjInstance(inst) = obj {
-                let rhs_val = self.eval_expr(val)?;
+        // static methods
+        if let Value::Obj(obj) = val {
+            return self.static_method(&obj, name, cursor);
+        }
 
-                let new_val = match op {
-                    AssignOp::Value => rhs_val.clone(),
-                    AssignOp::Add => {
-                        let current = Instance::get_rc(inst.clone(), name.clone(), expr.cursor)?;
-                        current.add_assign(rhs_val, expr.cursor)?
-                    }
-                    AssignOp::Sub => {
-                        let current = Instance::get_rc(inst.clone(), name.clone(), expr.cursor)?;
-                        current.sub_assign(rhs_val, expr.cursor)?
-                    }
-                };
+        // module members
+        if let Value::Module(module) = val {
+            return module.get(name, cursor);
+        }
 
-                inst.borrow_mut().set(name.clone(), new_val.clone());
-                return Ok(new_val);
+        // primitive prototype methods
+        if let Some(proto) = val.prototype(&self.prototypes) {
+            let method = match cache {
+                Some(cache) => cache.proto_method(&val, proto, name),
+                None => proto.get_method(name),
+            };
+            if let Some(method) = method {
+                let bound = BoundMethod {
+                    receiver: val.clone(),
+                    method,
+                };
+                return Ok(Value::Callable(Rc::new(bound)));
             }
+            return Err(RuntimeEvent::error(
+                ErrKind::Name,
+                format!("method '{}' not found in {} prototype", name, proto.name),
+                cursor,
+            ));
+        }
+
+        Err(RuntimeEvent::error(
+            ErrKind::Type,
+            "only instances and primitives with prototypes have properties".into(),
+            cursor,
+        ))
+    }
+
+    fn eval_expr_super(&mut self, expr: &Expr) -> EvalResult<Value> {
+        if let ExprKind::Super { method } = &expr.kind {
+            let (dist, slot) = expr
+                .get_resolved()
+                .expect("super expression should be resolved");
+            let parent = match Env::get_at(&self.env, dist, slot) {
+                Some(Value::Obj(obj)) => obj,
+                _ => unreachable!("super should always be bound to an Obj"),
+            };
 
+            // `self` lives in the first slot of the scope right inside the one holding `super`.
+            let receiver = Env::get_at(&self.env, dist - 1, 0);
+            return self.super_method(&parent, receiver, method, expr.cursor);
+        }
+        unreachable!("Non-super passed to Evaluator::eval_expr_super");
+    }
+
+    /// Gets a method of the parent object, bound to `receiver` inside bound methods.
+    pub(crate) fn super_method(
+        &self,
+        parent: &Object,
+        receiver: Option<Value>,
+        method: &str,
+        cursor: Cursor,
+    ) -> EvalResult<Value> {
+        let Some(receiver) = receiver else {
+            return self.static_method(parent, method, cursor);
+        };
+        if let Some(found) = parent.find_method(method) {
+            return Ok(Value::Callable(found.bind(receiver).get_callable()));
+        }
+        Err(RuntimeEvent::error(
+            ErrKind::Name,
+            format!(
+                "method '{}' undefined in parent object {}",
+                method, parent.name
+            ),
+            cursor,
+        ))
+    }
+
+    fn eval_expr_set(&mut self, expr: &Expr) -> EvalResult<Value> {
+        if let ExprKind::Set { obj, name, op, val } = &expr.kind {
+            let obj = self.eval_expr(obj)?;
+            return self.set_property(
+                obj,
+                name,
+                op,
+                |evaluator| evaluator.eval_expr(val),
+                expr.cursor,
+            );
+        }
+        unreachable!("Non-set passed to Evaluator::eval_expr_set");
+    }
+
+    /// Assigns to a field of an instance, `rhs` evaluates the right hand side once the value is
+    /// known to be an instance.
+    pub(crate) fn set_property(
+        &mut self,
+        obj: Value,
+        name: &str,
+        op: &AssignOp,
+        rhs: impl FnOnce(&mut Self) -> EvalResult<Value>,
+        cursor: Cursor,
+    ) -> EvalResult<Value> {
+        let Value::ObjInstance(inst) = obj else {
             return Err(RuntimeEvent::error(
                 ErrKind::Type,
                 "only instances have fields".into(),
-                expr.cursor,
+                cursor,
             ));
-        }
-        unreachable!("Non-set passed to Evaluator::eval_expr_set");
+        };
+
+        let rhs_val = rhs(self)?;
+        let new_val = match op {
+            AssignOp::Value => rhs_val,
+            AssignOp::Add => {
+                let current = Instance::get_rc(inst.clone(), name, None, cursor)?;
+                current.add_assign(self, rhs_val, cursor)?
+            }
+            AssignOp::Sub => {
+                let current = Instance::get_rc(inst.clone(), name, None, cursor)?;
+                current.sub_assign(self, rhs_val, cursor)?
+            }
+        };
+
+        inst.borrow_mut().set(name.to_string(), new_val.clone());
+        Ok(new_val)
     }
 
     fn eval_expr_grouping(&mut self, expr: &Expr) -> EvalResult<Value> {
@@ -1339,85 +1911,328 @@ impl<'a> Evaluator<'a> {
     fn eval_expr_unary(&mut self, expr: &Expr) -> EvalResult<Value> {
         if let ExprKind::Unary { op, right } = &expr.kind {
             let right = self.eval_expr(right)?;
-            return match op {
-                UnaryOp::Negate => Ok(Value::Num(OrderedFloat(
-                    -right.check_num(expr.cursor, None)?,
-                ))),
-                UnaryOp::Not => Ok(Value::Bool(!right.is_truthy())),
-            };
+            return self.unary_op(op, right, expr.cursor);
         }
         unreachable!("Non-unary passed to Evaluator::eval_expr_unary");
     }
 
+    pub(crate) fn unary_op(
+        &mut self,
+        op: &UnaryOp,
+        right: Value,
+        cursor: Cursor,
+    ) -> EvalResult<Value> {
+        if let Value::ObjInstance(inst) = &right {
+            match op {
+                UnaryOp::Negate => return self.operator_method(inst, "neg", vec![], cursor),
+                UnaryOp::BitNot => return self.operator_method(inst, "bit_not", vec![], cursor),
+                UnaryOp::Not => (),
+            }
+        }
+
+        match op {
+            UnaryOp::Negate => match right {
+                Value::Int(n) => Ok(Value::Int(-&n)),
+                right => Ok(Value::Num(OrderedFloat(-right.check_num(cursor, None)?))),
+            },
+            UnaryOp::Not => Ok(Value::Bool(!right.is_truthy())),
+            UnaryOp::BitNot => Ok(Value::Int(!&right.check_int(cursor, None)?)),
+        }
+    }
+
     fn eval_expr_binary(&mut self, expr: &Expr) -> EvalResult<Value> {
         if let ExprKind::Binary { left, op, right } = &expr.kind {
             let left = self.eval_expr(left)?;
             let right = self.eval_expr(right)?;
-            let cursor = expr.cursor;
-
-            return match op {
-                BinaryOp::Add => {
-                    if let (Value::Num(ln), Value::Num(rn)) = (left.clone(), right.clone()) {
-                        Ok(Value::Num(ln + rn))
-                    } else if let (Value::Str(ls), Value::Str(rs)) = (left, right) {
-                        Ok(Value::Str(Rc::new(RefCell::new(format!(
-                            "{}{}",
-                            ls.borrow(),
-                            rs.borrow()
-                        )))))
-                    } else {
-                        Ok(Value::Null)
-                    }
+            return self.binary_op(left, op, right, expr.cursor);
+        }
+        unreachable!("Non-binary passed to Evaluator::eval_expr_binary");
+    }
+
+    pub(crate) fn binary_op(
+        &mut self,
+        left: Value,
+        op: &BinaryOp,
+        right: Value,
+        cursor: Cursor,
+    ) -> EvalResult<Value> {
+        // operator overloading, dispatched on the left operand
+        if let Value::ObjInstance(inst) = &left {
+            let method = match op {
+                BinaryOp::Add => Some("add"),
+                BinaryOp::Sub => Some("sub"),
+                BinaryOp::Mult => Some("mul"),
+                BinaryOp::Div => Some("div"),
+                BinaryOp::FloorDiv => Some("floor_div"),
+                BinaryOp::Mod => Some("mod"),
+                BinaryOp::Pow => Some("pow"),
+                BinaryOp::BitAnd => Some("bit_and"),
+                BinaryOp::BitOr => Some("bit_or"),
+                BinaryOp::BitXor => Some("bit_xor"),
+                BinaryOp::ShiftLeft => Some("shl"),
+                BinaryOp::ShiftRight => Some("shr"),
+                BinaryOp::Greater
+                | BinaryOp::GreaterEquals
+                | BinaryOp::Lesser
+                | BinaryOp::LesserEquals => Some("compare"),
+                _ => None,
+            };
+
+            if let Some(method) = method {
+                let res = self.operator_method(inst, method, vec![right], cursor)?;
+                if method != "compare" {
+                    return Ok(res);
                 }
-                BinaryOp::Sub => Ok(Value::Num(OrderedFloat(
-                    left.check_num(cursor, None)? - right.check_num(cursor, None)?,
-                ))),
-                BinaryOp::Mult => Ok(Value::Num(OrderedFloat(
-                    left.check_num(cursor, None)? * right.check_num(cursor, None)?,
-                ))),
-                BinaryOp::Div => Ok(Value::Num(OrderedFloat(
-                    left.check_num(cursor, None)? / right.check_num(cursor, None)?,
-                ))),
-                BinaryOp::Mod => Ok(Value::Num(OrderedFloat(
-                    left.check_num(cursor, None)? % right.check_num(cursor, None)?,
-                ))),
-                BinaryOp::Pow => Ok(Value::Num(OrderedFloat(
-                    left.check_num(cursor, None)?
-                        .powf(right.check_num(cursor, None)?),
-                ))),
-                BinaryOp::Equals => Ok(Value::Bool(left.is_equal(&right))),
-                BinaryOp::NotEquals => Ok(Value::Bool(!left.is_equal(&right))),
-                BinaryOp::Greater => Ok(Value::Bool(
-                    left.check_num(cursor, None)? > right.check_num(cursor, None)?,
-                )),
-                BinaryOp::GreaterEquals => Ok(Value::Bool(
-                    left.check_num(cursor, None)? >= right.check_num(cursor, None)?,
-                )),
-                BinaryOp::Lesser => Ok(Value::Bool(
-                    left.check_num(cursor, None)? < right.check_num(cursor, None)?,
-                )),
-                BinaryOp::LesserEquals => Ok(Value::Bool(
-                    left.check_num(cursor, None)? <= right.check_num(cursor, None)?,
-                )),
-                BinaryOp::Nullish => {
-                    if let Value::Null = left {
-                        Ok(right)
-                    } else {
-                        Ok(left)
-                    }
+
+                let ord = res.check_num(cursor, Some("compare() result".into()))?;
+                return Ok(Value::Bool(match op {
+                    BinaryOp::Greater => ord > 0.0,
+                    BinaryOp::GreaterEquals => ord >= 0.0,
+                    BinaryOp::Lesser => ord < 0.0,
+                    BinaryOp::LesserEquals => ord <= 0.0,
+                    _ => unreachable!(),
+                }));
+            }
+        }
+
+        if let Some(res) = left.num_op(op, &right, cursor) {
+            return res;
+        }
+
+        match op {
+            BinaryOp::Add => {
+                if let (Value::Str(ls), Value::ObjInstance(_)) = (&left, &right) {
+                    let rs = self.stringify(&right, cursor)?;
+                    Ok(Value::Str(ls.append(&rs)))
+                } else if let (Value::Str(ls), Value::Str(rs)) = (left, right) {
+                    Ok(Value::Str(ls.append(&rs.borrow())))
+                } else {
+                    Ok(Value::Null)
                 }
-            };
+            }
+            BinaryOp::Sub
+            | BinaryOp::Mult
+            | BinaryOp::Div
+            | BinaryOp::FloorDiv
+            | BinaryOp::Mod
+            | BinaryOp::Pow
+            | BinaryOp::Greater
+            | BinaryOp::GreaterEquals
+            | BinaryOp::Lesser
+            | BinaryOp::LesserEquals => {
+                // not both numbers, reports the first operand that isn't one
+                left.check_num(cursor, None)?;
+                right.check_num(cursor, None)?;
+                unreachable!("numbers are handled by Value::num_op")
+            }
+            BinaryOp::BitAnd
+            | BinaryOp::BitOr
+            | BinaryOp::BitXor
+            | BinaryOp::ShiftLeft
+            | BinaryOp::ShiftRight => {
+                left.check_int(cursor, None)?;
+                right.check_int(cursor, None)?;
+                unreachable!("numbers are handled by Value::num_op")
+            }
+            BinaryOp::Equals => Ok(Value::Bool(self.values_equal(&left, &right, cursor)?)),
+            BinaryOp::NotEquals => Ok(Value::Bool(!self.values_equal(&left, &right, cursor)?)),
+            BinaryOp::Nullish => {
+                if let Value::Null = left {
+                    Ok(right)
+                } else {
+                    Ok(left)
+                }
+            }
         }
-        unreachable!("Non-binary passed to Evaluator::eval_expr_binary");
     }
 
     // Utility functions
 
+    /// Structural equality that compares two instances through the left one's `equals(self, other)`
+    /// method when its object defines one, and by identity otherwise.
+    pub fn values_equal(
+        &mut self,
+        left: &Value,
+        right: &Value,
+        cursor: Cursor,
+    ) -> EvalResult<bool> {
+        left.eq_with(right, &mut Vec::new(), &mut |inst, other| {
+            let args = vec![Value::ObjInstance(other.clone())];
+            if let Some(res) = self.call_operator(inst, "equals", args, cursor)? {
+                return Ok(res.is_truthy());
+            }
+            Ok(Rc::ptr_eq(inst, other))
+        })
+    }
+
+    /// Converts a value to its display string, using the `to_str(self)` method of instances whose
+    /// object defines one.
+    pub fn stringify(&mut self, val: &Value, cursor: Cursor) -> EvalResult<String> {
+        val.display_with(&mut Vec::new(), &mut |inst| match self.call_operator(
+            inst,
+            "to_str",
+            vec![],
+            cursor,
+        )? {
+            Some(Value::Str(s)) => Ok(s.borrow().to_string()),
+            Some(other) => Err(RuntimeEvent::error(
+                ErrKind::Type,
+                format!(
+                    "to_str() of object {} must return an Str, found {}",
+                    inst.borrow().obj.name,
+                    other.get_type()
+                ),
+                cursor,
+            )),
+            None => Ok(inst.borrow().to_string()),
+        })
+    }
+
+    /// Calls the operator method `name` on an instance, returning `None` if its object
+    /// doesn't define one.
+    pub fn call_operator(
+        &mut self,
+        inst: &Rc<RefCell<Instance>>,
+        name: &str,
+        args: Vec<Value>,
+        cursor: Cursor,
+    ) -> EvalResult<Option<Value>> {
+        let method = inst.borrow().obj.find_method(name);
+        let Some(method) = method.filter(|m| m.get_bound()) else {
+            return Ok(None);
+        };
+
+        let callable = method.bind(Value::ObjInstance(inst.clone())).get_callable();
+        if !callable.arity().accepts(args.len()) {
+            return Err(RuntimeEvent::error(
+                ErrKind::Arity,
+                format!(
+                    "operator method '{}' of object {} expects {} arguments but takes {}",
+                    name,
+                    inst.borrow().obj.name,
+                    args.len(),
+                    callable.arity()
+                ),
+                cursor,
+            ));
+        }
+        self.push_frame(name, cursor)?;
+        let res = callable.call(self, args, cursor);
+        self.pop_frame(res).map(Some)
+    }
+
+    /// Like `call_operator`, but errors if the object doesn't define the method.
+    pub fn operator_method(
+        &mut self,
+        inst: &Rc<RefCell<Instance>>,
+        name: &str,
+        args: Vec<Value>,
+        cursor: Cursor,
+    ) -> EvalResult<Value> {
+        match self.call_operator(inst, name, args, cursor)? {
+            Some(res) => Ok(res),
+            None => Err(RuntimeEvent::error(
+                ErrKind::Type,
+                format!(
+                    "object {} doesn't define operator method '{}'",
+                    inst.borrow().obj.name,
+                    name
+                ),
+                cursor,
+            )),
+        }
+    }
+
+    /// Materializes a range used as an index into the List of indices it selects, open-ended
+    /// ranges run until the end of the indexed List, Str or Range.
+    fn range_indices(&self, range: &Range, base: &Value, cursor: Cursor) -> EvalResult<Value> {
+        let range = match base {
+            Value::List(items) => range.bounded_to(items.borrow().len()),
+            Value::Str(s) => range.bounded_to(s.borrow().chars().count()),
+            Value::Range(r) => match r.count() {
+                Some(len) => range.bounded_to(len),
+                None => range.clone(),
+            },
+            _ => range.clone(),
+        };
+        Ok(Value::List(Rc::new(RefCell::new(range.to_list(cursor)?))))
+    }
+
+    /// Checks an index into a List or Str of `len` items, it must be an Int or a Num without a
+    /// fraction.
+    fn seq_index(
+        index: &Value,
+        kind: &str,
+        len: usize,
+        cursor: Cursor,
+        index_cursor: Cursor,
+    ) -> EvalResult<usize> {
+        let Some(i) = index.as_index() else {
+            return Err(RuntimeEvent::error(
+                ErrKind::Type,
+                format!("{} index must be an Int or List of Ints", kind),
+                index_cursor,
+            ));
+        };
+        if i >= len {
+            return Err(RuntimeEvent::error(
+                ErrKind::Value,
+                format!("{} index {} out of bounds (len = {})", kind, index, len),
+                cursor,
+            ));
+        }
+        Ok(i)
+    }
+
+    fn static_method(&self, obj: &Object, name: &str, cursor: Cursor) -> EvalResult<Value> {
+        if let Some(method) = obj.find_method(name) {
+            if method.get_bound() {
+                return Err(RuntimeEvent::error(
+                    ErrKind::Name,
+                    format!(
+                        "can't call bound method '{}' of object '{}' without an instance",
+                        name, obj.name
+                    ),
+                    cursor,
+                ));
+            }
+
+            // Mirror the object scope the resolver expects between the closure and params.
+            if let Method::User(func) = method {
+                let env = Env::enclosed(func.closure.clone());
+                return Ok(Value::Callable(Rc::new(Function::new(
+                    func.declr, env, func.bound, func.file,
+                ))));
+            }
+            return Ok(Value::Callable(method.get_callable()));
+        }
+        Err(RuntimeEvent::error(
+            ErrKind::Name,
+            format!("static method '{}' undefined in object {}", name, obj.name),
+            cursor,
+        ))
+    }
+
     pub fn lookup_var(&self, name: &str, expr: &Expr) -> EvalResult<Value> {
-        if let Some(d) = expr.get_resolved_dist() {
-            Env::get_at(&self.env.clone(), name, d, expr.cursor)
-        } else {
-            self.env.borrow().get(name, expr.cursor)
+        if let Some((dist, slot)) = expr.get_resolved() {
+            match Env::get_at(&self.env, dist, slot) {
+                Some(val) => return Ok(val),
+                // late uses see globals until the local they resolved to is declared
+                None if expr.is_late() => {}
+                None => return Err(Env::undefined(name, expr.cursor)),
+            }
+        }
+        self.env.borrow().get(name, expr.cursor)
+    }
+
+    /// Assigns a variable through the same envs `lookup_var` reads it from, so functions of a
+    /// module write the globals of their own script.
+    fn assign_var(&self, name: &str, val: Value, expr: &Expr) -> EvalResult<()> {
+        if let Some((dist, slot)) = expr.get_resolved()
+            && (!expr.is_late() || Env::get_at(&self.env, dist, slot).is_some())
+        {
+            return Env::assign_at(&self.env, name, val, dist, slot, expr.cursor);
         }
+        self.env.borrow_mut().assign(name, val, expr.cursor)
     }
 }
@@ -7,7 +7,7 @@ mod term;
 mod tui;
 
 use std::{
-    cell::RefCell,
+    collections::HashMap,
     io::{self, Write},
     rc::Rc,
     str::FromStr,
@@ -17,7 +17,9 @@ use crate::{
     evaluator::{
         Evaluator,
         env::{Env, EnvPtr},
+        object::{Instance, Method, NativeMethod, Object},
         runtime_err::{ErrKind, EvalResult, RuntimeErr, RuntimeEvent},
+        string::Str,
         value::{Callable, Value},
     },
     native_fn,
@@ -59,17 +61,27 @@ impl Natives {
 
         natives
     }
+
+    /// The object of the structured errors bound by catch clauses.
+    pub fn error_obj() -> Object {
+        let mut methods: HashMap<String, Method> = HashMap::new();
+        methods.insert(
+            "to_str".into(),
+            Method::Native(NativeMethod::new(Rc::new(FnErrorToStr), true)),
+        );
+        Object::new("Error".into(), methods)
+    }
 }
 
 // print(expr)
-native_fn!(FnPrint, "print", 1, |_evaluator, args, _cursor| {
-    print!("{}", args[0]);
+native_fn!(FnPrint, "print", 1, |evaluator, args, cursor| {
+    print!("{}", evaluator.stringify(&args[0], cursor)?);
     Ok(Value::Null)
 });
 
 // println(expr)
-native_fn!(FnPrintln, "println", 1, |_evaluator, args, _cursor| {
-    println!("{}", args[0]);
+native_fn!(FnPrintln, "println", 1, |evaluator, args, cursor| {
+    println!("{}", evaluator.stringify(&args[0], cursor)?);
     Ok(Value::Null)
 });
 
@@ -86,18 +98,29 @@ native_fn!(FnRead, "read", 0, |_evaluator, _args, cursor| {
     io::stdin().read_line(&mut string).map_err(|err| {
         RuntimeEvent::error(ErrKind::IO, format!("failed to read line: {}", err), cursor)
     })?;
-    Ok(Value::Str(Rc::new(RefCell::new(string.trim().to_string()))))
+    Ok(Value::Str(Str::new(string.trim().to_string())))
 });
 
 // err(kind, msg) -> throws a runtime error of given kind
 native_fn!(FnErr, "err", 2, |_evaluator, args, cursor| {
     let kind_str = args[0].check_str(cursor, Some("kind".into()))?;
-    let kind = ErrKind::from_str(kind_str.borrow().as_str())
+    let kind = ErrKind::from_str(&kind_str.borrow())
         .map_err(|_| RuntimeEvent::error(ErrKind::Value, "invalid error kind".into(), cursor))?;
     let msg = args[1].check_str(cursor, Some("message".into()))?;
     Err(RuntimeEvent::Err(RuntimeErr::new(
         kind,
-        msg.borrow().clone(),
+        msg.borrow().to_string(),
         cursor,
     )))
 });
+
+// Error.to_str(self) -> Str
+// the instance is bound as args[0]
+native_fn!(FnErrorToStr, "to_str", 0, |_evaluator, args, cursor| {
+    if let Value::ObjInstance(inst) = &args[0] {
+        let kind = Instance::get_rc(inst.clone(), "kind", None, cursor)?;
+        let msg = Instance::get_rc(inst.clone(), "msg", None, cursor)?;
+        return Ok(Value::Str(Str::new(format!("{}: {}", kind, msg))));
+    }
+    unreachable!("Error.to_str called without an Error instance")
+});
@@ -1,5 +1,29 @@
 #[macro_export]
 macro_rules! native_fn {
+    // parameter names instead of an arity allow passing the arguments by name
+    ($name:ident, $str_name:expr, [$($param:ident),* $(,)?], |$evaluator:ident, $args:ident, $cursor:ident| $body:block) => {
+        #[derive(Debug)]
+        pub struct $name;
+        impl Callable for $name {
+            fn name(&self) -> &str {
+                $str_name
+            }
+            fn arity(&self) -> $crate::evaluator::value::Arity {
+                $crate::evaluator::value::Arity::exact([$(stringify!($param)),*].len())
+            }
+            fn param_names(&self) -> Vec<String> {
+                vec![$(stringify!($param).to_string()),*]
+            }
+            fn call(
+                &self,
+                $evaluator: &mut Evaluator,
+                $args: Vec<Value>,
+                $cursor: $crate::lexer::cursor::Cursor,
+            ) -> EvalResult<Value> {
+                $body
+            }
+        }
+    };
     ($name:ident, $str_name:expr, $arity:expr, |$evaluator:ident, $args:ident, $cursor:ident| $body:block) => {
         #[derive(Debug)]
         pub struct $name;
@@ -7,8 +31,8 @@ macro_rules! native_fn {
             fn name(&self) -> &str {
                 $str_name
             }
-            fn arity(&self) -> usize {
-                $arity
+            fn arity(&self) -> $crate::evaluator::value::Arity {
+                $crate::evaluator::value::Arity::from($arity)
             }
             fn call(
                 &self,
@@ -33,8 +57,8 @@ macro_rules! native_fn_with_data {
             fn name(&self) -> &str {
                 $method_name
             }
-            fn arity(&self) -> usize {
-                $arity
+            fn arity(&self) -> $crate::evaluator::value::Arity {
+                $crate::evaluator::value::Arity::from($arity)
             }
 
             fn call(
@@ -67,8 +91,8 @@ macro_rules! native_fn_with_val {
             fn name(&self) -> &str {
                 $method_name
             }
-            fn arity(&self) -> usize {
-                $arity
+            fn arity(&self) -> $crate::evaluator::value::Arity {
+                $crate::evaluator::value::Arity::from($arity)
             }
 
             fn call(
@@ -14,6 +14,7 @@ use crate::{
         value::Value,
     },
     native_fn,
+    parser::expr::BinaryOp,
 };
 
 const TAU: f64 = PI * 2.0;
@@ -205,11 +206,13 @@ native_fn!(FnMathLog, "log", 2, |_evaluator, args, cursor| {
     Ok(Value::Num(OrderedFloat(value.log(base))))
 });
 
-// pow(base, exp) -> Num
+// pow(base, exp) -> Int | Num: same as `base ** exp`
 native_fn!(FnMathPow, "pow", 2, |_evaluator, args, cursor| {
-    let base = args[0].check_num(cursor, Some("base".into()))?;
-    let exp = args[1].check_num(cursor, Some("exponent".into()))?;
-    Ok(Value::Num(OrderedFloat(base.powf(exp))))
+    args[0].check_num(cursor, Some("base".into()))?;
+    args[1].check_num(cursor, Some("exponent".into()))?;
+    args[0]
+        .num_op(&BinaryOp::Pow, &args[1], cursor)
+        .expect("both operands are numbers")
 });
 
 // hypot(a, b) -> Num
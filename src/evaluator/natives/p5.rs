@@ -536,101 +536,121 @@ fn ensure_callable(value: &Value, cursor: Cursor, label: &str) -> EvalResult<Rc<
     }
 }
 
-native_fn!(FnP5Rect, "p5_rect", 4, |_evaluator, args, cursor| {
-    let x = clamp_to_usize(args[0].check_num(cursor, Some("x".into()))?);
-    let y = clamp_to_usize(args[1].check_num(cursor, Some("y".into()))?);
-    let w = clamp_to_usize(args[2].check_num(cursor, Some("width".into()))?);
-    let h = clamp_to_usize(args[3].check_num(cursor, Some("height".into()))?);
-
-    if w == 0 || h == 0 {
-        return Ok(Value::Null);
-    }
+native_fn!(
+    FnP5Rect,
+    "p5_rect",
+    [x, y, w, h],
+    |_evaluator, args, cursor| {
+        let x = clamp_to_usize(args[0].check_num(cursor, Some("x".into()))?);
+        let y = clamp_to_usize(args[1].check_num(cursor, Some("y".into()))?);
+        let w = clamp_to_usize(args[2].check_num(cursor, Some("width".into()))?);
+        let h = clamp_to_usize(args[3].check_num(cursor, Some("height".into()))?);
 
-    let runtime = get_runtime(cursor)?;
-    {
-        let state = runtime.state();
-        let mut lock = state.lock().unwrap();
-        if !lock.open {
-            return Err(RuntimeEvent::error(
-                ErrKind::Value,
-                "P5 window is closed; call P5.run() first".into(),
-                cursor,
-            ));
-        }
-        let clamped_x = x.min(lock.width);
-        let clamped_y = y.min(lock.height);
-        let clamped_w = w.min(lock.width.saturating_sub(clamped_x));
-        let clamped_h = h.min(lock.height.saturating_sub(clamped_y));
-        if clamped_w == 0 || clamped_h == 0 {
+        if w == 0 || h == 0 {
             return Ok(Value::Null);
         }
 
-        lock.draw_rect(
-            clamped_x as f32,
-            clamped_y as f32,
-            clamped_w as f32,
-            clamped_h as f32,
-        );
-    }
-    Ok(Value::Null)
-});
+        let runtime = get_runtime(cursor)?;
+        {
+            let state = runtime.state();
+            let mut lock = state.lock().unwrap();
+            if !lock.open {
+                return Err(RuntimeEvent::error(
+                    ErrKind::Value,
+                    "P5 window is closed; call P5.run() first".into(),
+                    cursor,
+                ));
+            }
+            let clamped_x = x.min(lock.width);
+            let clamped_y = y.min(lock.height);
+            let clamped_w = w.min(lock.width.saturating_sub(clamped_x));
+            let clamped_h = h.min(lock.height.saturating_sub(clamped_y));
+            if clamped_w == 0 || clamped_h == 0 {
+                return Ok(Value::Null);
+            }
 
-native_fn!(FnP5Circle, "p5_circle", 3, |_evaluator, args, cursor| {
-    let x = args[0].check_num(cursor, Some("center x".into()))?;
-    let y = args[1].check_num(cursor, Some("center y".into()))?;
-    let diameter = args[2].check_num(cursor, Some("diameter".into()))?;
-    if diameter <= 0.0 {
-        return Ok(Value::Null);
-    }
-    let runtime = get_runtime(cursor)?;
-    {
-        let state = runtime.state();
-        let mut lock = state.lock().unwrap();
-        lock.draw_circle(x as f32, y as f32, diameter as f32);
+            lock.draw_rect(
+                clamped_x as f32,
+                clamped_y as f32,
+                clamped_w as f32,
+                clamped_h as f32,
+            );
+        }
+        Ok(Value::Null)
     }
-    Ok(Value::Null)
-});
+);
 
-native_fn!(FnP5Ellipse, "p5_ellipse", 4, |_evaluator, args, cursor| {
-    let x = args[0].check_num(cursor, Some("center x".into()))?;
-    let y = args[1].check_num(cursor, Some("center y".into()))?;
-    let width = args[2].check_num(cursor, Some("width".into()))?;
-    let height = args[3].check_num(cursor, Some("height".into()))?;
-    if width <= 0.0 || height <= 0.0 {
-        return Ok(Value::Null);
+native_fn!(
+    FnP5Circle,
+    "p5_circle",
+    [x, y, d],
+    |_evaluator, args, cursor| {
+        let x = args[0].check_num(cursor, Some("center x".into()))?;
+        let y = args[1].check_num(cursor, Some("center y".into()))?;
+        let diameter = args[2].check_num(cursor, Some("diameter".into()))?;
+        if diameter <= 0.0 {
+            return Ok(Value::Null);
+        }
+        let runtime = get_runtime(cursor)?;
+        {
+            let state = runtime.state();
+            let mut lock = state.lock().unwrap();
+            lock.draw_circle(x as f32, y as f32, diameter as f32);
+        }
+        Ok(Value::Null)
     }
-    let runtime = get_runtime(cursor)?;
-    {
-        let state = runtime.state();
-        let mut lock = state.lock().unwrap();
-        lock.draw_oval(
-            x as f32,
-            y as f32,
-            (width / 2.0) as f32,
-            (height / 2.0) as f32,
-        );
+);
+
+native_fn!(
+    FnP5Ellipse,
+    "p5_ellipse",
+    [x, y, w, h],
+    |_evaluator, args, cursor| {
+        let x = args[0].check_num(cursor, Some("center x".into()))?;
+        let y = args[1].check_num(cursor, Some("center y".into()))?;
+        let width = args[2].check_num(cursor, Some("width".into()))?;
+        let height = args[3].check_num(cursor, Some("height".into()))?;
+        if width <= 0.0 || height <= 0.0 {
+            return Ok(Value::Null);
+        }
+        let runtime = get_runtime(cursor)?;
+        {
+            let state = runtime.state();
+            let mut lock = state.lock().unwrap();
+            lock.draw_oval(
+                x as f32,
+                y as f32,
+                (width / 2.0) as f32,
+                (height / 2.0) as f32,
+            );
+        }
+        Ok(Value::Null)
     }
-    Ok(Value::Null)
-});
+);
 
-native_fn!(FnP5Line, "p5_line", 4, |_evaluator, args, cursor| {
-    let x1 = args[0].check_num(cursor, Some("x1".into()))?;
-    let y1 = args[1].check_num(cursor, Some("y1".into()))?;
-    let x2 = args[2].check_num(cursor, Some("x2".into()))?;
-    let y2 = args[3].check_num(cursor, Some("y2".into()))?;
-    let runtime = get_runtime(cursor)?;
-    {
-        let state = runtime.state();
-        let mut lock = state.lock().unwrap();
-        lock.draw_line(x1 as f32, y1 as f32, x2 as f32, y2 as f32);
+native_fn!(
+    FnP5Line,
+    "p5_line",
+    [x1, y1, x2, y2],
+    |_evaluator, args, cursor| {
+        let x1 = args[0].check_num(cursor, Some("x1".into()))?;
+        let y1 = args[1].check_num(cursor, Some("y1".into()))?;
+        let x2 = args[2].check_num(cursor, Some("x2".into()))?;
+        let y2 = args[3].check_num(cursor, Some("y2".into()))?;
+        let runtime = get_runtime(cursor)?;
+        {
+            let state = runtime.state();
+            let mut lock = state.lock().unwrap();
+            lock.draw_line(x1 as f32, y1 as f32, x2 as f32, y2 as f32);
+        }
+        Ok(Value::Null)
     }
-    Ok(Value::Null)
-});
+);
 
 native_fn!(
     FnP5Background,
     "p5_background",
-    3,
+    [r, g, b],
     |_evaluator, args, cursor| {
         let r = args[0].check_num(cursor, Some("red".into()))?;
         let g = args[1].check_num(cursor, Some("green".into()))?;
@@ -646,31 +666,41 @@ native_fn!(
     }
 );
 
-native_fn!(FnP5Fill, "p5_fill", 3, |_evaluator, args, cursor| {
-    let r = args[0].check_num(cursor, Some("red".into()))?;
-    let g = args[1].check_num(cursor, Some("green".into()))?;
-    let b = args[2].check_num(cursor, Some("blue".into()))?;
-    let color = color_from_rgb(r, g, b);
-    let runtime = get_runtime(cursor)?;
-    {
-        let state = runtime.state();
-        state.lock().unwrap().fill_color = Some(color);
+native_fn!(
+    FnP5Fill,
+    "p5_fill",
+    [r, g, b],
+    |_evaluator, args, cursor| {
+        let r = args[0].check_num(cursor, Some("red".into()))?;
+        let g = args[1].check_num(cursor, Some("green".into()))?;
+        let b = args[2].check_num(cursor, Some("blue".into()))?;
+        let color = color_from_rgb(r, g, b);
+        let runtime = get_runtime(cursor)?;
+        {
+            let state = runtime.state();
+            state.lock().unwrap().fill_color = Some(color);
+        }
+        Ok(Value::Null)
     }
-    Ok(Value::Null)
-});
+);
 
-native_fn!(FnP5Stroke, "p5_stroke", 3, |_evaluator, args, cursor| {
-    let r = args[0].check_num(cursor, Some("red".into()))?;
-    let g = args[1].check_num(cursor, Some("green".into()))?;
-    let b = args[2].check_num(cursor, Some("blue".into()))?;
-    let color = color_from_rgb(r, g, b);
-    let runtime = get_runtime(cursor)?;
-    {
-        let state = runtime.state();
-        state.lock().unwrap().stroke_color = Some(color);
+native_fn!(
+    FnP5Stroke,
+    "p5_stroke",
+    [r, g, b],
+    |_evaluator, args, cursor| {
+        let r = args[0].check_num(cursor, Some("red".into()))?;
+        let g = args[1].check_num(cursor, Some("green".into()))?;
+        let b = args[2].check_num(cursor, Some("blue".into()))?;
+        let color = color_from_rgb(r, g, b);
+        let runtime = get_runtime(cursor)?;
+        {
+            let state = runtime.state();
+            state.lock().unwrap().stroke_color = Some(color);
+        }
+        Ok(Value::Null)
     }
-    Ok(Value::Null)
-});
+);
 
 native_fn!(FnP5NoFill, "p5_no_fill", 0, |_evaluator, _args, cursor| {
     let runtime = get_runtime(cursor)?;
@@ -717,7 +747,7 @@ native_fn!(
     }
 );
 
-native_fn!(FnP5Size, "p5_size", 2, |_evaluator, args, cursor| {
+native_fn!(FnP5Size, "p5_size", [w, h], |_evaluator, args, cursor| {
     let width = convert_len(
         args[0].check_num(cursor, Some("width".into()))?,
         "width",
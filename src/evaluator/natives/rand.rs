@@ -1,4 +1,4 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{collections::HashMap, rc::Rc};
 
 use ordered_float::OrderedFloat;
 use rand::Rng;
@@ -6,8 +6,10 @@ use rand::Rng;
 use crate::{
     evaluator::{
         Callable, EvalResult, Evaluator,
+        int::Int,
         object::{Method, NativeMethod, Object},
         runtime_err::{ErrKind, RuntimeEvent},
+        string::Str,
         value::Value,
     },
     native_fn,
@@ -100,7 +102,7 @@ native_fn!(FnRandString, "string", 1, |_evaluator, args, cursor| {
             RAND_STRING_CHARSET[idx] as char
         })
         .collect();
-    Ok(Value::Str(Rc::new(RefCell::new(result))))
+    Ok(Value::Str(Str::new(result)))
 });
 
 // rand_range(min: Num, max: Num) -> Num
@@ -119,19 +121,21 @@ native_fn!(FnRandRange, "range", 2, |_evaluator, args, cursor| {
     Ok(Value::Num(OrderedFloat(value)))
 });
 
-// rand_int(min: Num, max: Num) -> Num (integer)
+// rand_int(min: Int, max: Int) -> Int
 native_fn!(FnRandInt, "int", 2, |_evaluator, args, cursor| {
-    let min_raw = args[0].check_num(cursor, Some("min value".into()))?;
-    let max_raw = args[1].check_num(cursor, Some("max value".into()))?;
-    if (min_raw.fract()).abs() > f64::EPSILON || (max_raw.fract()).abs() > f64::EPSILON {
-        return Err(RuntimeEvent::error(
-            ErrKind::Value,
-            "Rand.int expects integer bounds".into(),
-            cursor,
-        ));
-    }
-    let min = min_raw as i64;
-    let max = max_raw as i64;
+    let bound = |val: &Value, name: &str| {
+        val.check_int(cursor, Some(name.into()))?
+            .to_i64()
+            .ok_or_else(|| {
+                RuntimeEvent::error(
+                    ErrKind::Value,
+                    "Rand.int bounds must fit in 64 bits".into(),
+                    cursor,
+                )
+            })
+    };
+    let min = bound(&args[0], "min value")?;
+    let max = bound(&args[1], "max value")?;
     if max < min {
         return Err(RuntimeEvent::error(
             ErrKind::Value,
@@ -145,5 +149,5 @@ native_fn!(FnRandInt, "int", 2, |_evaluator, args, cursor| {
     } else {
         rng.random_range(min..=max)
     };
-    Ok(Value::Num(OrderedFloat(value as f64)))
+    Ok(Value::Int(Int::from(value)))
 });
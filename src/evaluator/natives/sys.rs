@@ -6,13 +6,13 @@ use std::{
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
-use ordered_float::OrderedFloat;
-
 use crate::{
     evaluator::{
         Callable, EvalResult, Evaluator,
+        int::Int,
         object::{Method, NativeMethod, Object},
         runtime_err::{ErrKind, RuntimeEvent},
+        string::Str,
         value::Value,
     },
     native_fn,
@@ -50,13 +50,15 @@ native_fn!(FnSysClock, "sys_clock", 0, |_evaluator, _args, _cursor| {
     let from_epoch = start
         .duration_since(UNIX_EPOCH)
         .expect("time should go forward");
-    Ok(Value::Num(OrderedFloat(from_epoch.as_millis() as f64)))
+    Ok(Value::Int(Int::from(from_epoch.as_millis() as i64)))
 });
 
 // sleep(ms: Num)
 native_fn!(FnSysSleep, "sys_sleep", 1, |_evaluator, args, _cursor| {
-    if let Value::Num(millis) = args[0] {
-        thread::sleep(Duration::from_millis(millis.0 as u64));
+    match &args[0] {
+        Value::Int(millis) => thread::sleep(Duration::from_millis(millis.to_f64() as u64)),
+        Value::Num(millis) => thread::sleep(Duration::from_millis(millis.0 as u64)),
+        _ => {}
     }
     Ok(Value::Null)
 });
@@ -64,9 +66,9 @@ native_fn!(FnSysSleep, "sys_sleep", 1, |_evaluator, args, _cursor| {
 // env(name: Str) -> Str | Null
 native_fn!(FnSysEnv, "sys_env", 1, |_evaluator, args, cursor| {
     let name_rc = args[0].check_str(cursor, Some("environment variable name".into()))?;
-    let key = name_rc.borrow().clone();
+    let key = name_rc.borrow().to_string();
     match std::env::var(&key) {
-        Ok(val) => Ok(Value::Str(Rc::new(RefCell::new(val)))),
+        Ok(val) => Ok(Value::Str(Str::new(val))),
         Err(_) => Ok(Value::Null),
     }
 });
@@ -74,7 +76,7 @@ native_fn!(FnSysEnv, "sys_env", 1, |_evaluator, args, cursor| {
 // args() -> List<Str>
 native_fn!(FnSysArgs, "sys_args", 0, |_evaluator, _args, _cursor| {
     let values = std::env::args()
-        .map(|arg| Value::Str(Rc::new(RefCell::new(arg))))
+        .map(|arg| Value::Str(Str::new(arg)))
         .collect::<Vec<Value>>();
     Ok(Value::List(Rc::new(RefCell::new(values))))
 });
@@ -88,7 +90,5 @@ native_fn!(FnSysCwd, "sys_cwd", 0, |_evaluator, _args, cursor| {
             cursor,
         )
     })?;
-    Ok(Value::Str(Rc::new(RefCell::new(
-        cwd.to_string_lossy().to_string(),
-    ))))
+    Ok(Value::Str(Str::new(cwd.to_string_lossy().to_string())))
 });
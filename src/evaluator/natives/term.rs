@@ -9,7 +9,9 @@ use std::{
 use crate::{
     evaluator::{
         Callable, EvalResult, Evaluator,
+        int::Int,
         object::{Method, NativeMethod, Object},
+        string::Str,
         value::Value,
     },
     native_fn, native_fn_with_data, native_fn_with_val,
@@ -21,7 +23,6 @@ use crossterm::{
     execute,
     terminal::{Clear, ClearType, SetTitle, disable_raw_mode, enable_raw_mode},
 };
-use ordered_float::OrderedFloat;
 
 pub fn native_term() -> Value {
     let mut methods: HashMap<String, Method> = HashMap::new();
@@ -91,8 +92,8 @@ native_fn!(
         let (cols, rows) = crossterm::terminal::size()?;
 
         Ok(Value::List(Rc::new(RefCell::new(vec![
-            Value::Num(OrderedFloat(cols as f64)),
-            Value::Num(OrderedFloat(rows as f64)),
+            Value::Int(Int::from(cols as i64)),
+            Value::Int(Int::from(rows as i64)),
         ]))))
     }
 );
@@ -184,7 +185,7 @@ native_fn_with_data!(
     KeyInputData,
     |_evaluator, _args, _cursor, data| {
         let d = data.borrow();
-        Ok(Value::Str(Rc::new(RefCell::new(d.key.clone()))))
+        Ok(Value::Str(Str::new(d.key.clone())))
     }
 );
 
@@ -240,14 +241,14 @@ native_fn!(
     "terminal_cursor_move",
     2,
     |_evaluator, args, _cursor| {
-        let x = if let Value::Num(n) = args[0] {
-            n.0 as u16
+        let x = if let Some(n) = args[0].as_index() {
+            n as u16
         } else {
             return Ok(Value::Null);
         };
 
-        let y = if let Value::Num(n) = args[1] {
-            n.0 as u16
+        let y = if let Some(n) = args[1].as_index() {
+            n as u16
         } else {
             return Ok(Value::Null);
         };
@@ -309,20 +310,20 @@ native_fn!(
 
 // Term.put(x, y, str): puts string at position without moving cursor after
 native_fn!(FnTermPut, "terminal_put", 3, |_evaluator, args, _cursor| {
-    let x = if let Value::Num(n) = args[0] {
-        n.0 as u16
+    let x = if let Some(n) = args[0].as_index() {
+        n as u16
     } else {
         return Ok(Value::Null);
     };
 
-    let y = if let Value::Num(n) = args[1] {
-        n.0 as u16
+    let y = if let Some(n) = args[1].as_index() {
+        n as u16
     } else {
         return Ok(Value::Null);
     };
 
     let s = match &args[2] {
-        Value::Str(s) => s.borrow().clone(),
+        Value::Str(s) => s.borrow().to_string(),
         _ => " ".to_string(),
     };
 
@@ -340,7 +341,7 @@ native_fn!(
     1,
     |_evaluator, args, _cursor| {
         let s = match &args[0] {
-            Value::Str(s) => s.borrow().clone(),
+            Value::Str(s) => s.borrow().to_string(),
             other => other.to_string(),
         };
 
@@ -358,7 +359,7 @@ native_fn!(
     1,
     |_evaluator, args, _cursor| {
         if let Value::Str(s) = &args[0] {
-            execute!(io::stdout(), SetTitle(s.borrow().as_str()))?;
+            execute!(io::stdout(), SetTitle(&*s.borrow()))?;
         }
         Ok(Value::Null)
     }
@@ -1,12 +1,12 @@
 mod canvas;
 mod text_input;
 
-use ordered_float::OrderedFloat;
 use std::{cell::RefCell, collections::HashMap, io, rc::Rc};
 
 use crate::{
     evaluator::{
         Callable, ErrKind, EvalResult, Evaluator, RuntimeEvent,
+        int::Int,
         natives::tui::{
             canvas::{CanvasWidget, FnTuiCreateCanvas, render_canvas},
             text_input::{FnTuiCreateTextInput, TextInputWidget, render_text_input},
@@ -632,7 +632,7 @@ native_fn!(
 native_fn!(
     FnTuiDrawBlock,
     "tui_draw_block",
-    6,
+    [x, y, width, height, title, border_color],
     |_evaluator, args, cursor| {
         let x = args[0].check_num(cursor, Some("x position".into()))? as u16;
         let y = args[1].check_num(cursor, Some("y position".into()))? as u16;
@@ -661,7 +661,7 @@ native_fn!(
 native_fn!(
     FnTuiDrawBlockRect,
     "tui_draw_block_rect",
-    3,
+    [rect_id, title, border_color],
     |_evaluator, args, cursor| {
         let rect_id = args[0].check_num(cursor, Some("rect id".into()))? as usize;
         let title = string_from_value(&args[1]);
@@ -683,7 +683,7 @@ native_fn!(
 native_fn!(
     FnTuiDrawText,
     "tui_draw_text",
-    7,
+    [x, y, width, height, text, fg_color, bg_color],
     |_evaluator, args, cursor| {
         let x = args[0].check_num(cursor, Some("x position".into()))? as u16;
         let y = args[1].check_num(cursor, Some("y position".into()))? as u16;
@@ -712,7 +712,7 @@ native_fn!(
 native_fn!(
     FnTuiDrawTextRect,
     "tui_draw_text_rect",
-    4,
+    [rect_id, text, fg_color, bg_color],
     |_evaluator, args, cursor| {
         let rect_id = args[0].check_num(cursor, Some("rect id".into()))? as usize;
         let text = string_from_value(&args[1]);
@@ -735,7 +735,7 @@ native_fn!(
 native_fn!(
     FnTuiDrawList,
     "tui_draw_list",
-    8,
+    [x, y, width, height, items, selected, color, title],
     |_evaluator, args, cursor| {
         let x = args[0].check_num(cursor, Some("x".into()))? as u16;
         let y = args[1].check_num(cursor, Some("y".into()))? as u16;
@@ -782,7 +782,7 @@ native_fn!(
 native_fn!(
     FnTuiDrawCheckbox,
     "tui_draw_checkbox",
-    7,
+    [x, y, label, checked, fg_color, bg_color, accent_color],
     |_evaluator, args, cursor| {
         let x = args[0].check_num(cursor, Some("x position".into()))? as u16;
         let y = args[1].check_num(cursor, Some("y position".into()))? as u16;
@@ -810,7 +810,7 @@ native_fn!(
 native_fn!(
     FnTuiDrawProgress,
     "tui_draw_progress",
-    6,
+    [x, y, width, percent, label, color],
     |_evaluator, args, cursor| {
         let x = args[0].check_num(cursor, Some("x".into()))? as u16;
         let y = args[1].check_num(cursor, Some("y".into()))? as u16;
@@ -841,7 +841,7 @@ native_fn!(
 native_fn!(
     FnTuiDrawListRect,
     "tui_draw_list_rect",
-    5,
+    [rect_id, items, selected, color, title],
     |_evaluator, args, cursor| {
         let rect_id = args[0].check_num(cursor, Some("rect id".into()))? as usize;
 
@@ -881,7 +881,7 @@ native_fn!(
 native_fn!(
     FnTuiDrawProgressRect,
     "tui_draw_progress_rect",
-    4,
+    [rect_id, percent, label, color],
     |_evaluator, args, cursor| {
         let rect_id = args[0].check_num(cursor, Some("rect id".into()))? as usize;
         let percent = args[1]
@@ -903,11 +903,11 @@ native_fn!(
     }
 );
 
-// Tui.draw_checkbox_rect(rect_id, label, checked, fg, bg, accent)
+// Tui.draw_checkbox_rect(rect_id, label, checked, fg_color, bg_color, accent_color)
 native_fn!(
     FnTuiDrawCheckboxRect,
     "tui_draw_checkbox_rect",
-    6,
+    [rect_id, label, checked, fg_color, bg_color, accent_color],
     |_evaluator, args, cursor| {
         let rect_id = args[0].check_num(cursor, Some("rect id".into()))? as usize;
         let label = string_from_value(&args[1]);
@@ -975,7 +975,7 @@ native_fn!(
         });
 
         let rect_ids: Vec<Value> = (start..start + count)
-            .map(|id| Value::Num(OrderedFloat(id as f64)))
+            .map(|id| Value::Int(Int::from(id)))
             .collect();
         Ok(Value::List(Rc::new(RefCell::new(rect_ids))))
     }
@@ -1006,7 +1006,7 @@ native_fn!(
         });
 
         let rect_ids: Vec<Value> = (start..start + count)
-            .map(|id| Value::Num(OrderedFloat(id as f64)))
+            .map(|id| Value::Int(Int::from(id)))
             .collect();
         Ok(Value::List(Rc::new(RefCell::new(rect_ids))))
     }
@@ -1037,7 +1037,7 @@ pub fn parse_color(s: &str) -> Color {
 
 fn string_from_value(value: &Value) -> String {
     match value {
-        Value::Str(s) => s.borrow().clone(),
+        Value::Str(s) => s.borrow().to_string(),
         _ => String::new(),
     }
 }
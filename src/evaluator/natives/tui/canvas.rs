@@ -325,10 +325,12 @@ native_fn_with_data!(
                             return Ok(Value::Null);
                         }
                         let x = match &pair_ref[0] {
+                            Value::Int(n) => n.to_f64(),
                             Value::Num(n) => n.0,
                             _ => return Ok(Value::Null),
                         };
                         let y = match &pair_ref[1] {
+                            Value::Int(n) => n.to_f64(),
                             Value::Num(n) => n.0,
                             _ => return Ok(Value::Null),
                         };
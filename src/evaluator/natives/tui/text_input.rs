@@ -8,7 +8,7 @@ use crate::{
 
 use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
-use crate::evaluator::{Callable, EvalResult, Evaluator, value::Value};
+use crate::evaluator::{Callable, EvalResult, Evaluator, string::Str, value::Value};
 use ratatui::{
     Frame,
     layout::Rect,
@@ -118,7 +118,7 @@ native_fn!(
 
 fn string_from_value(value: &Value) -> String {
     match value {
-        Value::Str(s) => s.borrow().clone(),
+        Value::Str(s) => s.borrow().to_string(),
         _ => String::new(),
     }
 }
@@ -144,7 +144,7 @@ native_fn_with_data!(
     TextInputData,
     |_evaluator, _args, _cursor, data| {
         let d = data.borrow();
-        Ok(Value::Str(Rc::new(RefCell::new(d.content.clone()))))
+        Ok(Value::Str(Str::new(d.content.clone())))
     }
 );
 
@@ -155,7 +155,7 @@ native_fn_with_data!(
     TextInputData,
     |_evaluator, args, _cursor, data| {
         let text = match &args[0] {
-            Value::Str(s) => s.borrow().clone(),
+            Value::Str(s) => s.borrow().to_string(),
             _ => return Ok(Value::Null),
         };
 
@@ -174,7 +174,7 @@ native_fn_with_data!(
     TextInputData,
     |_evaluator, args, _cursor, data| {
         let key = match &args[0] {
-            Value::Str(s) => s.borrow().clone(),
+            Value::Str(s) => s.borrow().to_string(),
             _ => return Ok(Value::Null),
         };
 
@@ -2,13 +2,14 @@ use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
 use crate::{
     evaluator::{
+        cache::PropCache,
         env::Env,
         function::Function,
         runtime_err::{ErrKind, EvalResult, RuntimeEvent},
-        value::{Callable, Value},
+        value::{Arity, Callable, Value},
     },
     lexer::cursor::Cursor,
-    parser::stmt::StmtKind,
+    vm::closure::Closure,
 };
 
 #[derive(Debug, Clone)]
@@ -39,7 +40,7 @@ impl Callable for NativeMethod {
         self.callable.name()
     }
 
-    fn arity(&self) -> usize {
+    fn arity(&self) -> Arity {
         self.callable.arity()
     }
 
@@ -54,20 +55,40 @@ impl Callable for NativeMethod {
         }
         self.callable.call(evaluator, args, cursor)
     }
+
+    fn as_closure(&self) -> Option<(&Closure, Option<&Value>)> {
+        self.callable
+            .as_closure()
+            .map(|(closure, _)| (closure, self.bind.as_ref()))
+    }
+
+    fn param_names(&self) -> Vec<String> {
+        self.callable.param_names()
+    }
+
+    fn call_named(
+        &self,
+        evaluator: &mut crate::evaluator::Evaluator,
+        mut args: Vec<Option<Value>>,
+        cursor: Cursor,
+    ) -> EvalResult<Value> {
+        if let Some(bind) = &self.bind {
+            args.insert(0, Some(bind.clone()));
+        }
+        self.callable.call_named(evaluator, args, cursor)
+    }
 }
 
 impl Method {
     pub fn bind(self, val: Value) -> Method {
         if let Value::ObjInstance(_) = val {
             if let Method::User(func) = self {
-                if let StmtKind::Fn { name, bound, .. } = func.declr.kind.clone() {
-                    let env = Env::enclosed(func.closure.clone());
-                    if bound || name == "init" {
-                        env.borrow_mut().define("self".to_string(), val);
-                    }
-                    return Method::User(Function::new(func.declr, env, bound));
+                let bound = func.declr.bound;
+                let env = Env::enclosed(func.closure.clone());
+                if bound || func.declr.name == "init" {
+                    env.borrow_mut().define("self".to_string(), val);
                 }
-                unreachable!();
+                return Method::User(Function::new(func.declr, env, bound, func.file));
             }
 
             if let Method::Native(func) = self {
@@ -100,16 +121,37 @@ impl Method {
 #[derive(Debug, Clone)]
 pub struct Object {
     pub name: String,
-    pub methods: HashMap<String, Method>,
+    /// Shared by the instances of the object, also identifies it in inline caches
+    pub methods: Rc<HashMap<String, Method>>,
+    pub parent: Option<Rc<Object>>,
 }
 
 impl Object {
     pub fn new(name: String, methods: HashMap<String, Method>) -> Self {
-        Self { name, methods }
+        Self {
+            name,
+            methods: Rc::new(methods),
+            parent: None,
+        }
+    }
+
+    pub fn with_parent(name: String, methods: HashMap<String, Method>, parent: Rc<Object>) -> Self {
+        Self {
+            name,
+            methods: Rc::new(methods),
+            parent: Some(parent),
+        }
     }
 
-    fn find_method(&self, name: String) -> Option<Method> {
-        self.methods.get(&name).cloned()
+    /// Find a method on this object, walking up the parent chain.
+    pub fn find_method(&self, name: &str) -> Option<Method> {
+        let method = self.methods.get(name).cloned();
+        if method.is_none()
+            && let Some(parent) = &self.parent
+        {
+            return parent.find_method(name);
+        }
+        method
     }
 }
 
@@ -118,15 +160,15 @@ impl Callable for Object {
         self.name.as_str()
     }
 
-    fn arity(&self) -> usize {
-        if let Some(init) = self.find_method("init".to_string()) {
+    fn arity(&self) -> Arity {
+        if let Some(init) = self.find_method("init") {
             return match init {
                 Method::User(func) => func.arity(),
                 Method::Native(func) => func.arity(),
             };
         }
 
-        0
+        Arity::exact(0)
     }
 
     fn call(
@@ -134,13 +176,30 @@ impl Callable for Object {
         evaluator: &mut super::Evaluator,
         args: Vec<super::value::Value>,
         cursor: Cursor,
+    ) -> EvalResult<Value> {
+        self.call_named(evaluator, args.into_iter().map(Some).collect(), cursor)
+    }
+
+    fn param_names(&self) -> Vec<String> {
+        if let Some(init) = self.find_method("init") {
+            return init.get_callable().param_names();
+        }
+
+        vec![]
+    }
+
+    fn call_named(
+        &self,
+        evaluator: &mut super::Evaluator,
+        args: Vec<Option<Value>>,
+        cursor: Cursor,
     ) -> EvalResult<Value> {
         let inst = Value::ObjInstance(Rc::new(RefCell::new(Instance::new(self.clone()))));
 
-        if let Some(init) = self.find_method("init".to_string()) {
+        if let Some(init) = self.find_method("init") {
             init.bind(inst.clone())
                 .get_callable()
-                .call(evaluator, args, cursor)?;
+                .call_named(evaluator, args, cursor)?;
         }
 
         Ok(inst)
@@ -161,18 +220,24 @@ impl Instance {
         }
     }
 
+    /// Gets a field or a bound method, methods are looked up through the cache if there is one.
     pub fn get_rc(
         inst_rc: Rc<RefCell<Instance>>,
-        name: String,
+        name: &str,
+        cache: Option<&PropCache>,
         cursor: Cursor,
     ) -> EvalResult<Value> {
         let inst_ref = inst_rc.borrow();
 
-        if let Some(val) = inst_ref.fields.get(&name) {
+        if let Some(val) = inst_ref.fields.get(name) {
             return Ok(val.clone());
         }
 
-        if let Some(method) = inst_ref.obj.find_method(name.clone()) {
+        let method = match cache {
+            Some(cache) => cache.method(&inst_ref.obj, name),
+            None => inst_ref.obj.find_method(name),
+        };
+        if let Some(method) = method {
             let bound = method.bind(Value::ObjInstance(inst_rc.clone()));
             return Ok(Value::Callable(bound.get_callable()));
         }
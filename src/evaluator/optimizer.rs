@@ -0,0 +1,423 @@
+use std::rc::Rc;
+
+use crate::{
+    evaluator::{Evaluator, range::Range, value::Value},
+    lexer::cursor::Cursor,
+    parser::{
+        expr::{BinaryOp, Expr, ExprKind, LiteralType, LogicalOp, UnaryOp},
+        pattern::{Pattern, PatternKind},
+        stmt::{FnDecl, Stmt, StmtKind},
+    },
+};
+
+/// Rewrites a resolved AST before it is run: constant expressions and literal ranges are
+/// computed ahead of time, groupings are unwrapped and unreachable statements are dropped.
+/// Expressions that would raise an error are left to fail at runtime.
+pub struct Optimizer;
+
+impl Optimizer {
+    pub fn optimize(ast: Vec<Stmt>) -> Vec<Stmt> {
+        Self::stmts(ast)
+    }
+
+    // Statement functions
+
+    /// Optimizes a list of statements, the ones after a `return`, `break`, `continue` or
+    /// `throw` are dropped.
+    fn stmts(stmts: Vec<Stmt>) -> Vec<Stmt> {
+        let mut out = Vec::with_capacity(stmts.len());
+        for stmt in stmts {
+            let Some(stmt) = Self::stmt(stmt) else {
+                continue;
+            };
+            let jumps = matches!(
+                stmt.kind,
+                StmtKind::Return { .. } | StmtKind::Break | StmtKind::Continue | StmtKind::Throw(_)
+            );
+            out.push(stmt);
+            if jumps {
+                break;
+            }
+        }
+        out
+    }
+
+    /// Optimizes a statement, `None` if it has no effect left.
+    fn stmt(mut stmt: Stmt) -> Option<Stmt> {
+        let cursor = stmt.cursor;
+        match &mut stmt.kind {
+            StmtKind::Expr(expr) | StmtKind::Throw(expr) => Self::expr(expr),
+            StmtKind::Return { val, .. } => {
+                if let Some(val) = val {
+                    Self::expr(val);
+                }
+            }
+            StmtKind::Use { path, .. } => Self::expr(path),
+            StmtKind::Break | StmtKind::Continue => {}
+            StmtKind::Var { init, .. } => {
+                if let Some(init) = init {
+                    Self::expr(init);
+                }
+            }
+            StmtKind::Block(stmts) => *stmts = Self::stmts(std::mem::take(stmts)),
+            StmtKind::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                Self::expr(condition);
+                if let Some(truthy) = Self::truthiness(condition) {
+                    // branches can't declare variables, so they can replace the `if`
+                    let branch = if truthy {
+                        Some(std::mem::replace(
+                            then_branch,
+                            Box::new(Self::empty(cursor)),
+                        ))
+                    } else {
+                        else_branch.take()
+                    };
+                    return branch.and_then(|branch| Self::stmt(*branch));
+                }
+                Self::branch(then_branch);
+                if let Some(else_branch) = else_branch {
+                    Self::branch(else_branch);
+                }
+            }
+            StmtKind::Match {
+                val,
+                arms,
+                else_branch,
+            } => {
+                Self::expr(val);
+                for arm in arms {
+                    Self::pattern(&mut arm.pattern);
+                    if let Some(guard) = &mut arm.guard {
+                        Self::expr(guard);
+                    }
+                    let body = std::mem::replace(&mut arm.body, Self::empty(cursor));
+                    arm.body = Self::stmt(body).unwrap_or_else(|| Self::empty(cursor));
+                }
+                if let Some(else_branch) = else_branch {
+                    Self::branch(else_branch);
+                }
+            }
+            StmtKind::While {
+                declr,
+                condition,
+                step,
+                body,
+            } => {
+                if let Some(declr) = declr {
+                    Self::branch(declr);
+                }
+                Self::expr(condition);
+                if let Some(step) = step {
+                    Self::expr(step);
+                }
+                Self::branch(body);
+            }
+            StmtKind::For { iter, body, .. } => {
+                Self::expr(iter);
+                Self::branch(body);
+            }
+            StmtKind::Try {
+                body,
+                catches,
+                ensure,
+            } => {
+                Self::branch(body);
+                for clause in catches {
+                    Self::branch(&mut clause.body);
+                }
+                if let Some(ensure) = ensure {
+                    Self::branch(ensure);
+                }
+            }
+            StmtKind::Fn(declr) => Self::function(declr),
+            StmtKind::Obj {
+                parent, methods, ..
+            } => {
+                if let Some(parent) = parent {
+                    Self::expr(parent);
+                }
+                for method in methods {
+                    if let StmtKind::Fn(declr) = &mut method.kind {
+                        Self::function(declr);
+                    }
+                }
+            }
+        }
+        Some(stmt)
+    }
+
+    /// Optimizes a nested statement, replacing it with an empty block if nothing is left.
+    fn branch(stmt: &mut Stmt) {
+        let cursor = stmt.cursor;
+        let inner = std::mem::replace(stmt, Self::empty(cursor));
+        *stmt = Self::stmt(inner).unwrap_or_else(|| Self::empty(cursor));
+    }
+
+    fn function(declr: &mut Rc<FnDecl>) {
+        let declr = Rc::make_mut(declr);
+        for param in &mut declr.params {
+            if let Some(default) = &mut param.default {
+                Self::expr(default);
+            }
+        }
+        Self::branch(&mut declr.body);
+    }
+
+    fn empty(cursor: Cursor) -> Stmt {
+        Stmt::new(StmtKind::Block(vec![]), cursor)
+    }
+
+    fn pattern(pattern: &mut Pattern) {
+        match &mut pattern.kind {
+            PatternKind::Value(expr) => Self::expr(expr),
+            PatternKind::List { items, rest } => {
+                for item in items {
+                    Self::pattern(item);
+                }
+                if let Some(rest) = rest {
+                    Self::pattern(rest);
+                }
+            }
+            PatternKind::Dict(entries) => {
+                for (key, value) in entries {
+                    Self::expr(key);
+                    Self::pattern(value);
+                }
+            }
+            PatternKind::Or(alts) => {
+                for alt in alts {
+                    Self::pattern(alt);
+                }
+            }
+            PatternKind::Wildcard | PatternKind::Bind(_) | PatternKind::Type { .. } => {}
+        }
+    }
+
+    // Expression functions
+
+    /// Optimizes an expression in place, folding it if its operands are constant.
+    fn expr(expr: &mut Expr) {
+        let cursor = expr.cursor;
+        let folded = match &mut expr.kind {
+            ExprKind::Literal(_) | ExprKind::Var(_) | ExprKind::ESelf | ExprKind::Super { .. } => {
+                None
+            }
+            ExprKind::Grouping { expr: inner } => {
+                Self::expr(inner);
+                Some(Self::take(inner))
+            }
+            ExprKind::Binary { left, op, right } => {
+                Self::expr(left);
+                Self::expr(right);
+                match (Self::literal(left), Self::literal(right)) {
+                    (Some(l), Some(r)) => Self::fold_binary(l, op, r),
+                    _ => None,
+                }
+                .map(|lit| Expr::new(ExprKind::Literal(lit), cursor))
+            }
+            ExprKind::Unary { op, right } => {
+                Self::expr(right);
+                Self::literal(right)
+                    .and_then(|lit| Self::fold_unary(op, lit))
+                    .map(|lit| Expr::new(ExprKind::Literal(lit), cursor))
+            }
+            ExprKind::Logical { left, op, right } => {
+                Self::expr(left);
+                Self::expr(right);
+                // the result is the left operand unless it lets the right one decide
+                Self::truthiness(left).map(|truthy| match (op, truthy) {
+                    (LogicalOp::Or, true) | (LogicalOp::And, false) => Self::take(left),
+                    _ => Self::take(right),
+                })
+            }
+            ExprKind::Ternary {
+                condition,
+                true_branch,
+                false_branch,
+            } => {
+                Self::expr(condition);
+                Self::expr(true_branch);
+                Self::expr(false_branch);
+                Self::truthiness(condition).map(|truthy| {
+                    if truthy {
+                        Self::take(true_branch)
+                    } else {
+                        Self::take(false_branch)
+                    }
+                })
+            }
+            ExprKind::Range {
+                start,
+                end,
+                inclusive,
+                step,
+            } => {
+                Self::range_bounds(start, end, step);
+                Self::fold_range(start, end.as_deref(), step.as_deref(), *inclusive, cursor)
+                    .map(|range| Expr::new(ExprKind::Literal(LiteralType::Range(range)), cursor))
+            }
+            ExprKind::Interp(parts) | ExprKind::List(parts) => {
+                for part in parts {
+                    Self::expr(part);
+                }
+                None
+            }
+            ExprKind::Dict(entries) => {
+                for (key, value) in entries {
+                    Self::expr(key);
+                    Self::expr(value);
+                }
+                None
+            }
+            ExprKind::Assign { val, .. } => {
+                Self::expr(val);
+                None
+            }
+            ExprKind::Call {
+                callee,
+                args,
+                named,
+            } => {
+                Self::expr(callee);
+                for arg in args {
+                    Self::expr(arg);
+                }
+                for arg in named {
+                    Self::expr(&mut arg.val);
+                }
+                None
+            }
+            ExprKind::Lambda(declr) => {
+                Self::function(declr);
+                None
+            }
+            ExprKind::Get { obj, .. } => {
+                Self::expr(obj);
+                None
+            }
+            ExprKind::Set { obj, val, .. } => {
+                Self::expr(obj);
+                Self::expr(val);
+                None
+            }
+            ExprKind::Index { obj, index } => {
+                Self::expr(obj);
+                Self::expr(index);
+                None
+            }
+            ExprKind::IndexSet {
+                obj, index, val, ..
+            } => {
+                Self::expr(obj);
+                // range literals assign slices, so the range itself is kept
+                match &mut index.kind {
+                    ExprKind::Range {
+                        start, end, step, ..
+                    } => Self::range_bounds(start, end, step),
+                    _ => Self::expr(index),
+                }
+                Self::expr(val);
+                None
+            }
+        };
+        if let Some(folded) = folded {
+            *expr = folded;
+        }
+    }
+
+    fn range_bounds(start: &mut Expr, end: &mut Option<Box<Expr>>, step: &mut Option<Box<Expr>>) {
+        Self::expr(start);
+        for expr in [end, step].into_iter().flatten() {
+            Self::expr(expr);
+        }
+    }
+
+    /// Folds a binary operation on two literals, following `Evaluator::binary_op`. Operations
+    /// that raise errors or mix types are kept.
+    fn fold_binary(left: &LiteralType, op: &BinaryOp, right: &LiteralType) -> Option<LiteralType> {
+        if let BinaryOp::Equals | BinaryOp::NotEquals = op {
+            let equal = Value::from(left)
+                .eq_with(&Value::from(right), &mut Vec::new(), &mut |_, _| Ok(false))
+                .ok()?;
+            return Some(LiteralType::Bool(equal == matches!(op, BinaryOp::Equals)));
+        }
+        if let BinaryOp::Nullish = op {
+            return Some(match left {
+                LiteralType::Null => right.clone(),
+                _ => left.clone(),
+            });
+        }
+
+        if let (LiteralType::Str(l), LiteralType::Str(r), BinaryOp::Add) = (left, right, op) {
+            return Some(LiteralType::Str(format!("{}{}", l, r)));
+        }
+        // errors are left for the program to raise
+        match Value::from(left).num_op(op, &Value::from(right), Cursor::new())? {
+            Ok(Value::Int(n)) => Some(LiteralType::Int(n)),
+            Ok(Value::Num(n)) => Some(LiteralType::Num(n)),
+            Ok(Value::Bool(b)) => Some(LiteralType::Bool(b)),
+            _ => None,
+        }
+    }
+
+    fn fold_unary(op: &UnaryOp, right: &LiteralType) -> Option<LiteralType> {
+        match (op, right) {
+            (UnaryOp::Negate, LiteralType::Int(n)) => Some(LiteralType::Int(-n)),
+            (UnaryOp::Negate, LiteralType::Num(n)) => Some(LiteralType::Num(-*n)),
+            (UnaryOp::Negate, _) => None,
+            (UnaryOp::BitNot, LiteralType::Int(n)) => Some(LiteralType::Int(!n)),
+            (UnaryOp::BitNot, _) => None,
+            (UnaryOp::Not, lit) => Some(LiteralType::Bool(!Value::from(lit).is_truthy())),
+        }
+    }
+
+    /// Builds a range with literal bounds ahead of time, as `Evaluator::range_value` would.
+    fn fold_range(
+        start: &Expr,
+        end: Option<&Expr>,
+        step: Option<&Expr>,
+        inclusive: bool,
+        cursor: Cursor,
+    ) -> Option<Range> {
+        let value = |expr: &Expr| Self::literal(expr).map(Value::from);
+        let start = value(start)?;
+        let end = match end {
+            Some(end) => Some(value(end)?),
+            None => None,
+        };
+        let step = match step {
+            Some(step) => Some(value(step)?),
+            None => None,
+        };
+        match Evaluator::range_value(start, end, step, inclusive, cursor, cursor).ok()? {
+            Value::Range(range) => Some((*range).clone()),
+            _ => None,
+        }
+    }
+
+    // Utility functions
+
+    fn literal(expr: &Expr) -> Option<&LiteralType> {
+        match &expr.kind {
+            ExprKind::Literal(lit) => Some(lit),
+            _ => None,
+        }
+    }
+
+    /// Truthiness of a constant expression.
+    fn truthiness(expr: &Expr) -> Option<bool> {
+        Self::literal(expr).map(|lit| Value::from(lit).is_truthy())
+    }
+
+    fn take(expr: &mut Box<Expr>) -> Expr {
+        let cursor = expr.cursor;
+        *std::mem::replace(
+            expr,
+            Box::new(Expr::new(ExprKind::Literal(LiteralType::Null), cursor)),
+        )
+    }
+}
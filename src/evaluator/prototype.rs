@@ -7,8 +7,10 @@ use std::{cell::RefCell, collections::HashMap, rc::Rc};
 use crate::{
     evaluator::{
         EvalResult, Evaluator,
+        int::Int,
         runtime_err::{ErrKind, RuntimeEvent},
-        value::{Callable, Value, ValueKey},
+        string::Str,
+        value::{Arity, Callable, Value, ValueKey},
     },
     lexer::cursor::Cursor,
 };
@@ -49,9 +51,7 @@ macro_rules! str_color_method {
             0,
             |_evaluator, args, _cursor, recv| {
                 if let Value::Str(s) = recv {
-                    Ok(Value::Str(Rc::new(RefCell::new(
-                        s.borrow().$colorize().to_string(),
-                    ))))
+                    Ok(Value::Str(Str::new(s.borrow().$colorize().to_string())))
                 } else {
                     Ok(recv.clone())
                 }
@@ -87,8 +87,8 @@ impl Prototype {
         self.methods.insert(name, method);
     }
 
-    pub fn get_method(&self, name: String) -> Option<Rc<dyn Callable>> {
-        let method = self.methods.get(&name).cloned();
+    pub fn get_method(&self, name: &str) -> Option<Rc<dyn Callable>> {
+        let method = self.methods.get(name).cloned();
         if let None = method {
             if let Some(parent) = &self.parent {
                 return parent.get_method(name);
@@ -104,6 +104,7 @@ pub struct ValuePrototypes {
     pub num: Prototype,
     pub bool: Prototype,
     pub dict: Prototype,
+    pub range: Prototype,
 }
 
 impl ValuePrototypes {
@@ -114,12 +115,14 @@ impl ValuePrototypes {
         let num = ValuePrototypes::num_proto(&value);
         let bool = ValuePrototypes::bool_proto(&value);
         let dict = ValuePrototypes::dict_proto(&value);
+        let range = ValuePrototypes::range_proto(&value);
         Self {
             list,
             str,
             num,
             bool,
             dict,
+            range,
         }
     }
 
@@ -132,9 +135,7 @@ impl ValuePrototypes {
             ValueType,
             "type",
             0,
-            |_evaluator, args, _cursor, recv| {
-                Ok(Value::Str(Rc::new(RefCell::new(recv.get_type()))))
-            }
+            |_evaluator, args, _cursor, recv| { Ok(Value::Str(Str::new(recv.get_type()))) }
         );
 
         // type_of(type) -> bool: returns true if type of the value matches type, false otherwise
@@ -146,7 +147,7 @@ impl ValuePrototypes {
             |_evaluator, args, _cursor, recv| {
                 if let Value::Str(str) = &args[1] {
                     return Ok(Value::Bool(
-                        recv.get_type().to_uppercase() == str.borrow().clone().to_uppercase(),
+                        recv.get_type().to_uppercase() == str.borrow().to_uppercase(),
                     ));
                 }
                 Ok(Value::Null)
@@ -162,7 +163,7 @@ impl ValuePrototypes {
             |_evaluator, args, cursor, recv| {
                 if let Value::Str(str) = &args[1] {
                     return recv
-                        .check_type(str.borrow().clone(), cursor)
+                        .check_type(str.borrow().to_string(), cursor)
                         .map(|v| Value::Bool(v));
                 }
                 Ok(Value::Null)
@@ -175,7 +176,7 @@ impl ValuePrototypes {
     pub fn list_proto(value_proto: &Rc<Prototype>) -> Prototype {
         let mut proto = Prototype::with_parent("List".to_string(), value_proto);
 
-        // len() -> Num: returns number of elements
+        // len() -> Int: returns number of elements
         proto_method!(
             proto,
             ListLen,
@@ -183,8 +184,7 @@ impl ValuePrototypes {
             0,
             |_evaluator, args, _cursor, recv| {
                 if let Value::List(list) = recv {
-                    let len = list.borrow().len() as f64;
-                    return Ok(Value::Num(len.into()));
+                    return Ok(Value::Int(Int::from(list.borrow().len())));
                 }
                 unreachable!()
             }
@@ -233,8 +233,8 @@ impl ValuePrototypes {
             2,
             |_evaluator, args, _cursor, recv| {
                 if let Value::List(list) = recv {
-                    if let Value::Num(n) = args[1] {
-                        list.borrow_mut().insert(n.0 as usize, args[2].clone());
+                    if let Some(i) = args[1].as_index() {
+                        list.borrow_mut().insert(i, args[2].clone());
                     }
                     return Ok(Value::Null);
                 }
@@ -250,8 +250,8 @@ impl ValuePrototypes {
             1,
             |_evaluator, args, _cursor, recv| {
                 if let Value::List(list) = recv {
-                    if let Value::Num(n) = args[1] {
-                        list.borrow_mut().remove(n.0 as usize);
+                    if let Some(i) = args[1].as_index() {
+                        list.borrow_mut().remove(i);
                     }
                     return Ok(Value::Null);
                 }
@@ -299,9 +299,15 @@ impl ValuePrototypes {
             ListContains,
             "contains",
             1,
-            |_evaluator, args, _cursor, recv| {
+            |evaluator, args, cursor, recv| {
                 if let Value::List(list) = recv {
-                    return Ok(Value::Bool(list.borrow().contains(&args[1])));
+                    let items = list.borrow().clone();
+                    for item in items.iter() {
+                        if evaluator.values_equal(item, &args[1], cursor)? {
+                            return Ok(Value::Bool(true));
+                        }
+                    }
+                    return Ok(Value::Bool(false));
                 }
                 unreachable!()
             }
@@ -313,7 +319,7 @@ impl ValuePrototypes {
     pub fn str_proto(value_proto: &Rc<Prototype>) -> Prototype {
         let mut proto = Prototype::with_parent("Str".to_string(), value_proto);
 
-        // parse_num() -> Num: parses the Str to a Num
+        // parse_num() -> Int | Num: parses the Str to an Int, or a Num if it isn't an integer
         proto_method!(
             proto,
             StrParseNum,
@@ -321,7 +327,9 @@ impl ValuePrototypes {
             0,
             |_evaluator, _cursor, _args, recv| {
                 if let Value::Str(str) = recv {
-                    if let Ok(num) = str.borrow().parse::<f64>() {
+                    if let Ok(int) = str.borrow().parse::<Int>() {
+                        return Ok(Value::Int(int));
+                    } else if let Ok(num) = str.borrow().parse::<f64>() {
                         return Ok(Value::Num(OrderedFloat(num)));
                     } else {
                         return Ok(Value::Null);
@@ -331,7 +339,7 @@ impl ValuePrototypes {
             }
         );
 
-        // len() -> Str: returns the length of the string
+        // len() -> Int: returns the length of the string
         proto_method!(
             proto,
             StrLen,
@@ -339,7 +347,7 @@ impl ValuePrototypes {
             0,
             |_evaluator, args, _cursor, recv| {
                 if let Value::Str(str) = recv {
-                    return Ok(Value::Num(OrderedFloat(str.borrow().len() as f64)));
+                    return Ok(Value::Int(Int::from(str.borrow().len())));
                 }
                 unreachable!()
             }
@@ -353,10 +361,9 @@ impl ValuePrototypes {
             1,
             |_evaluator, args, cursor, recv| {
                 if let Value::Str(str) = recv {
-                    let n = args[1].check_num(cursor, Some("repeat amount".to_string()))?;
-                    return Ok(Value::Str(Rc::new(RefCell::new(
-                        str.borrow_mut().repeat(n as usize),
-                    ))));
+                    let n = args[1].check_int(cursor, Some("repeat amount".to_string()))?;
+                    let n = n.to_i64().unwrap_or(i64::MAX).max(0) as usize;
+                    return Ok(Value::Str(Str::new(str.borrow().repeat(n))));
                 }
                 unreachable!()
             }
@@ -445,7 +452,7 @@ impl ValuePrototypes {
                         "on_white" => str_val.borrow().on_white().to_string(),
                         _ => str_val.borrow().to_string(),
                     };
-                    return Ok(Value::Str(Rc::new(RefCell::new(styled))));
+                    return Ok(Value::Str(Str::new(styled)));
                 }
                 unreachable!()
             }
@@ -457,98 +464,142 @@ impl ValuePrototypes {
     pub fn num_proto(value_proto: &Rc<Prototype>) -> Prototype {
         let mut proto = Prototype::with_parent("Num".to_string(), value_proto);
 
-        // abs() -> Num: returns absolute value of number
+        // abs() -> Int | Num: returns absolute value of number
         proto_method!(
             proto,
             NumAbs,
             "abs",
             0,
             |_evaluator, args, _cursor, recv| {
-                if let Value::Num(num) = recv {
-                    return Ok(Value::Num(OrderedFloat(num.abs())));
+                match recv {
+                    Value::Int(int) => Ok(Value::Int(int.abs())),
+                    Value::Num(num) => Ok(Value::Num(OrderedFloat(num.abs()))),
+                    _ => unreachable!(),
                 }
-                unreachable!()
             }
         );
 
-        // round() -> Num: returns the number rounded to the nearest integer
+        // round() -> Int: returns the number rounded to the nearest integer
         proto_method!(
             proto,
             NumRound,
             "round",
             0,
-            |_evaluator, args, _cursor, recv| {
-                if let Value::Num(num) = recv {
-                    return Ok(Value::Num(OrderedFloat(num.round())));
+            |_evaluator, args, cursor, recv| {
+                match recv {
+                    Value::Int(int) => Ok(Value::Int(int.clone())),
+                    Value::Num(num) => to_int(num.round(), cursor),
+                    _ => unreachable!(),
                 }
-                unreachable!()
             }
         );
 
-        // ceil() -> Num: returns the number rounded to the smallest larger integer
+        // ceil() -> Int: returns the number rounded to the smallest larger integer
         proto_method!(
             proto,
             NumCeil,
             "ceil",
             0,
-            |_evaluator, args, _cursor, recv| {
-                if let Value::Num(num) = recv {
-                    return Ok(Value::Num(OrderedFloat(num.ceil())));
+            |_evaluator, args, cursor, recv| {
+                match recv {
+                    Value::Int(int) => Ok(Value::Int(int.clone())),
+                    Value::Num(num) => to_int(num.ceil(), cursor),
+                    _ => unreachable!(),
                 }
-                unreachable!()
             }
         );
 
-        // floor() -> Num: returns the number rounded to the largest smaller integer
+        // floor() -> Int: returns the number rounded to the largest smaller integer
         proto_method!(
             proto,
             NumFloor,
             "floor",
             0,
-            |_evaluator, args, _cursor, recv| {
-                if let Value::Num(num) = recv {
-                    return Ok(Value::Num(OrderedFloat(num.floor())));
+            |_evaluator, args, cursor, recv| {
+                match recv {
+                    Value::Int(int) => Ok(Value::Int(int.clone())),
+                    Value::Num(num) => to_int(num.floor(), cursor),
+                    _ => unreachable!(),
                 }
-                unreachable!()
             }
         );
 
-        // clamp(min, max) -> Num: returns the number clamped between min and max
+        // clamp(min, max) -> Int | Num: returns the number clamped between min and max, Ints
+        // stay Ints when both bounds are Ints
         proto_method!(
             proto,
             NumClamp,
             "clamp",
             2,
             |_evaluator, args, _cursor, recv| {
-                if let Value::Num(num) = recv {
-                    let min = if let Value::Num(n) = args[1] {
-                        n.0
-                    } else {
-                        return Ok(Value::Null);
-                    };
-                    let max = if let Value::Num(n) = args[2] {
-                        n.0
-                    } else {
-                        return Ok(Value::Null);
-                    };
-
-                    return Ok(Value::Num(OrderedFloat(num.0.clamp(min, max))));
+                if let (Value::Int(num), Value::Int(min), Value::Int(max)) =
+                    (recv, &args[1], &args[2])
+                {
+                    return Ok(Value::Int(num.clone().clamp(min.clone(), max.clone())));
                 }
-                unreachable!()
+
+                let num = match recv {
+                    Value::Int(int) => int.to_f64(),
+                    Value::Num(num) => num.0,
+                    _ => unreachable!(),
+                };
+                let min = match &args[1] {
+                    Value::Int(n) => n.to_f64(),
+                    Value::Num(n) => n.0,
+                    _ => return Ok(Value::Null),
+                };
+                let max = match &args[2] {
+                    Value::Int(n) => n.to_f64(),
+                    Value::Num(n) => n.0,
+                    _ => return Ok(Value::Null),
+                };
+
+                Ok(Value::Num(OrderedFloat(num.clamp(min, max))))
             }
         );
 
-        // to_str() -> Num: returns the number as an Str
+        // to_str() -> Str: returns the number as an Str
         proto_method!(
             proto,
             NumToStr,
             "to_str",
             0,
             |_evaluator, args, _cursor, recv| {
-                if let Value::Num(num) = recv {
-                    return Ok(Value::Str(Rc::new(RefCell::new(num.to_string()))));
+                match recv {
+                    Value::Int(int) => Ok(Value::Str(Str::new(int.to_string()))),
+                    Value::Num(num) => Ok(Value::Str(Str::new(num.to_string()))),
+                    _ => unreachable!(),
+                }
+            }
+        );
+
+        // to_int() -> Int: returns the number without its fraction
+        proto_method!(
+            proto,
+            NumToInt,
+            "to_int",
+            0,
+            |_evaluator, args, cursor, recv| {
+                match recv {
+                    Value::Int(int) => Ok(Value::Int(int.clone())),
+                    Value::Num(num) => to_int(num.0, cursor),
+                    _ => unreachable!(),
+                }
+            }
+        );
+
+        // to_num() -> Num: returns the number as a float
+        proto_method!(
+            proto,
+            NumToNum,
+            "to_num",
+            0,
+            |_evaluator, args, _cursor, recv| {
+                match recv {
+                    Value::Int(int) => Ok(Value::Num(OrderedFloat(int.to_f64()))),
+                    Value::Num(num) => Ok(Value::Num(*num)),
+                    _ => unreachable!(),
                 }
-                unreachable!()
             }
         );
 
@@ -558,7 +609,7 @@ impl ValuePrototypes {
     pub fn bool_proto(value_proto: &Rc<Prototype>) -> Prototype {
         let mut proto = Prototype::with_parent("Bool".to_string(), value_proto);
 
-        // to_num() -> Num: returns 1 if true, 0 if false
+        // to_num() -> Int: returns 1 if true, 0 if false
         proto_method!(
             proto,
             BoolToNum,
@@ -566,11 +617,7 @@ impl ValuePrototypes {
             0,
             |_evaluator, args, _cursor, recv| {
                 if let Value::Bool(b) = recv {
-                    if *b {
-                        return Ok(Value::Num(OrderedFloat(1.0)));
-                    } else {
-                        return Ok(Value::Num(OrderedFloat(0.0)));
-                    }
+                    return Ok(Value::Int(Int::from(*b as i64)));
                 }
                 unreachable!()
             }
@@ -582,7 +629,7 @@ impl ValuePrototypes {
     pub fn dict_proto(value_proto: &Rc<Prototype>) -> Prototype {
         let mut proto = Prototype::with_parent("Dict".to_string(), value_proto);
 
-        // len() -> Num: returns number of key-value pairs
+        // len() -> Int: returns number of key-value pairs
         proto_method!(
             proto,
             DictLen,
@@ -590,8 +637,7 @@ impl ValuePrototypes {
             0,
             |_evaluator, args, _cursor, recv| {
                 if let Value::Dict(dict) = recv {
-                    let len = dict.borrow().len() as f64;
-                    return Ok(Value::Num(len.into()));
+                    return Ok(Value::Int(Int::from(dict.borrow().len())));
                 }
                 unreachable!()
             }
@@ -608,7 +654,7 @@ impl ValuePrototypes {
                     let key = ValueKey::try_from(&args[1]).map_err(|_| {
                         RuntimeEvent::error(
                             ErrKind::Type,
-                            "only Null, Bool, Num or Str values can be Dict keys".into(),
+                            "only Null, Bool, Int, Num or Str values can be Dict keys".into(),
                             cursor,
                         )
                     })?;
@@ -629,7 +675,7 @@ impl ValuePrototypes {
                     let key = ValueKey::try_from(&args[1]).map_err(|_| {
                         RuntimeEvent::error(
                             ErrKind::Type,
-                            "only Null, Bool, Num or Str values can be Dict keys".into(),
+                            "only Null, Bool, Int, Num or Str values can be Dict keys".into(),
                             cursor,
                         )
                     })?;
@@ -652,7 +698,7 @@ impl ValuePrototypes {
                     let key = ValueKey::try_from(&args[1]).map_err(|_| {
                         RuntimeEvent::error(
                             ErrKind::Type,
-                            "only Null, Bool, Num or Str values can be Dict keys".into(),
+                            "only Null, Bool, Int, Num or Str values can be Dict keys".into(),
                             cursor,
                         )
                     })?;
@@ -673,7 +719,7 @@ impl ValuePrototypes {
                     let key = ValueKey::try_from(&args[1]).map_err(|_| {
                         RuntimeEvent::error(
                             ErrKind::Type,
-                            "only Null, Bool, Num or Str values can be Dict keys".into(),
+                            "only Null, Bool, Int, Num or Str values can be Dict keys".into(),
                             cursor,
                         )
                     })?;
@@ -716,6 +762,61 @@ impl ValuePrototypes {
 
         proto
     }
+
+    pub fn range_proto(value_proto: &Rc<Prototype>) -> Prototype {
+        let mut proto = Prototype::with_parent("Range".to_string(), value_proto);
+
+        // len() -> Int: returns number of values in the range
+        proto_method!(
+            proto,
+            RangeLen,
+            "len",
+            0,
+            |_evaluator, args, cursor, recv| {
+                if let Value::Range(range) = recv {
+                    let len = range.count().ok_or_else(|| {
+                        RuntimeEvent::error(
+                            ErrKind::Value,
+                            "open-ended range has no length".into(),
+                            cursor,
+                        )
+                    })?;
+                    return Ok(Value::Int(Int::from(len)));
+                }
+                unreachable!()
+            }
+        );
+
+        // contains(num) -> Bool: returns true if the range yields num
+        proto_method!(
+            proto,
+            RangeContains,
+            "contains",
+            1,
+            |_evaluator, args, _cursor, recv| {
+                if let Value::Range(range) = recv {
+                    return Ok(Value::Bool(range.contains(&args[1])));
+                }
+                unreachable!()
+            }
+        );
+
+        // to_list() -> List: returns a list of every value in the range
+        proto_method!(
+            proto,
+            RangeToList,
+            "to_list",
+            0,
+            |_evaluator, args, cursor, recv| {
+                if let Value::Range(range) = recv {
+                    return Ok(Value::List(Rc::new(RefCell::new(range.to_list(cursor)?))));
+                }
+                unreachable!()
+            }
+        );
+
+        proto
+    }
 }
 
 #[derive(Debug)]
@@ -729,7 +830,7 @@ impl Callable for BoundMethod {
         self.method.name()
     }
 
-    fn arity(&self) -> usize {
+    fn arity(&self) -> Arity {
         // method will receive `self` as arg[0], so from the caller’s POV arity stays the same
         self.method.arity()
     }
@@ -746,4 +847,31 @@ impl Callable for BoundMethod {
         real_args.append(&mut args);
         self.method.call(evaluator, real_args, cursor)
     }
+
+    fn param_names(&self) -> Vec<String> {
+        self.method.param_names()
+    }
+
+    fn call_named(
+        &self,
+        evaluator: &mut Evaluator,
+        args: Vec<Option<Value>>,
+        cursor: Cursor,
+    ) -> EvalResult<Value> {
+        let mut real_args = Vec::with_capacity(args.len() + 1);
+        real_args.push(Some(self.receiver.clone()));
+        real_args.extend(args);
+        self.method.call_named(evaluator, real_args, cursor)
+    }
+}
+
+/// Converts a rounded Num to an Int, infinities and NaN have no integer value.
+fn to_int(num: f64, cursor: Cursor) -> EvalResult<Value> {
+    Int::from_f64(num).map(Value::Int).ok_or_else(|| {
+        RuntimeEvent::error(
+            ErrKind::Value,
+            format!("can't convert {} to an Int", num),
+            cursor,
+        )
+    })
 }
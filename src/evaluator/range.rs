@@ -0,0 +1,240 @@
+use std::fmt::Display;
+
+use ordered_float::OrderedFloat;
+
+use crate::{
+    evaluator::{
+        int::Int,
+        runtime_err::{ErrKind, EvalResult, RuntimeEvent},
+        value::Value,
+    },
+    lexer::cursor::Cursor,
+};
+
+// tolerance used when counting float steps
+const EPSILON: f64 = 1e-9;
+
+/// Bounds and step of a range. Int ranges are counted exactly at any size, a Num bound or step
+/// makes the whole range count in floats.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Bounds {
+    Int {
+        start: Int,
+        end: Option<Int>,
+        step: Int,
+    },
+    Num {
+        start: f64,
+        end: Option<f64>,
+        step: f64,
+    },
+}
+
+/// A lazily evaluated range of numbers. The step is signed: ranges with an end count towards it,
+/// open-ended ranges count in the direction of their step forever.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Range {
+    pub bounds: Bounds,
+    pub inclusive: bool,
+}
+
+impl Range {
+    /// Checks the step of a range, `cursor` is the one of the step. Bounded ranges have to count
+    /// towards their end.
+    pub fn new(bounds: Bounds, inclusive: bool, cursor: Cursor) -> EvalResult<Self> {
+        let away = match &bounds {
+            Bounds::Int { start, end, step } => {
+                if step.is_zero() {
+                    return Err(Self::step_err(cursor));
+                }
+                end.as_ref()
+                    .is_some_and(|end| *end != *start && (*end < *start) != step.is_negative())
+            }
+            Bounds::Num { start, end, step } => {
+                if *step == 0.0 || !step.is_finite() {
+                    return Err(Self::step_err(cursor));
+                }
+                end.is_some_and(|end| end != *start && (end < *start) != (*step < 0.0))
+            }
+        };
+        if away {
+            return Err(RuntimeEvent::error(
+                ErrKind::Value,
+                "range step must count towards the end of the range".into(),
+                cursor,
+            ));
+        }
+        Ok(Self { bounds, inclusive })
+    }
+
+    fn step_err(cursor: Cursor) -> RuntimeEvent {
+        RuntimeEvent::error(
+            ErrKind::Value,
+            "range step must be a non-zero finite Num".into(),
+            cursor,
+        )
+    }
+
+    /// Step of a range without one, it counts down if the end is lower than the start.
+    pub fn default_step(down: bool) -> i64 {
+        if down { -1 } else { 1 }
+    }
+
+    /// Number of values in the range, `None` if it is open-ended.
+    pub fn count(&self) -> Option<usize> {
+        match &self.bounds {
+            Bounds::Int { start, end, step } => {
+                let diff = end.as_ref()? - start;
+                // the last step is counted whole for inclusive ranges and partially for others
+                let len = if self.inclusive {
+                    &diff.checked_div_floor(step)? + &Int::Small(1)
+                } else {
+                    -&(-&diff).checked_div_floor(step)?
+                };
+                if len.is_negative() {
+                    return Some(0);
+                }
+                Some(len.to_i64().unwrap_or(i64::MAX) as usize)
+            }
+            Bounds::Num { start, end, step } => {
+                let steps = (end.as_ref()? - start) / step;
+                let len = if self.inclusive {
+                    (steps + EPSILON).floor() + 1.0
+                } else {
+                    (steps - EPSILON).ceil()
+                };
+                Some(len.max(0.0) as usize)
+            }
+        }
+    }
+
+    /// The i-th value of the range, computed from the start to avoid accumulating float errors.
+    pub fn nth_value(&self, i: usize) -> Value {
+        match &self.bounds {
+            Bounds::Int { start, step, .. } => Value::Int(start + &(step * &Int::from(i))),
+            Bounds::Num { start, step, .. } => Value::Num(OrderedFloat(start + i as f64 * step)),
+        }
+    }
+
+    /// Whether the range yields a number, Int ranges only yield whole numbers.
+    pub fn contains(&self, val: &Value) -> bool {
+        match &self.bounds {
+            Bounds::Int { start, end, step } => {
+                let n = match val {
+                    Value::Int(n) => n.clone(),
+                    Value::Num(n) => match Int::from_f64(n.0) {
+                        Some(int) if int.to_f64() == n.0 => int,
+                        _ => return false,
+                    },
+                    _ => return false,
+                };
+                let diff = &n - start;
+                if diff
+                    .checked_mod_floor(step)
+                    .is_none_or(|rem| !rem.is_zero())
+                {
+                    return false;
+                }
+                // the value is a whole number of steps away, it has to lie between the bounds
+                let i = diff.checked_div_floor(step);
+                if i.is_none_or(|i| i.is_negative()) {
+                    return false;
+                }
+                match end {
+                    Some(end) if step.is_negative() => n > *end || (self.inclusive && n == *end),
+                    Some(end) => n < *end || (self.inclusive && n == *end),
+                    None => true,
+                }
+            }
+            Bounds::Num { start, step, .. } => {
+                let n = match val {
+                    Value::Int(n) => n.to_f64(),
+                    Value::Num(n) => n.0,
+                    _ => return false,
+                };
+                let steps = (n - start) / step;
+                let i = steps.round();
+                if i < 0.0 || (steps - i).abs() > EPSILON {
+                    return false;
+                }
+                match self.count() {
+                    Some(len) => (i as usize) < len,
+                    None => true,
+                }
+            }
+        }
+    }
+
+    /// Closes an open-ended range used to slice a sequence of `len` items.
+    pub fn bounded_to(&self, len: usize) -> Range {
+        let bounds = match &self.bounds {
+            Bounds::Int {
+                start,
+                end: None,
+                step,
+            } => Bounds::Int {
+                start: start.clone(),
+                end: Some(if step.is_negative() {
+                    Int::Small(-1)
+                } else {
+                    Int::from(len)
+                }),
+                step: step.clone(),
+            },
+            Bounds::Num {
+                start,
+                end: None,
+                step,
+            } => Bounds::Num {
+                start: *start,
+                end: Some(if *step > 0.0 { len as f64 } else { -1.0 }),
+                step: *step,
+            },
+            _ => return self.clone(),
+        };
+        Range {
+            bounds,
+            inclusive: false,
+        }
+    }
+
+    pub fn to_list(&self, cursor: Cursor) -> EvalResult<Vec<Value>> {
+        match self.count() {
+            Some(len) => Ok((0..len).map(|i| self.nth_value(i)).collect()),
+            None => Err(RuntimeEvent::error(
+                ErrKind::Value,
+                "can't convert an open-ended range to a List".into(),
+                cursor,
+            )),
+        }
+    }
+}
+
+impl Display for Range {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let op = if self.inclusive { "..=" } else { ".." };
+        match &self.bounds {
+            Bounds::Int { start, end, step } => {
+                write!(f, "{}{}", start, op)?;
+                if let Some(end) = end {
+                    write!(f, "{}", end)?;
+                }
+                let down = end.as_ref().is_some_and(|end| end < start);
+                if *step != Int::Small(Self::default_step(down)) {
+                    write!(f, " step {}", step)?;
+                }
+            }
+            Bounds::Num { start, end, step } => {
+                write!(f, "{}{}", start, op)?;
+                if let Some(end) = end {
+                    write!(f, "{}", end)?;
+                }
+                let down = end.is_some_and(|end| end < *start);
+                if *step != Self::default_step(down) as f64 {
+                    write!(f, " step {}", step)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
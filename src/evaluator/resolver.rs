@@ -4,7 +4,8 @@ use crate::{
     lexer::{cursor::Cursor, token::KeywordKind},
     parser::{
         expr::{Expr, ExprKind},
-        stmt::{Stmt, StmtKind},
+        pattern::{Pattern, PatternKind},
+        stmt::{FnDecl, Stmt, StmtKind},
     },
     reporter::Reporter,
     src::Src,
@@ -62,31 +63,53 @@ struct ScopedVar {
     defined: bool,
     used: bool,
     loc: Cursor,
+    /// Index of the variable in the env of its scope
+    slot: usize,
 }
 
 impl ScopedVar {
-    fn declared(loc: Cursor) -> Self {
+    fn declared(loc: Cursor, slot: usize) -> Self {
         ScopedVar {
             defined: false,
             used: false,
             loc,
+            slot,
         }
     }
 
-    fn defined(loc: Cursor) -> Self {
+    fn defined(loc: Cursor, slot: usize) -> Self {
         ScopedVar {
             defined: true,
             used: false,
             loc,
+            slot,
         }
     }
 }
 
+/// A lexical scope, every declaration in it gets its own slot.
+#[derive(Default)]
+struct Scope {
+    vars: HashMap<String, ScopedVar>,
+    /// Slots taken by the declarations so far, redeclared names shadow their earlier slot
+    slots: usize,
+}
+
+impl Scope {
+    fn next_slot(&mut self) -> usize {
+        self.slots += 1;
+        self.slots - 1
+    }
+}
+
 pub struct Resolver<'a> {
     pub src: &'a Src,
-    pub ast: Vec<Stmt>,
     /// Stack of lexical scopes
-    scopes: Vec<HashMap<String, ScopedVar>>,
+    scopes: Vec<Scope>,
+    /// Variables used before any scope declared them, with the number of scopes open at the use
+    /// and the depth of the innermost of them that is still open. They're resolved late by the
+    /// first scope that declares them further down, if any.
+    late: Vec<(&'a Expr, usize, usize)>,
     /// Resolver output
     out: ResolverOutput,
 }
@@ -95,44 +118,48 @@ impl<'a> Resolver<'a> {
     pub fn new(src: &'a Src) -> Self {
         Self {
             src,
-            ast: src.ast.clone().expect("expected ast"),
             scopes: vec![],
+            late: vec![],
             out: ResolverOutput::default(),
         }
     }
 
     pub fn resolve(&mut self) -> ResolverOutput {
-        let mut ast = self.ast.clone();
-        for stmt in ast.iter_mut() {
+        // resolutions are stored in the AST of the source, the output gets a copy of it
+        let ast = self.src.ast.as_ref().expect("expected ast");
+        for stmt in ast {
             if let Err(err) = self.resolve_stmt(stmt) {
                 self.out.add_err(err.clone());
                 Reporter::error_at(&err.msg, "ResolveErr".into(), self.src, err.cursor);
             }
         }
 
+        // the rest are globals
+        self.late.clear();
+
         if self.out.error_count < 1 {
-            self.out.ast = Some(ast);
+            self.out.ast = Some(ast.clone());
         } else {
             self.out.ast = None;
         }
-        self.out.clone()
+        std::mem::take(&mut self.out)
     }
 
     // Statement functions
 
-    fn resolve_stmts(&mut self, stmts: &Vec<Stmt>) -> ResolveResult {
+    fn resolve_stmts(&mut self, stmts: &'a Vec<Stmt>) -> ResolveResult {
         for stmt in stmts {
             self.resolve_stmt(stmt)?;
         }
         Ok(())
     }
 
-    fn resolve_stmt(&mut self, stmt: &Stmt) -> ResolveResult {
+    fn resolve_stmt(&mut self, stmt: &'a Stmt) -> ResolveResult {
         match &stmt.kind {
             StmtKind::Expr(_) => self.resolve_stmt_expr(stmt),
             StmtKind::Throw(_) => self.resolve_stmt_err(stmt),
-            StmtKind::Use(_) => self.resolve_stmt_use(stmt),
-            StmtKind::Return(_) => self.resolve_stmt_return(stmt),
+            StmtKind::Use { .. } => self.resolve_stmt_use(stmt),
+            StmtKind::Return { .. } => self.resolve_stmt_return(stmt),
             StmtKind::Break => Ok(()),
             StmtKind::Continue => Ok(()),
             StmtKind::Var { .. } => self.resolve_stmt_var(stmt),
@@ -147,7 +174,7 @@ impl<'a> Resolver<'a> {
         }
     }
 
-    fn resolve_stmt_block(&mut self, stmt: &Stmt, fn_block: bool) -> ResolveResult {
+    fn resolve_stmt_block(&mut self, stmt: &'a Stmt, fn_block: bool) -> ResolveResult {
         if let StmtKind::Block(statements) = &stmt.kind {
             if !fn_block {
                 self.begin_scope();
@@ -161,7 +188,7 @@ impl<'a> Resolver<'a> {
         unreachable!("Non-block statement passed to Resolver::resolve_stmt_block");
     }
 
-    fn resolve_stmt_var(&mut self, stmt: &Stmt) -> ResolveResult {
+    fn resolve_stmt_var(&mut self, stmt: &'a Stmt) -> ResolveResult {
         if let StmtKind::Var { name, init } = &stmt.kind {
             // Declare first (not defined yet) to catch self-initialization reads.
             self.declare(name.clone(), stmt.cursor);
@@ -175,7 +202,7 @@ impl<'a> Resolver<'a> {
         unreachable!("Non-var statement passed to Resolver::resolve_stmt_var");
     }
 
-    fn resolve_stmt_expr(&mut self, stmt: &Stmt) -> ResolveResult {
+    fn resolve_stmt_expr(&mut self, stmt: &'a Stmt) -> ResolveResult {
         if let StmtKind::Expr(expr) = &stmt.kind {
             self.resolve_expr(expr)?;
             return Ok(());
@@ -183,7 +210,7 @@ impl<'a> Resolver<'a> {
         unreachable!("Non-expr statement passed to Resolver::resolve_stmt_expr");
     }
 
-    fn resolve_stmt_err(&mut self, stmt: &Stmt) -> ResolveResult {
+    fn resolve_stmt_err(&mut self, stmt: &'a Stmt) -> ResolveResult {
         if let StmtKind::Throw(expr) = &stmt.kind {
             self.resolve_expr(expr)?;
             return Ok(());
@@ -191,17 +218,23 @@ impl<'a> Resolver<'a> {
         unreachable!("Non-err statement passed to Resolver::resolve_stmt_err");
     }
 
-    fn resolve_stmt_use(&mut self, stmt: &Stmt) -> ResolveResult {
-        if let StmtKind::Use(expr) = &stmt.kind {
-            self.resolve_expr(expr)?;
+    fn resolve_stmt_use(&mut self, stmt: &'a Stmt) -> ResolveResult {
+        if let StmtKind::Use { path, alias, names } = &stmt.kind {
+            self.resolve_expr(path)?;
+            if let Some(alias) = alias {
+                self.define(alias.clone(), stmt.cursor);
+            }
+            for name in names.iter().flatten() {
+                self.define(name.clone(), stmt.cursor);
+            }
             return Ok(());
         }
         unreachable!("Non-use statement passed to Resolver::resolve_stmt_use");
     }
 
-    fn resolve_stmt_return(&mut self, stmt: &Stmt) -> ResolveResult {
-        if let StmtKind::Return(expr) = &stmt.kind {
-            if let Some(e) = expr {
+    fn resolve_stmt_return(&mut self, stmt: &'a Stmt) -> ResolveResult {
+        if let StmtKind::Return { val, .. } = &stmt.kind {
+            if let Some(e) = val {
                 self.resolve_expr(e)?;
             }
             return Ok(());
@@ -209,7 +242,7 @@ impl<'a> Resolver<'a> {
         unreachable!("Non-return statement passed to Resolver::resolve_stmt_return");
     }
 
-    fn resolve_stmt_if(&mut self, stmt: &Stmt) -> ResolveResult {
+    fn resolve_stmt_if(&mut self, stmt: &'a Stmt) -> ResolveResult {
         if let StmtKind::If {
             condition,
             then_branch,
@@ -226,7 +259,7 @@ impl<'a> Resolver<'a> {
         unreachable!("Non-if statement passed to Resolver::resolve_stmt_if");
     }
 
-    fn resolve_stmt_match(&mut self, stmt: &Stmt) -> ResolveResult {
+    fn resolve_stmt_match(&mut self, stmt: &'a Stmt) -> ResolveResult {
         if let StmtKind::Match {
             val,
             arms,
@@ -234,9 +267,22 @@ impl<'a> Resolver<'a> {
         } = &stmt.kind
         {
             self.resolve_expr(val)?;
-            for (e, s) in arms.iter() {
-                self.resolve_expr(e)?;
-                self.resolve_stmt(s)?;
+            for arm in arms.iter() {
+                // pattern values are evaluated outside of the arm scope
+                self.resolve_pattern(&arm.pattern)?;
+
+                // bound names are scoped to their own arm
+                self.begin_scope();
+                for (name, loc) in arm.pattern.bindings() {
+                    self.declare(name.clone(), loc);
+                    self.define(name, loc);
+                }
+                self.resolve_bindings(&arm.pattern);
+                if let Some(guard) = &arm.guard {
+                    self.resolve_expr(guard)?;
+                }
+                self.resolve_stmt(&arm.body)?;
+                self.end_scope();
             }
             if let Some(else_s) = else_branch {
                 self.resolve_stmt(else_s)?;
@@ -246,7 +292,63 @@ impl<'a> Resolver<'a> {
         unreachable!("Non-match statement passed to Resolver::resolve_stmt_match");
     }
 
-    fn resolve_stmt_for(&mut self, stmt: &Stmt) -> ResolveResult {
+    fn resolve_pattern(&mut self, pattern: &'a Pattern) -> ResolveResult {
+        match &pattern.kind {
+            PatternKind::Value(expr) => self.resolve_expr(expr),
+            PatternKind::List { items, .. } => {
+                for item in items {
+                    self.resolve_pattern(item)?;
+                }
+                Ok(())
+            }
+            PatternKind::Dict(entries) => {
+                for (key, value) in entries {
+                    self.resolve_expr(key)?;
+                    self.resolve_pattern(value)?;
+                }
+                Ok(())
+            }
+            PatternKind::Or(alts) => {
+                for alt in alts {
+                    self.resolve_pattern(alt)?;
+                }
+                Ok(())
+            }
+            PatternKind::Wildcard | PatternKind::Bind(_) | PatternKind::Type { .. } => Ok(()),
+        }
+    }
+
+    /// Gives the bind and type patterns the slots of the names they bind in the arm scope.
+    fn resolve_bindings(&self, pattern: &Pattern) {
+        match &pattern.kind {
+            PatternKind::Bind(name)
+            | PatternKind::Type {
+                bind: Some(name), ..
+            } => {
+                if let Some(var) = self.scopes.last().and_then(|scope| scope.vars.get(name)) {
+                    pattern.slot.set(var.slot);
+                }
+            }
+            PatternKind::List { items, rest } => {
+                for item in items.iter().chain(rest.as_deref()) {
+                    self.resolve_bindings(item);
+                }
+            }
+            PatternKind::Dict(entries) => {
+                for (_, value) in entries {
+                    self.resolve_bindings(value);
+                }
+            }
+            PatternKind::Or(alts) => {
+                for alt in alts {
+                    self.resolve_bindings(alt);
+                }
+            }
+            PatternKind::Wildcard | PatternKind::Value(_) | PatternKind::Type { .. } => {}
+        }
+    }
+
+    fn resolve_stmt_for(&mut self, stmt: &'a Stmt) -> ResolveResult {
         if let StmtKind::For {
             item,
             index,
@@ -280,7 +382,7 @@ impl<'a> Resolver<'a> {
         unreachable!("Non-for statement passed to Resolver::resolve_stmt_for");
     }
 
-    fn resolve_stmt_while(&mut self, stmt: &Stmt) -> ResolveResult {
+    fn resolve_stmt_while(&mut self, stmt: &'a Stmt) -> ResolveResult {
         if let StmtKind::While {
             declr,
             condition,
@@ -301,31 +403,27 @@ impl<'a> Resolver<'a> {
         unreachable!("Non-while statement passed to Resolver::resolve_stmt_while");
     }
 
-    fn resolve_stmt_try(&mut self, stmt: &Stmt) -> ResolveResult {
+    fn resolve_stmt_try(&mut self, stmt: &'a Stmt) -> ResolveResult {
         if let StmtKind::Try {
             body,
-            err_kind,
-            err_val,
-            catch,
+            catches,
             ensure,
         } = &stmt.kind
         {
             self.resolve_stmt(body)?;
 
-            self.begin_scope();
+            for clause in catches {
+                self.begin_scope();
 
-            if let Some(kind) = err_kind {
-                self.declare(kind.clone(), stmt.cursor);
-                self.define(kind.clone(), stmt.cursor);
-            }
-            if let Some(val) = err_val {
-                self.declare(val.clone(), stmt.cursor);
-                self.define(val.clone(), stmt.cursor);
-            }
+                for name in [&clause.err, &clause.val].into_iter().flatten() {
+                    self.declare(name.clone(), clause.cursor);
+                    self.define(name.clone(), clause.cursor);
+                }
 
-            self.resolve_stmt_block(catch, true)?;
+                self.resolve_stmt_block(&clause.body, true)?;
 
-            self.end_scope();
+                self.end_scope();
+            }
 
             if let Some(ensure_body) = ensure {
                 self.resolve_stmt(ensure_body)?;
@@ -336,48 +434,76 @@ impl<'a> Resolver<'a> {
         unreachable!("Non-try statement passed to Resolver::resolve_stmt_try");
     }
 
-    fn resolve_stmt_fn(&mut self, stmt: &Stmt) -> ResolveResult {
-        if let StmtKind::Fn {
-            name, params, body, ..
-        } = &stmt.kind
-        {
+    fn resolve_stmt_fn(&mut self, stmt: &'a Stmt) -> ResolveResult {
+        if let StmtKind::Fn(declr) = &stmt.kind {
             // Function name is bound in the enclosing scope.
-            self.declare(name.clone(), stmt.cursor);
-            self.define(name.clone(), stmt.cursor);
+            self.declare(declr.name.clone(), stmt.cursor);
+            self.define(declr.name.clone(), stmt.cursor);
 
-            // Resolve function body in its own scope with parameters.
-            self.begin_scope();
-            for p in params {
-                self.declare(p.clone(), stmt.cursor);
-                self.define(p.clone(), stmt.cursor);
-            }
-            self.resolve_stmt_block(body, true)?;
-            self.end_scope();
-            return Ok(());
+            return self.resolve_function(stmt.cursor, declr);
         }
         unreachable!("Non-fn statement passed to Resolver::resolve_stmt_fn");
     }
 
-    fn resolve_stmt_obj(&mut self, stmt: &Stmt) -> ResolveResult {
-        if let StmtKind::Obj { name, methods } = &stmt.kind {
+    fn resolve_function(&mut self, cursor: Cursor, declr: &'a FnDecl) -> ResolveResult {
+        // Resolve function body in its own scope with parameters.
+        self.begin_scope();
+        for p in &declr.params {
+            // defaults run in the call scope and only see the parameters before them
+            self.declare(p.name.clone(), cursor);
+            if let Some(default) = &p.default {
+                self.resolve_expr(default)?;
+            }
+            self.define(p.name.clone(), cursor);
+        }
+        self.resolve_stmt_block(&declr.body, true)?;
+        self.end_scope();
+        Ok(())
+    }
+
+    fn resolve_stmt_obj(&mut self, stmt: &'a Stmt) -> ResolveResult {
+        if let StmtKind::Obj {
+            name,
+            parent,
+            methods,
+        } = &stmt.kind
+        {
             self.declare(name.clone(), stmt.cursor);
             self.define(name.clone(), stmt.cursor);
 
-            self.begin_scope();
+            if let Some(parent) = parent {
+                if let ExprKind::Var(parent_name) = &parent.kind
+                    && parent_name == name
+                {
+                    return Err(ResolveErr::new(
+                        "an object can't inherit from itself".into(),
+                        parent.cursor,
+                    ));
+                }
+                self.resolve_expr(parent)?;
+
+                // Methods of child objects close over a scope holding `super`.
+                self.begin_scope();
+                self.define(KeywordKind::Super.to_string(), stmt.cursor);
+            }
 
             for method in methods {
-                if let StmtKind::Fn { bound, .. } = &method.kind {
-                    if *bound {
-                        self.scopes.last_mut().unwrap().insert(
-                            KeywordKind::KSelf.to_string(),
-                            ScopedVar::defined(stmt.cursor),
-                        );
+                if let StmtKind::Fn(declr) = &method.kind {
+                    // Each method is bound in its own scope, `self` is its only variable for bound
+                    // methods and `init`.
+                    self.begin_scope();
+                    if declr.bound || declr.name == "init" {
+                        self.define(KeywordKind::KSelf.to_string(), stmt.cursor);
                     }
+                    // Methods aren't bound as locals, they're only reachable through the object.
+                    self.resolve_function(method.cursor, declr)?;
+                    self.end_scope();
                 }
-                self.resolve_stmt_fn(method)?;
             }
 
-            self.end_scope();
+            if parent.is_some() {
+                self.end_scope();
+            }
 
             return Ok(());
         }
@@ -386,7 +512,7 @@ impl<'a> Resolver<'a> {
 
     // Expression functions
 
-    fn resolve_expr(&mut self, expr: &Expr) -> ResolveResult {
+    fn resolve_expr(&mut self, expr: &'a Expr) -> ResolveResult {
         match &expr.kind {
             ExprKind::Binary { left, right, .. } => {
                 self.resolve_expr(left)?;
@@ -429,7 +555,9 @@ impl<'a> Resolver<'a> {
                 start, end, step, ..
             } => {
                 self.resolve_expr(start)?;
-                self.resolve_expr(end)?;
+                if let Some(expr) = end {
+                    self.resolve_expr(expr)?;
+                }
                 if let Some(expr) = step {
                     self.resolve_expr(expr)?;
                 }
@@ -448,11 +576,25 @@ impl<'a> Resolver<'a> {
                 self.resolve_expr(val)?;
                 Ok(())
             }
-            ExprKind::Call { callee, args } => {
+            ExprKind::Call {
+                callee,
+                args,
+                named,
+            } => {
                 self.resolve_expr(callee)?;
                 for a in args {
                     self.resolve_expr(a)?;
                 }
+                for a in named {
+                    self.resolve_expr(&a.val)?;
+                }
+                Ok(())
+            }
+            ExprKind::Lambda(declr) => self.resolve_function(expr.cursor, declr),
+            ExprKind::Interp(parts) => {
+                for part in parts {
+                    self.resolve_expr(part)?;
+                }
                 Ok(())
             }
             ExprKind::Var(name) => self.resolve_expr_var(expr, name),
@@ -479,49 +621,86 @@ impl<'a> Resolver<'a> {
                 self.resolve_local(expr, KeywordKind::KSelf.to_string().as_str());
                 Ok(())
             }
-        }
-    }
-
-    fn resolve_expr_var(&mut self, expr: &Expr, name: &str) -> ResolveResult {
-        // If the variable exists in the innermost scope but is not yet defined,
-        // we’re reading it in its own initializer.
-        if let Some(scope) = self.scopes.last_mut() {
-            if let Some(var) = scope.get_mut(name) {
-                if !var.defined {
+            ExprKind::Super { .. } => {
+                let super_name = KeywordKind::Super.to_string();
+                if !self
+                    .scopes
+                    .iter()
+                    .any(|scope| scope.vars.contains_key(&super_name))
+                {
                     return Err(ResolveErr::new(
-                        "can't read local variable in its own initializer".into(),
+                        "can't use 'super' outside of an object with a parent".into(),
                         expr.cursor,
                     ));
                 }
-                if !var.used {
-                    var.used = true;
-                }
+                self.resolve_local(expr, super_name.as_str());
+                Ok(())
             }
         }
+    }
+
+    fn resolve_expr_var(&mut self, expr: &'a Expr, name: &str) -> ResolveResult {
+        // If the variable exists in the innermost scope but is not yet defined,
+        // we’re reading it in its own initializer.
+        if let Some(var) = self.scopes.last().and_then(|scope| scope.vars.get(name))
+            && !var.defined
+        {
+            return Err(ResolveErr::new(
+                "can't read local variable in its own initializer".into(),
+                expr.cursor,
+            ));
+        }
 
-        // Annotate variable access distance if found; else it remains global (None).
-        self.resolve_local(expr, name);
+        // Annotate variable access distance if found; else it's resolved late or remains global.
+        if !self.resolve_local(expr, name) && !self.scopes.is_empty() {
+            self.late.push((expr, self.scopes.len(), self.scopes.len()));
+        }
         Ok(())
     }
 
     // Utility functions
 
-    fn resolve_local(&mut self, expr: &Expr, name: &str) {
-        for (i, scope) in self.scopes.iter().rev().enumerate() {
-            if scope.contains_key(name) {
-                *expr.resolved_dist.borrow_mut() = Some(i);
-                return;
+    fn resolve_local(&mut self, expr: &Expr, name: &str) -> bool {
+        for (i, scope) in self.scopes.iter_mut().rev().enumerate() {
+            if let Some(var) = scope.vars.get_mut(name) {
+                // Mark usage in the declaring scope so captured and nested uses count too.
+                var.used = true;
+                expr.resolve(i, var.slot);
+                return true;
             }
         }
+        false
     }
 
     fn begin_scope(&mut self) {
-        self.scopes.push(HashMap::new());
+        self.scopes.push(Scope::default());
     }
 
     fn end_scope(&mut self) {
-        for (name, var) in self.scopes.last().unwrap() {
-            if !var.used && *name != KeywordKind::KSelf.to_string() {
+        let depth = self.scopes.len();
+        let scope = self.scopes.last_mut().unwrap();
+
+        // late uses inside this scope see its declarations, the others move to the enclosing one
+        self.late.retain_mut(|(expr, used_at, open)| {
+            if *open != depth {
+                return true;
+            }
+            if let ExprKind::Var(name) = &expr.kind
+                && let Some(var) = scope.vars.get_mut(name)
+            {
+                var.used = true;
+                expr.resolve_late(*used_at - depth, var.slot);
+                return false;
+            }
+            *open -= 1;
+            true
+        });
+
+        for (name, var) in &scope.vars {
+            if !var.used
+                && *name != KeywordKind::KSelf.to_string()
+                && *name != KeywordKind::Super.to_string()
+            {
                 Reporter::warning_at(
                     format!("local variable {} never used", name).as_str(),
                     self.src,
@@ -536,13 +715,19 @@ impl<'a> Resolver<'a> {
     fn declare(&mut self, name: String, loc: Cursor) {
         if let Some(scope) = self.scopes.last_mut() {
             // false = declared but not yet defined
-            scope.insert(name, ScopedVar::declared(loc));
+            let slot = scope.next_slot();
+            scope.vars.insert(name, ScopedVar::declared(loc, slot));
         }
     }
 
+    /// Defines the variable being declared, names that weren't declared first get a new slot.
     fn define(&mut self, name: String, loc: Cursor) {
         if let Some(scope) = self.scopes.last_mut() {
-            scope.insert(name, ScopedVar::defined(loc));
+            let slot = match scope.vars.get(&name) {
+                Some(var) if !var.defined => var.slot,
+                _ => scope.next_slot(),
+            };
+            scope.vars.insert(name, ScopedVar::defined(loc, slot));
         }
     }
 }
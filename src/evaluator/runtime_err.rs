@@ -1,6 +1,9 @@
-use std::{error::Error, fmt::Display, io, str::FromStr};
+use std::{error::Error, fmt::Display, io, path::PathBuf, rc::Rc, str::FromStr};
 
-use crate::{evaluator::value::Value, lexer::cursor::Cursor};
+use crate::{
+    evaluator::value::{Callable, Value},
+    lexer::cursor::Cursor,
+};
 
 pub type EvalResult<T> = std::result::Result<T, RuntimeEvent>;
 
@@ -8,7 +11,16 @@ pub type EvalResult<T> = std::result::Result<T, RuntimeEvent>;
 pub enum RuntimeEvent {
     Err(RuntimeErr),
     Return(Value),
-    UserErr { val: Value, cursor: Cursor },
+    /// Call returned in tail position, run by the returning function in place of its body
+    TailCall {
+        callee: Rc<dyn Callable>,
+        args: Vec<Option<Value>>,
+    },
+    UserErr {
+        val: Value,
+        cursor: Cursor,
+        trace: Option<Trace>,
+    },
     Break,
     Continue,
 }
@@ -20,6 +32,7 @@ impl RuntimeEvent {
             msg,
             cursor,
             note: None,
+            trace: None,
         })
     }
 
@@ -29,11 +42,39 @@ impl RuntimeEvent {
             msg,
             cursor,
             note: Some(note),
+            trace: None,
         })
     }
 
     pub fn user_err(val: Value, cursor: Cursor) -> Self {
-        RuntimeEvent::UserErr { val, cursor }
+        RuntimeEvent::UserErr {
+            val,
+            cursor,
+            trace: None,
+        }
+    }
+
+    /// Attaches a trace to errors that don't have one yet, other events are returned as is.
+    pub fn with_trace(mut self, trace: impl FnOnce() -> Trace) -> Self {
+        match &mut self {
+            RuntimeEvent::Err(RuntimeErr {
+                trace: t @ None, ..
+            })
+            | RuntimeEvent::UserErr {
+                trace: t @ None, ..
+            } => *t = Some(trace()),
+            _ => {}
+        }
+        self
+    }
+
+    /// Trace of the call stack when the error left its innermost call, if it left one.
+    pub fn trace(&self) -> Option<&Trace> {
+        match self {
+            RuntimeEvent::Err(e) => e.trace.as_ref(),
+            RuntimeEvent::UserErr { trace, .. } => trace.as_ref(),
+            _ => None,
+        }
     }
 
     pub fn is_break(&self) -> bool {
@@ -63,6 +104,8 @@ pub struct RuntimeErr {
     pub cursor: Cursor,
     /// Friendly note for the user
     pub note: Option<String>,
+    /// Call stack at the time of the error
+    pub trace: Option<Trace>,
 }
 
 impl RuntimeErr {
@@ -72,6 +115,7 @@ impl RuntimeErr {
             msg,
             cursor,
             note: None,
+            trace: None,
         }
     }
 
@@ -99,6 +143,44 @@ impl Display for RuntimeErr {
     }
 }
 
+/// A call on the evaluator's call stack: the called function and where it was called from.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub name: String,
+    pub file: Rc<PathBuf>,
+    pub cursor: Cursor,
+}
+
+/// Call stack captured when an error leaves a call, `file` is the file the error was raised in.
+#[derive(Debug, Clone)]
+pub struct Trace {
+    pub frames: Vec<Frame>,
+    pub file: Rc<PathBuf>,
+}
+
+impl Trace {
+    /// Traceback entries, outermost first: each names the function running at that location,
+    /// the last one is the error location.
+    pub fn entries(&self, cursor: Cursor) -> Vec<Frame> {
+        let mut entries = Vec::with_capacity(self.frames.len() + 1);
+        let mut name = "<script>".to_string();
+        for frame in &self.frames {
+            entries.push(Frame {
+                name,
+                file: frame.file.clone(),
+                cursor: frame.cursor,
+            });
+            name = frame.name.clone();
+        }
+        entries.push(Frame {
+            name,
+            file: self.file.clone(),
+            cursor,
+        });
+        entries
+    }
+}
+
 #[derive(Debug)]
 pub enum ErrKind {
     Type,
@@ -107,6 +189,7 @@ pub enum ErrKind {
     Value,
     Native,
     IO,
+    Recursion,
 }
 
 impl ToString for ErrKind {
@@ -118,6 +201,7 @@ impl ToString for ErrKind {
             ErrKind::Value => "ValueErr",
             ErrKind::Native => "NativeErr",
             ErrKind::IO => "IOErr",
+            ErrKind::Recursion => "RecursionErr",
         }
         .into()
     }
@@ -134,6 +218,7 @@ impl FromStr for ErrKind {
             "ValueErr" => Ok(ErrKind::Value),
             "NativeErr" => Ok(ErrKind::Native),
             "IOErr" => Ok(ErrKind::IO),
+            "RecursionErr" => Ok(ErrKind::Recursion),
 
             _ => Err(()),
         }
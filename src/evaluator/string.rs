@@ -0,0 +1,75 @@
+use std::{
+    cell::{Ref, RefCell},
+    fmt::{Debug, Display},
+    rc::Rc,
+};
+
+/// An immutable string. Clones share the buffer, appending pushes onto it when the string ends
+/// where the buffer does, so building a Str with `+=` doesn't copy it every time.
+#[derive(Clone)]
+pub struct Str {
+    buf: Rc<RefCell<String>>,
+    /// Length in bytes, appends made through other Strs sharing the buffer are past it
+    len: usize,
+}
+
+impl Str {
+    pub fn new(s: String) -> Self {
+        Self {
+            len: s.len(),
+            buf: Rc::new(RefCell::new(s)),
+        }
+    }
+
+    pub fn borrow(&self) -> Ref<'_, str> {
+        Ref::map(self.buf.borrow(), |buf| &buf[..self.len])
+    }
+
+    /// Returns the string followed by `rhs`.
+    pub fn append(&self, rhs: &str) -> Str {
+        if let Ok(mut buf) = self.buf.try_borrow_mut()
+            && buf.len() == self.len
+        {
+            buf.push_str(rhs);
+            return Self {
+                buf: self.buf.clone(),
+                len: buf.len(),
+            };
+        }
+
+        let mut s = String::with_capacity(self.len + rhs.len());
+        s.push_str(&self.borrow());
+        s.push_str(rhs);
+        Self::new(s)
+    }
+}
+
+impl From<String> for Str {
+    fn from(s: String) -> Self {
+        Self::new(s)
+    }
+}
+
+impl From<&str> for Str {
+    fn from(s: &str) -> Self {
+        Self::new(s.to_string())
+    }
+}
+
+impl PartialEq for Str {
+    fn eq(&self, other: &Self) -> bool {
+        *self.borrow() == *other.borrow()
+    }
+}
+
+impl Display for Str {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.borrow())
+    }
+}
+
+impl Debug for Str {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&*self.borrow(), f)
+    }
+}
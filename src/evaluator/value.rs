@@ -2,7 +2,6 @@ use std::{
     cell::RefCell,
     collections::HashMap,
     fmt::{Debug, Display},
-    ops::Deref,
     rc::Rc,
 };
 
@@ -11,24 +10,34 @@ use ordered_float::OrderedFloat;
 use crate::{
     evaluator::{
         Evaluator,
+        function::Function,
+        int::Int,
+        loader::Module,
         object::{Instance, Object},
         prototype::{Prototype, ValuePrototypes},
+        range::Range,
         runtime_err::{ErrKind, EvalResult, RuntimeErr, RuntimeEvent},
+        string::Str,
     },
     lexer::cursor::Cursor,
+    parser::expr::{BinaryOp, LiteralType},
+    vm::closure::Closure,
 };
 
 #[derive(Debug, Clone)]
 pub enum Value {
     Null,
     Bool(bool),
+    Int(Int),
     Num(OrderedFloat<f64>),
-    Str(Rc<RefCell<String>>),
+    Str(Str),
     List(Rc<RefCell<Vec<Value>>>),
     Dict(Rc<RefCell<HashMap<ValueKey, Value>>>),
+    Range(Rc<Range>),
     Callable(Rc<dyn Callable>),
     Obj(Rc<Object>),
     ObjInstance(Rc<RefCell<Instance>>),
+    Module(Rc<Module>),
 }
 
 impl PartialEq for Value {
@@ -39,68 +48,23 @@ impl PartialEq for Value {
 
 impl Display for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Value::Null => write!(f, "Null"),
-            Value::Bool(b) => write!(f, "{b}"),
-            Value::Num(n) => write!(f, "{}", n.0),
-            Value::Str(s) => write!(f, "{}", s.borrow()),
-            Value::List(l) => {
-                write!(
-                    f,
-                    "[{}]",
-                    l.borrow()
-                        .iter()
-                        .map(|e| if e.get_type() == "Str" {
-                            format!("\"{}\"", e)
-                        } else {
-                            e.to_string()
-                        })
-                        .collect::<Vec<String>>()
-                        .join(", ")
-                )
-            }
-            Value::Dict(d) => {
-                let entries = d
-                    .borrow()
-                    .iter()
-                    .map(|(key, value)| {
-                        let key_str = match key {
-                            ValueKey::Str(s) => format!("\"{}\"", s),
-                            ValueKey::Bool(b) => b.to_string(),
-                            ValueKey::Num(n) => n.0.to_string(),
-                            ValueKey::Null => "Null".into(),
-                        };
-                        let val_str = if value.get_type() == "Str" {
-                            format!("\"{}\"", value)
-                        } else {
-                            value.to_string()
-                        };
-                        format!("  {}: {}", key_str, val_str)
-                    })
-                    .collect::<Vec<String>>()
-                    .join(",\n");
-
-                if entries.is_empty() {
-                    write!(f, "{{}}")
-                } else {
-                    write!(f, "{{\n{}\n}}", entries)
-                }
-            }
-            Value::Callable(c) => write!(f, "{:?}", c),
-            Value::Obj(o) => write!(f, "{}", o.name),
-            Value::ObjInstance(i) => write!(f, "{}", i.borrow().to_string()),
-        }
+        // without an evaluator instances can't run their to_str() method
+        let s = self
+            .display_with(&mut Vec::new(), &mut |inst| Ok(inst.borrow().to_string()))
+            .map_err(|_| std::fmt::Error)?;
+        write!(f, "{}", s)
     }
 }
 
 impl Value {
     pub fn prototype<'a>(&self, prototypes: &'a ValuePrototypes) -> Option<&'a Prototype> {
         match self {
-            Value::Num(_) => Some(&prototypes.num),
+            Value::Int(_) | Value::Num(_) => Some(&prototypes.num),
             Value::Str(_) => Some(&prototypes.str),
             Value::List(_) => Some(&prototypes.list),
             Value::Bool(_) => Some(&prototypes.bool),
             Value::Dict(_) => Some(&prototypes.dict),
+            Value::Range(_) => Some(&prototypes.range),
             _ => None,
         }
     }
@@ -109,14 +73,42 @@ impl Value {
         match self {
             Value::Null => "Null".to_string(),
             Value::Bool(_) => "Bool".to_string(),
+            Value::Int(_) => "Int".to_string(),
             Value::Num(_) => "Num".to_string(),
             Value::Str(_) => "Str".to_string(),
             Value::List(_) => "List".to_string(),
             Value::Dict(_) => "Dict".to_string(),
+            Value::Range(_) => "Range".to_string(),
             Value::Callable(_) => "Fn".to_string(),
             Value::Obj(_) => "Obj".to_string(),
             Value::ObjInstance(inst) => inst.borrow().obj.name.clone(),
+            Value::Module(_) => "Module".to_string(),
+        }
+    }
+
+    /// Returns true if the value has the given type name, Ints also match Num and instances
+    /// match the names of their object's parents.
+    pub fn is_type(&self, name: &str) -> bool {
+        if let Value::Int(_) = self
+            && name == "Num"
+        {
+            return true;
+        }
+        if let Value::ObjInstance(inst) = self {
+            let inst = inst.borrow();
+            if inst.obj.name == name {
+                return true;
+            }
+            let mut parent = inst.obj.parent.clone();
+            while let Some(obj) = parent {
+                if obj.name == name {
+                    return true;
+                }
+                parent = obj.parent.clone();
+            }
+            return false;
         }
+        self.get_type() == name
     }
 
     pub fn check_type(&self, expected: String, cursor: Cursor) -> EvalResult<bool> {
@@ -134,9 +126,12 @@ impl Value {
         )))
     }
 
+    /// Checks for a number, Ints are converted to floats.
     pub fn check_num(&self, cursor: Cursor, name: Option<String>) -> EvalResult<f64> {
-        if let Value::Num(f) = self {
-            return Ok(f.0);
+        match self {
+            Value::Num(f) => return Ok(f.0),
+            Value::Int(n) => return Ok(n.to_f64()),
+            _ => (),
         }
         let val = match name {
             Some(val) => val,
@@ -149,13 +144,46 @@ impl Value {
         )))
     }
 
-    pub fn check_str(
-        &self,
-        cursor: Cursor,
-        name: Option<String>,
-    ) -> EvalResult<Rc<RefCell<String>>> {
+    /// Checks for an integer, Nums without a fraction are converted to Ints.
+    pub fn check_int(&self, cursor: Cursor, name: Option<String>) -> EvalResult<Int> {
+        match self {
+            Value::Int(n) => return Ok(n.clone()),
+            Value::Num(f) if f.fract() == 0.0 => {
+                if let Some(n) = Int::from_f64(f.0) {
+                    return Ok(n);
+                }
+            }
+            _ => (),
+        }
+        let val = match name {
+            Some(val) => val,
+            None => "value".to_string(),
+        };
+        Err(RuntimeEvent::Err(RuntimeErr::new(
+            ErrKind::Type,
+            format!("expected {} of type Int, found {}", val, self.get_type()),
+            cursor,
+        )))
+    }
+
+    /// The value as a position in a sequence, `None` if it isn't an integer. Negative and huge
+    /// positions saturate to `usize::MAX` so they are out of bounds.
+    pub fn as_index(&self) -> Option<usize> {
+        let n = match self {
+            Value::Int(n) => n.clone(),
+            Value::Num(f) if f.fract() == 0.0 => Int::from_f64(f.0)?,
+            _ => return None,
+        };
+        Some(
+            n.to_i64()
+                .and_then(|n| usize::try_from(n).ok())
+                .unwrap_or(usize::MAX),
+        )
+    }
+
+    pub fn check_str(&self, cursor: Cursor, name: Option<String>) -> EvalResult<Str> {
         if let Value::Str(str) = self {
-            return Ok(Rc::clone(&str));
+            return Ok(str.clone());
         }
         let val = match name {
             Some(val) => val,
@@ -191,6 +219,10 @@ impl Value {
         if let Value::List(list) = self {
             return Ok(Rc::clone(&list));
         }
+        // ranges are materialized so natives expecting a List keep accepting them
+        if let Value::Range(range) = self {
+            return Ok(Rc::new(RefCell::new(range.to_list(cursor)?)));
+        }
         let val = match name {
             Some(val) => val,
             None => "value".to_string(),
@@ -202,55 +234,213 @@ impl Value {
         )))
     }
 
+    /// Display string where instances are converted by `inst_str`. `seen` tracks the containers
+    /// currently being displayed so self-referential values print as `[...]` or `{...}`.
+    pub fn display_with<F>(&self, seen: &mut Vec<usize>, inst_str: &mut F) -> EvalResult<String>
+    where
+        F: FnMut(&Rc<RefCell<Instance>>) -> EvalResult<String>,
+    {
+        // Str values nested inside containers are quoted
+        let mut nested = |val: &Value, seen: &mut Vec<usize>| -> EvalResult<String> {
+            let s = val.display_with(seen, inst_str)?;
+            if let Value::Str(_) = val {
+                return Ok(format!("\"{}\"", s));
+            }
+            Ok(s)
+        };
+
+        match self {
+            Value::Null => Ok("Null".to_string()),
+            Value::Bool(b) => Ok(b.to_string()),
+            Value::Int(n) => Ok(n.to_string()),
+            Value::Num(n) => Ok(n.0.to_string()),
+            Value::Str(s) => Ok(s.borrow().to_string()),
+            Value::List(l) => {
+                let ptr = Rc::as_ptr(l) as usize;
+                if seen.contains(&ptr) {
+                    return Ok("[...]".to_string());
+                }
+
+                seen.push(ptr);
+                let items = l.borrow().clone();
+                let mut parts = Vec::with_capacity(items.len());
+                for item in items.iter() {
+                    parts.push(nested(item, seen)?);
+                }
+                seen.pop();
+
+                Ok(format!("[{}]", parts.join(", ")))
+            }
+            Value::Dict(d) => {
+                let ptr = Rc::as_ptr(d) as usize;
+                if seen.contains(&ptr) {
+                    return Ok("{...}".to_string());
+                }
+
+                seen.push(ptr);
+                let entries = d.borrow().clone();
+                let mut parts = Vec::with_capacity(entries.len());
+                for (key, value) in entries.iter() {
+                    let key_str = match key {
+                        ValueKey::Str(s) => format!("\"{}\"", s),
+                        ValueKey::Bool(b) => b.to_string(),
+                        ValueKey::Int(n) => n.to_string(),
+                        ValueKey::Num(n) => n.0.to_string(),
+                        ValueKey::Null => "Null".into(),
+                    };
+                    parts.push(format!("  {}: {}", key_str, nested(value, seen)?));
+                }
+                seen.pop();
+
+                if parts.is_empty() {
+                    Ok("{}".to_string())
+                } else {
+                    Ok(format!("{{\n{}\n}}", parts.join(",\n")))
+                }
+            }
+            Value::Range(r) => Ok(r.to_string()),
+            Value::Callable(c) => Ok(format!("<fn {}>", c.name())),
+            Value::Obj(o) => Ok(o.name.clone()),
+            Value::ObjInstance(i) => inst_str(i),
+            Value::Module(m) => Ok(format!("<module {}>", m.name)),
+        }
+    }
+
     pub fn is_equal(&self, other: &Value) -> bool {
+        // without an evaluator instances can only be compared by identity
+        self.eq_with(other, &mut Vec::new(), &mut |a, b| Ok(Rc::ptr_eq(a, b)))
+            .unwrap_or(false)
+    }
+
+    /// Structural equality where instance pairs are compared by `inst_eq`. `seen` tracks the
+    /// container pairs currently being compared so self-referential values terminate.
+    pub fn eq_with<F>(
+        &self,
+        other: &Value,
+        seen: &mut Vec<(usize, usize)>,
+        inst_eq: &mut F,
+    ) -> EvalResult<bool>
+    where
+        F: FnMut(&Rc<RefCell<Instance>>, &Rc<RefCell<Instance>>) -> EvalResult<bool>,
+    {
         match self {
             Value::Null => {
                 if let Value::Null = other {
-                    return true;
+                    return Ok(true);
                 }
-                return false;
+                Ok(false)
             }
             Value::Bool(b) => {
                 if let Value::Bool(ob) = other {
-                    return b == ob;
+                    return Ok(b == ob);
                 }
-                return false;
-            }
-            Value::Num(n) => {
-                if let Value::Num(on) = other {
-                    return n == on;
-                }
-                return false;
+                Ok(false)
             }
+            // Ints and Nums are compared by value, a Num only equals the Int it holds exactly
+            Value::Int(n) => match other {
+                Value::Int(on) => Ok(n == on),
+                Value::Num(on) => Ok(on.fract() == 0.0 && Int::from_f64(on.0).as_ref() == Some(n)),
+                _ => Ok(false),
+            },
+            Value::Num(n) => match other {
+                Value::Num(on) => Ok(n == on),
+                Value::Int(on) => Ok(n.fract() == 0.0 && Int::from_f64(n.0).as_ref() == Some(on)),
+                _ => Ok(false),
+            },
             Value::Str(s) => {
                 if let Value::Str(os) = other {
-                    return s == os;
+                    return Ok(s == os);
                 }
-                return false;
+                Ok(false)
             }
-            Value::List(_) => {
-                // TODO: implement list eq
-                return false;
+            Value::List(l) => {
+                if let Value::List(ol) = other {
+                    if Rc::ptr_eq(l, ol) {
+                        return Ok(true);
+                    }
+                    let pair = (Rc::as_ptr(l) as usize, Rc::as_ptr(ol) as usize);
+                    if seen.contains(&pair) {
+                        return Ok(true);
+                    }
+
+                    // compare snapshots so user equals methods can't hit a borrowed list
+                    let (items, other_items) = (l.borrow().clone(), ol.borrow().clone());
+                    if items.len() != other_items.len() {
+                        return Ok(false);
+                    }
+
+                    seen.push(pair);
+                    for (a, b) in items.iter().zip(other_items.iter()) {
+                        if !a.eq_with(b, seen, inst_eq)? {
+                            seen.pop();
+                            return Ok(false);
+                        }
+                    }
+                    seen.pop();
+                    return Ok(true);
+                }
+                Ok(false)
             }
-            Value::Dict(_) => {
-                // TODO: implement dict eq
-                return false;
+            Value::Dict(d) => {
+                if let Value::Dict(od) = other {
+                    if Rc::ptr_eq(d, od) {
+                        return Ok(true);
+                    }
+                    let pair = (Rc::as_ptr(d) as usize, Rc::as_ptr(od) as usize);
+                    if seen.contains(&pair) {
+                        return Ok(true);
+                    }
+
+                    let (entries, other_entries) = (d.borrow().clone(), od.borrow().clone());
+                    if entries.len() != other_entries.len() {
+                        return Ok(false);
+                    }
+
+                    seen.push(pair);
+                    for (key, val) in entries.iter() {
+                        let equal = match other_entries.get(key) {
+                            Some(other_val) => val.eq_with(other_val, seen, inst_eq)?,
+                            None => false,
+                        };
+                        if !equal {
+                            seen.pop();
+                            return Ok(false);
+                        }
+                    }
+                    seen.pop();
+                    return Ok(true);
+                }
+                Ok(false)
+            }
+            Value::Range(r) => {
+                if let Value::Range(or) = other {
+                    return Ok(r == or);
+                }
+                Ok(false)
             }
             Value::Obj(o) => {
                 if let Value::Obj(oo) = other {
-                    return o.name == oo.name;
+                    return Ok(o.name == oo.name);
                 }
-                return false;
+                Ok(false)
             }
             Value::Callable(c) => {
                 if let Value::Callable(oc) = other {
-                    return c.name() == oc.name();
+                    return Ok(c.name() == oc.name());
+                }
+                Ok(false)
+            }
+            Value::ObjInstance(i) => {
+                if let Value::ObjInstance(oi) = other {
+                    return inst_eq(i, oi);
                 }
-                return false;
+                Ok(false)
             }
-            Value::ObjInstance(_) => {
-                // TODO: implement obj instance eq
-                return false;
+            Value::Module(m) => {
+                if let Value::Module(om) = other {
+                    return Ok(Rc::ptr_eq(&m.env, &om.env));
+                }
+                Ok(false)
             }
         }
     }
@@ -260,32 +450,146 @@ impl Value {
         match self {
             Value::Bool(b) => *b,
             Value::Null => false,
+            Value::Int(n) => !n.is_zero(),
             Value::Num(n) => *n != 0.,
             _ => true,
         }
     }
 
-    pub fn add_assign(&self, rhs: Value, cursor: Cursor) -> EvalResult<Value> {
-        match self {
-            // number += number
-            Value::Num(n) => {
-                if let Value::Num(m) = rhs {
-                    Ok(Value::Num(OrderedFloat(n.0 + m.0)))
-                } else {
-                    Err(RuntimeEvent::error(
-                        ErrKind::Type,
-                        "cannot add-asssign non-Num to Num".into(),
+    /// Applies an arithmetic, bitwise or comparison operator to two numbers, `None` if either
+    /// value isn't a number. Operations on two Ints give Ints, except `/` and `**` with a
+    /// negative exponent, everything else is done on floats. Bitwise operators only take
+    /// integers.
+    pub fn num_op(&self, op: &BinaryOp, rhs: &Value, cursor: Cursor) -> Option<EvalResult<Value>> {
+        let is_num = |val: &Value| matches!(val, Value::Int(_) | Value::Num(_));
+        if !is_num(self) || !is_num(rhs) {
+            return None;
+        }
+        if matches!(
+            op,
+            BinaryOp::BitAnd
+                | BinaryOp::BitOr
+                | BinaryOp::BitXor
+                | BinaryOp::ShiftLeft
+                | BinaryOp::ShiftRight
+        ) {
+            return Some(self.bit_op(op, rhs, cursor));
+        }
+
+        let div_by_zero = || RuntimeEvent::error(ErrKind::Value, "division by zero".into(), cursor);
+
+        if let (Value::Int(l), Value::Int(r)) = (self, rhs) {
+            return Some(Ok(match op {
+                BinaryOp::Add => Value::Int(l + r),
+                BinaryOp::Sub => Value::Int(l - r),
+                BinaryOp::Mult => Value::Int(l * r),
+                BinaryOp::Div => Value::Num(OrderedFloat(l.to_f64() / r.to_f64())),
+                BinaryOp::FloorDiv => match l.checked_div_floor(r) {
+                    Some(n) => Value::Int(n),
+                    None => return Some(Err(div_by_zero())),
+                },
+                BinaryOp::Mod => match l.checked_mod_floor(r) {
+                    Some(n) => Value::Int(n),
+                    None => return Some(Err(div_by_zero())),
+                },
+                BinaryOp::Pow if r.is_negative() => {
+                    Value::Num(OrderedFloat(l.to_f64().powf(r.to_f64())))
+                }
+                BinaryOp::Pow => match r.to_i64().and_then(|r| u32::try_from(r).ok()) {
+                    Some(exp) => Value::Int(l.pow(exp)),
+                    None => {
+                        return Some(Err(RuntimeEvent::error(
+                            ErrKind::Value,
+                            "exponent too large".into(),
+                            cursor,
+                        )));
+                    }
+                },
+                BinaryOp::Greater => Value::Bool(l > r),
+                BinaryOp::GreaterEquals => Value::Bool(l >= r),
+                BinaryOp::Lesser => Value::Bool(l < r),
+                BinaryOp::LesserEquals => Value::Bool(l <= r),
+                _ => return None,
+            }));
+        }
+
+        let num = |val: &Value| match val {
+            Value::Int(n) => Some(n.to_f64()),
+            Value::Num(n) => Some(n.0),
+            _ => None,
+        };
+        let (l, r) = (num(self)?, num(rhs)?);
+        Some(Ok(match op {
+            BinaryOp::Add => Value::Num(OrderedFloat(l + r)),
+            BinaryOp::Sub => Value::Num(OrderedFloat(l - r)),
+            BinaryOp::Mult => Value::Num(OrderedFloat(l * r)),
+            BinaryOp::Div => Value::Num(OrderedFloat(l / r)),
+            BinaryOp::FloorDiv => Value::Num(OrderedFloat((l / r).floor())),
+            // the remainder takes the sign of the divisor, like the floor division
+            BinaryOp::Mod => match l % r {
+                rem if rem != 0.0 && (rem < 0.0) != (r < 0.0) => Value::Num(OrderedFloat(rem + r)),
+                rem => Value::Num(OrderedFloat(rem)),
+            },
+            BinaryOp::Pow => Value::Num(OrderedFloat(l.powf(r))),
+            BinaryOp::Greater => Value::Bool(l > r),
+            BinaryOp::GreaterEquals => Value::Bool(l >= r),
+            BinaryOp::Lesser => Value::Bool(l < r),
+            BinaryOp::LesserEquals => Value::Bool(l <= r),
+            _ => return None,
+        }))
+    }
+
+    fn bit_op(&self, op: &BinaryOp, rhs: &Value, cursor: Cursor) -> EvalResult<Value> {
+        let l = self.check_int(cursor, Some("left operand".into()))?;
+        let r = rhs.check_int(cursor, Some("right operand".into()))?;
+        Ok(Value::Int(match op {
+            BinaryOp::BitAnd => &l & &r,
+            BinaryOp::BitOr => &l | &r,
+            BinaryOp::BitXor => &l ^ &r,
+            _ => {
+                if r.is_negative() {
+                    return Err(RuntimeEvent::error(
+                        ErrKind::Value,
+                        "negative shift amount".into(),
                         cursor,
-                    ))
+                    ));
+                }
+                let Some(n) = r.to_i64().and_then(|r| u32::try_from(r).ok()) else {
+                    return Err(RuntimeEvent::error(
+                        ErrKind::Value,
+                        "shift amount too large".into(),
+                        cursor,
+                    ));
+                };
+                match op {
+                    BinaryOp::ShiftLeft => &l << n,
+                    _ => &l >> n,
                 }
             }
+        }))
+    }
+
+    pub fn add_assign(
+        &self,
+        evaluator: &mut Evaluator,
+        rhs: Value,
+        cursor: Cursor,
+    ) -> EvalResult<Value> {
+        match self {
+            // number += number
+            Value::Int(_) | Value::Num(_) => match self.num_op(&BinaryOp::Add, &rhs, cursor) {
+                Some(res) => res,
+                None => Err(RuntimeEvent::error(
+                    ErrKind::Type,
+                    "cannot add-asssign non-Num to Num".into(),
+                    cursor,
+                )),
+            },
 
-            // string += anything -> string append
+            // string += anything -> new string, appended in place when nothing else follows it
             Value::Str(s) => {
-                let mut s_mut = s.borrow_mut();
-                s_mut.push_str(rhs.to_string().as_str());
-                // return same string value (Rc)
-                Ok(Value::Str(s.clone()))
+                let rhs_str = evaluator.stringify(&rhs, cursor)?;
+                Ok(Value::Str(s.append(&rhs_str)))
             }
 
             // list += elem -> push
@@ -294,7 +598,9 @@ impl Value {
                 Ok(Value::List(vec.clone()))
             }
 
-            // you might want to support ObjInstance here, etc.
+            // instance += value -> add(value)
+            Value::ObjInstance(inst) => evaluator.operator_method(inst, "add", vec![rhs], cursor),
+
             _ => Err(RuntimeEvent::error(
                 ErrKind::Type,
                 "invalid left-hand side for '+='".into(),
@@ -304,19 +610,24 @@ impl Value {
     }
 
     /// v -= rhs
-    pub fn sub_assign(&self, rhs: Value, cursor: Cursor) -> EvalResult<Value> {
+    pub fn sub_assign(
+        &self,
+        evaluator: &mut Evaluator,
+        rhs: Value,
+        cursor: Cursor,
+    ) -> EvalResult<Value> {
         match self {
-            Value::Num(n) => {
-                if let Value::Num(m) = rhs {
-                    Ok(Value::Num(OrderedFloat(n.0 - m.0)))
-                } else {
-                    Err(RuntimeEvent::error(
-                        ErrKind::Type,
-                        "cannot sub-assign non-Num from Num".into(),
-                        cursor,
-                    ))
-                }
-            }
+            Value::Int(_) | Value::Num(_) => match self.num_op(&BinaryOp::Sub, &rhs, cursor) {
+                Some(res) => res,
+                None => Err(RuntimeEvent::error(
+                    ErrKind::Type,
+                    "cannot sub-assign non-Num from Num".into(),
+                    cursor,
+                )),
+            },
+
+            // instance -= value -> sub(value)
+            Value::ObjInstance(inst) => evaluator.operator_method(inst, "sub", vec![rhs], cursor),
 
             // TODO: list -= ???
             // TODO: string -= ???
@@ -331,13 +642,85 @@ impl Value {
 
 pub trait Callable: Debug {
     fn name(&self) -> &str;
-    fn arity(&self) -> usize;
+    fn arity(&self) -> Arity;
     fn call(
         &self,
         evaluator: &mut Evaluator,
         args: Vec<Value>,
         cursor: Cursor,
     ) -> EvalResult<Value>;
+
+    /// The user function behind the callable, only those can be tail called.
+    fn as_function(&self) -> Option<&Function> {
+        None
+    }
+
+    /// The compiled function behind the callable with the instance bound to it, the VM runs
+    /// those in its own frames.
+    fn as_closure(&self) -> Option<(&Closure, Option<&Value>)> {
+        None
+    }
+
+    /// Names of the parameters that can be passed as named arguments, in order. Callables
+    /// without names only take positional arguments.
+    fn param_names(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Calls with arguments matched to parameters by name, `None` marks a parameter skipped by
+    /// the call. Only callables with default parameter values can fill those in.
+    fn call_named(
+        &self,
+        evaluator: &mut Evaluator,
+        args: Vec<Option<Value>>,
+        cursor: Cursor,
+    ) -> EvalResult<Value> {
+        let Some(args) = args.into_iter().collect::<Option<Vec<_>>>() else {
+            return Err(RuntimeEvent::error(
+                ErrKind::Arity,
+                format!("function '{}' can't skip arguments", self.name()),
+                cursor,
+            ));
+        };
+        self.call(evaluator, args, cursor)
+    }
+}
+
+/// Number of arguments a Callable accepts, `max` is `None` for variadic callables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Arity {
+    pub min: usize,
+    pub max: Option<usize>,
+}
+
+impl Arity {
+    pub fn new(min: usize, max: Option<usize>) -> Self {
+        Self { min, max }
+    }
+
+    pub fn exact(n: usize) -> Self {
+        Self::new(n, Some(n))
+    }
+
+    pub fn accepts(&self, n: usize) -> bool {
+        n >= self.min && self.max.is_none_or(|max| n <= max)
+    }
+}
+
+impl From<usize> for Arity {
+    fn from(n: usize) -> Self {
+        Self::exact(n)
+    }
+}
+
+impl Display for Arity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.max {
+            Some(max) if max == self.min => write!(f, "{}", max),
+            Some(max) => write!(f, "{} to {}", self.min, max),
+            None => write!(f, "at least {}", self.min),
+        }
+    }
 }
 
 // Hashable value types that can be used as Dict keys
@@ -345,6 +728,7 @@ pub trait Callable: Debug {
 pub enum ValueKey {
     Null,
     Bool(bool),
+    Int(Int),
     Num(OrderedFloat<f64>),
     Str(String),
 }
@@ -356,20 +740,39 @@ impl TryFrom<&Value> for ValueKey {
         match value {
             Value::Null => Ok(ValueKey::Null),
             Value::Bool(b) => Ok(ValueKey::Bool(*b)),
+            Value::Int(n) => Ok(ValueKey::Int(n.clone())),
+            // Nums equal to an Int are the same key as it
+            Value::Num(n) if n.fract() == 0.0 => {
+                Ok(Int::from_f64(n.0).map_or(ValueKey::Num(*n), ValueKey::Int))
+            }
             Value::Num(n) => Ok(ValueKey::Num(*n)),
-            Value::Str(s) => Ok(ValueKey::Str((*s.deref().borrow().deref()).clone())),
+            Value::Str(s) => Ok(ValueKey::Str(s.borrow().to_string())),
             _ => Err(()),
         }
     }
 }
 
+impl From<&LiteralType> for Value {
+    fn from(literal: &LiteralType) -> Self {
+        match literal {
+            LiteralType::Null => Value::Null,
+            LiteralType::Int(n) => Value::Int(n.clone()),
+            LiteralType::Num(n) => Value::Num(*n),
+            LiteralType::Bool(b) => Value::Bool(*b),
+            LiteralType::Str(s) => Value::Str(Str::new(s.clone())),
+            LiteralType::Range(range) => Value::Range(Rc::new(range.clone())),
+        }
+    }
+}
+
 impl Into<Value> for ValueKey {
     fn into(self) -> Value {
         match self {
             ValueKey::Null => Value::Null,
             ValueKey::Bool(b) => Value::Bool(b),
+            ValueKey::Int(n) => Value::Int(n),
             ValueKey::Num(n) => Value::Num(n),
-            ValueKey::Str(s) => Value::Str(Rc::new(RefCell::new(s))),
+            ValueKey::Str(s) => Value::Str(Str::new(s)),
         }
     }
 }
@@ -4,7 +4,7 @@ pub mod token;
 use std::str::FromStr;
 
 use crate::lexer::cursor::Cursor;
-use crate::lexer::token::{KeywordKind, Token, TokenKind};
+use crate::lexer::token::{KeywordKind, StrPart, Token, TokenKind};
 
 #[derive(Default, Clone)]
 pub struct LexerOutput {
@@ -88,10 +88,7 @@ impl Lexer {
 
         let token = match c {
             // Types
-            '"' => {
-                let s = self.consume_string();
-                Some(TokenKind::Str(s))
-            }
+            '"' => Some(self.consume_string()),
             // Assign
             '=' => {
                 if self.consume('=') {
@@ -137,6 +134,11 @@ impl Lexer {
                 Some(TokenKind::Mult)
             }
             '/' => {
+                if self.consume('/') {
+                    self.next();
+                    return Some(TokenKind::FloorDiv);
+                }
+
                 self.next();
                 Some(TokenKind::Div)
             }
@@ -144,11 +146,27 @@ impl Lexer {
                 self.next();
                 Some(TokenKind::Mod)
             }
+            // Bitwise ops
+            '&' => {
+                self.next();
+                Some(TokenKind::BitAnd)
+            }
+            '^' => {
+                self.next();
+                Some(TokenKind::BitXor)
+            }
+            '~' => {
+                self.next();
+                Some(TokenKind::BitNot)
+            }
             // Bool ops
             '<' => {
                 if self.consume('=') {
                     self.next();
                     return Some(TokenKind::LesserEquals);
+                } else if self.consume('<') {
+                    self.next();
+                    return Some(TokenKind::ShiftLeft);
                 }
 
                 self.next();
@@ -158,6 +176,9 @@ impl Lexer {
                 if self.consume('=') {
                     self.next();
                     return Some(TokenKind::GreaterEquals);
+                } else if self.consume('>') {
+                    self.next();
+                    return Some(TokenKind::ShiftRight);
                 }
 
                 self.next();
@@ -214,8 +235,17 @@ impl Lexer {
                 self.next();
                 Some(TokenKind::Comma)
             }
+            '|' => {
+                self.next();
+                Some(TokenKind::Pipe)
+            }
             '.' => {
                 if self.consume('.') {
+                    if self.consume('.') {
+                        self.next();
+                        return Some(TokenKind::Ellipsis);
+                    }
+
                     if self.consume('=') {
                         self.next();
                         return Some(TokenKind::RangeEq);
@@ -263,7 +293,7 @@ impl Lexer {
 
                 if let Some(num) = self.check_num() {
                     self.next();
-                    return Some(TokenKind::Num(num));
+                    return Some(num);
                 }
 
                 // check keywords, assume identifiers if it doesn't match any
@@ -308,57 +338,99 @@ impl Lexer {
         None
     }
 
-    fn check_num(&mut self) -> Option<String> {
-        if !self.current().is_numeric() {
+    /// Lexes a number literal. Literals with a fraction or an exponent are Nums, other ones are
+    /// Ints, which can also be written in hex, binary or octal with a `0x`, `0b` or `0o` prefix.
+    /// Digits can be separated with `_`, the separators are left out of the literal.
+    fn check_num(&mut self) -> Option<TokenKind> {
+        if !self.current().is_ascii_digit() {
             return None;
         }
 
+        if self.current() == '0'
+            && let Some(radix) = match self.peek() {
+                'x' | 'X' => Some(16),
+                'b' | 'B' => Some(2),
+                'o' | 'O' => Some(8),
+                _ => None,
+            }
+        {
+            let prefix: String = [self.current(), self.peek().to_ascii_lowercase()]
+                .iter()
+                .collect();
+            self.next(); // move onto the prefix letter
+            let mut num = prefix.clone();
+            if !self.consume_digits(&mut num, radix) {
+                self.add_err(format!("expected digits after '{}'", prefix));
+            } else if self.peek().is_ascii_alphanumeric() {
+                self.next(); // move onto the invalid digit
+                self.add_err(format!(
+                    "invalid digit '{}' in base {} literal",
+                    self.current(),
+                    radix
+                ));
+                // skip the rest of the literal so it isn't lexed as another token
+                while self.peek().is_ascii_alphanumeric() {
+                    self.next();
+                }
+            }
+            return Some(TokenKind::Int(num));
+        }
+
         let mut num = String::new();
-        let mut seen_dot = false;
+        let mut is_num = false;
 
         // consume the first digit (current)
         num.push(self.current());
+        self.consume_digits(&mut num, 10);
+
+        // optional '.' with a digit after it
+        if self.peek() == '.' && self.peek_nth(2).is_ascii_digit() {
+            is_num = true;
+            self.next(); // move onto '.'
+            num.push('.');
+            self.consume_digits(&mut num, 10);
+        }
 
-        loop {
-            let nxt = self.peek();
-
-            // more digits?
-            if nxt.is_numeric() {
-                self.next(); // move onto that digit
-                num.push(self.current());
-                continue;
-            }
-
-            // optional single '.' with a digit after it
-            if !seen_dot && nxt == '.' {
-                // ensure we have a digit after the dot
-                let after_dot = if self.curr + 2 < self.src.len() {
-                    self.src[self.curr + 2]
-                } else {
-                    ' '
-                };
-                if after_dot.is_numeric() {
-                    seen_dot = true;
-                    self.next(); // move onto '.'
-                    num.push('.');
-
-                    self.next(); // move onto first frac digit
+        // optional exponent with a digit after it or after its sign
+        if matches!(self.peek(), 'e' | 'E') {
+            let sign = matches!(self.peek_nth(2), '+' | '-');
+            let digit = self.peek_nth(if sign { 3 } else { 2 });
+            if digit.is_ascii_digit() {
+                is_num = true;
+                self.next(); // move onto 'e'
+                num.push('e');
+                if sign {
+                    self.next();
                     num.push(self.current());
-
-                    // consume remaining fractional digits
-                    while self.peek().is_numeric() {
-                        self.next();
-                        num.push(self.current());
-                    }
-                    continue;
                 }
+                self.consume_digits(&mut num, 10);
             }
+        }
 
-            // next char is not part of the number → stop WITHOUT advancing
-            break;
+        if is_num {
+            Some(TokenKind::Num(num))
+        } else {
+            Some(TokenKind::Int(num))
         }
+    }
 
-        Some(num)
+    /// Consumes the digits following the current char into `num`, skipping `_` separators
+    /// between digits. Stops before the first char that isn't part of the number, returns
+    /// whether any digit was consumed.
+    fn consume_digits(&mut self, num: &mut String, radix: u32) -> bool {
+        let mut consumed = false;
+        loop {
+            let nxt = self.peek();
+            if nxt.is_digit(radix) {
+                self.next();
+                num.push(nxt);
+                consumed = true;
+            } else if nxt == '_' && self.peek_nth(2).is_digit(radix) {
+                self.next(); // move onto '_', the digit is pushed next
+            } else {
+                return consumed;
+            }
+        }
     }
 
     // Iter utils
@@ -389,11 +461,16 @@ impl Lexer {
     }
 
     fn peek(&self) -> char {
-        if self.curr + 1 >= self.src.len() {
+        self.peek_nth(1)
+    }
+
+    /// Returns the char `n` chars after the current one.
+    fn peek_nth(&self, n: usize) -> char {
+        if self.curr + n >= self.src.len() {
             return ' ';
         }
 
-        self.src[self.curr + 1]
+        self.src[self.curr + n]
     }
 
     fn consume(&mut self, c: char) -> bool {
@@ -429,7 +506,8 @@ impl Lexer {
         false
     }
 
-    fn consume_string(&mut self) -> String {
+    fn consume_string(&mut self) -> TokenKind {
+        let mut parts: Vec<StrPart> = Vec::new();
         let mut out = String::new();
         // skip opening quote
         self.next();
@@ -452,6 +530,8 @@ impl Lexer {
                     'n' => Some('\n'),
                     't' => Some('\t'),
                     'r' => Some('\r'),
+                    '{' => Some('{'),
+                    '}' => Some('}'),
                     _ => None,
                 };
                 // advance over the escape char
@@ -467,20 +547,92 @@ impl Lexer {
                 }
             }
 
+            if ch == '{' {
+                let brace_cursor = self.cursor;
+                // skip opening brace, the expression is lexed from its first char
+                self.next();
+                let expr_cursor = self.cursor;
+                let Some(expr_src) = self.consume_interpolation() else {
+                    self.add_err_at(
+                        "unterminated interpolation in string literal".into(),
+                        brace_cursor,
+                    );
+                    return TokenKind::Str(out);
+                };
+
+                if !out.is_empty() {
+                    parts.push(StrPart::Lit(std::mem::take(&mut out)));
+                }
+
+                let mut lexer = Lexer::with_cursor(expr_src, expr_cursor);
+                let expr_out = lexer.tokenize();
+                if let Some(errors) = expr_out.errors {
+                    self.out.error_count += errors.len();
+                    self.out.errors.get_or_insert(Vec::new()).extend(errors);
+                }
+                parts.push(StrPart::Expr(expr_out.tokens.unwrap_or_default()));
+                continue;
+            }
+
             out.push(ch);
             self.next();
         }
 
         if !terminated {
-            self.out.error_count += 1;
-            let err = LexErr {
-                msg: "unterminated string literal".into(),
-                cursor: self.cursor,
-            };
-            self.out.errors.get_or_insert(Vec::new()).push(err.clone());
+            self.add_err("unterminated string literal".into());
+        }
+
+        if parts.is_empty() {
+            return TokenKind::Str(out);
+        }
+        if !out.is_empty() {
+            parts.push(StrPart::Lit(out));
         }
+        TokenKind::InterpStr(parts)
+    }
+
+    /// Consumes the source of an interpolated expression up to its closing brace, skipping over
+    /// nested braces and strings. Returns `None` if the source ends first.
+    fn consume_interpolation(&mut self) -> Option<String> {
+        let mut src = String::new();
+        let mut depth = 0;
+        let mut in_str = false;
+
+        while !self.is_at_end() {
+            let ch = self.current();
+            self.next();
+
+            if in_str {
+                if ch == '\\' && !self.is_at_end() {
+                    src.push(ch);
+                    src.push(self.current());
+                    self.next();
+                    continue;
+                }
+                in_str = ch != '"';
+            } else {
+                match ch {
+                    '"' => in_str = true,
+                    '{' => depth += 1,
+                    '}' if depth == 0 => return Some(src),
+                    '}' => depth -= 1,
+                    _ => {}
+                }
+            }
+            src.push(ch);
+        }
+
+        None
+    }
+
+    fn add_err(&mut self, msg: String) {
+        self.add_err_at(msg, self.cursor);
+    }
 
-        out
+    fn add_err_at(&mut self, msg: String, cursor: Cursor) {
+        self.out.error_count += 1;
+        let err = LexErr { msg, cursor };
+        self.out.errors.get_or_insert(Vec::new()).push(err);
     }
 
     fn get_lexeme(&self) -> String {
@@ -527,7 +679,7 @@ mod tests {
             vec![
                 TokenKind::Identifier("a".into()),
                 TokenKind::Assign,
-                TokenKind::Num("10".into()),
+                TokenKind::Int("10".into()),
                 TokenKind::EOL,
                 TokenKind::EOF
             ]
@@ -576,7 +728,7 @@ mod tests {
                 TokenKind::Keyword(KeywordKind::If),
                 TokenKind::Identifier("a".into()),
                 TokenKind::Equals,
-                TokenKind::Num("100".into()),
+                TokenKind::Int("100".into()),
                 TokenKind::Keyword(KeywordKind::Do),
                 TokenKind::EOL,
                 TokenKind::Keyword(KeywordKind::End),
@@ -646,6 +798,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn interpolated_string() {
+        let kinds = tokens("\"a{b}\\{c\"\n");
+        let TokenKind::InterpStr(parts) = &kinds[0] else {
+            panic!("expected InterpStr, found {:?}", kinds[0]);
+        };
+        assert_eq!(parts.len(), 3);
+        assert_eq!(parts[0], StrPart::Lit("a".into()));
+        let StrPart::Expr(expr) = &parts[1] else {
+            panic!("expected interpolated expression, found {:?}", parts[1]);
+        };
+        assert_eq!(
+            expr.iter().map(|t| t.kind.clone()).collect::<Vec<_>>(),
+            vec![
+                TokenKind::Identifier("b".into()),
+                TokenKind::EOL,
+                TokenKind::EOF
+            ]
+        );
+        // expression tokens keep their location inside the string
+        assert_eq!((expr[0].cursor.line, expr[0].cursor.col), (0, 4));
+        assert_eq!(parts[2], StrPart::Lit("{c".into()));
+    }
+
+    #[test]
+    fn unterminated_interpolation_points_at_brace() {
+        let mut lx = Lexer::new("\"ab{c + 1\nd\n".to_string());
+        let out = lx.tokenize();
+        assert_eq!(out.error_count, 1);
+        let err = &out.errors.unwrap()[0];
+        assert_eq!(err.msg, "unterminated interpolation in string literal");
+        assert_eq!((err.cursor.line, err.cursor.col), (0, 3));
+    }
+
     #[test]
     fn comment_then_identifier() {
         // Assumes you EMIT a Comment token and then an EOL after it.
@@ -660,6 +846,41 @@ mod tests {
         );
     }
 
+    #[test]
+    fn short_lambda() {
+        assert_eq!(
+            tokens("|x| x\n"),
+            vec![
+                TokenKind::Pipe,
+                TokenKind::Identifier("x".into()),
+                TokenKind::Pipe,
+                TokenKind::Identifier("x".into()),
+                TokenKind::EOL,
+                TokenKind::EOF
+            ]
+        );
+    }
+
+    #[test]
+    fn ellipsis_vs_range() {
+        assert_eq!(
+            tokens("[a, ...b] 0..1\n"),
+            vec![
+                TokenKind::LBracket,
+                TokenKind::Identifier("a".into()),
+                TokenKind::Comma,
+                TokenKind::Ellipsis,
+                TokenKind::Identifier("b".into()),
+                TokenKind::RBracket,
+                TokenKind::Int("0".into()),
+                TokenKind::Range,
+                TokenKind::Int("1".into()),
+                TokenKind::EOL,
+                TokenKind::EOF
+            ]
+        );
+    }
+
     #[test]
     fn keywords_vs_identifiers() {
         assert_eq!(
@@ -675,4 +896,80 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn ints_vs_nums() {
+        assert_eq!(
+            tokens("7 // 2.5 / 10\n"),
+            vec![
+                TokenKind::Int("7".into()),
+                TokenKind::FloorDiv,
+                TokenKind::Num("2.5".into()),
+                TokenKind::Div,
+                TokenKind::Int("10".into()),
+                TokenKind::EOL,
+                TokenKind::EOF
+            ]
+        );
+    }
+
+    #[test]
+    fn radix_and_exponent_literals() {
+        assert_eq!(
+            tokens("0xFF_ff 0b1010 0o17 1_000 1.5e-3 2E8 3..4\n"),
+            vec![
+                TokenKind::Int("0xFFff".into()),
+                TokenKind::Int("0b1010".into()),
+                TokenKind::Int("0o17".into()),
+                TokenKind::Int("1000".into()),
+                TokenKind::Num("1.5e-3".into()),
+                TokenKind::Num("2e8".into()),
+                TokenKind::Int("3".into()),
+                TokenKind::Range,
+                TokenKind::Int("4".into()),
+                TokenKind::EOL,
+                TokenKind::EOF
+            ]
+        );
+    }
+
+    #[test]
+    fn bitwise_ops() {
+        assert_eq!(
+            tokens("~a & b | c ^ d << 2 >> 1 <= e\n"),
+            vec![
+                TokenKind::BitNot,
+                TokenKind::Identifier("a".into()),
+                TokenKind::BitAnd,
+                TokenKind::Identifier("b".into()),
+                TokenKind::Pipe,
+                TokenKind::Identifier("c".into()),
+                TokenKind::BitXor,
+                TokenKind::Identifier("d".into()),
+                TokenKind::ShiftLeft,
+                TokenKind::Int("2".into()),
+                TokenKind::ShiftRight,
+                TokenKind::Int("1".into()),
+                TokenKind::LesserEquals,
+                TokenKind::Identifier("e".into()),
+                TokenKind::EOL,
+                TokenKind::EOF
+            ]
+        );
+    }
+
+    #[test]
+    fn radix_prefix_without_digits() {
+        let mut lx = Lexer::new("0x\n".to_string());
+        assert_eq!(lx.tokenize().error_count, 1);
+    }
+
+    #[test]
+    fn invalid_radix_digit() {
+        let mut lx = Lexer::new("0b102 0o78 0xFG\n".to_string());
+        let out = lx.tokenize();
+        assert_eq!(out.error_count, 3);
+        let msgs: Vec<String> = out.errors.unwrap().into_iter().map(|e| e.msg).collect();
+        assert_eq!(msgs[0], "invalid digit '2' in base 2 literal");
+    }
 }
@@ -6,9 +6,11 @@ use crate::lexer::cursor::Cursor;
 #[derive(Debug, PartialEq, Clone, EnumDiscriminants)]
 pub enum TokenKind {
     // Literals
+    Int(String),
     Num(String),
     Bool(bool),
     Str(String),
+    InterpStr(Vec<StrPart>),
     Null,
     // Assign
     Assign,
@@ -21,8 +23,15 @@ pub enum TokenKind {
     Sub,
     Mult,
     Div,
+    FloorDiv,
     Mod,
     Pow,
+    // Bitwise, `|` is a Pipe
+    BitAnd,
+    BitXor,
+    BitNot,
+    ShiftLeft,
+    ShiftRight,
     // Boolean
     Not,
     Equals,
@@ -45,6 +54,8 @@ pub enum TokenKind {
     Question,
     Range,
     RangeEq,
+    Ellipsis,
+    Pipe,
     // Other
     Keyword(KeywordKind),
     Identifier(String),
@@ -56,9 +67,11 @@ impl ToString for TokenKindDiscriminants {
     fn to_string(&self) -> String {
         match self {
             // Literals
+            TokenKindDiscriminants::Int => "Int",
             TokenKindDiscriminants::Num => "Num",
             TokenKindDiscriminants::Bool => "Bool",
             TokenKindDiscriminants::Str => "Str",
+            TokenKindDiscriminants::InterpStr => "InterpStr",
 
             // Assign
             TokenKindDiscriminants::Assign => "Assign",
@@ -72,9 +85,17 @@ impl ToString for TokenKindDiscriminants {
             TokenKindDiscriminants::Sub => "Sub",
             TokenKindDiscriminants::Mult => "Mult",
             TokenKindDiscriminants::Div => "Div",
+            TokenKindDiscriminants::FloorDiv => "FloorDiv",
             TokenKindDiscriminants::Mod => "Mod",
             TokenKindDiscriminants::Pow => "Pow",
 
+            // Bitwise
+            TokenKindDiscriminants::BitAnd => "BitAnd",
+            TokenKindDiscriminants::BitXor => "BitXor",
+            TokenKindDiscriminants::BitNot => "BitNot",
+            TokenKindDiscriminants::ShiftLeft => "ShiftLeft",
+            TokenKindDiscriminants::ShiftRight => "ShiftRight",
+
             // Boolean
             TokenKindDiscriminants::Not => "Not",
             TokenKindDiscriminants::Equals => "Equals",
@@ -98,6 +119,8 @@ impl ToString for TokenKindDiscriminants {
             TokenKindDiscriminants::Question => "Question",
             TokenKindDiscriminants::Range => "Range",
             TokenKindDiscriminants::RangeEq => "RangeEq",
+            TokenKindDiscriminants::Ellipsis => "Ellipsis",
+            TokenKindDiscriminants::Pipe => "Pipe",
 
             // Other
             TokenKindDiscriminants::Keyword => "Keyword",
@@ -123,7 +146,9 @@ pub enum KeywordKind {
     Break,
     Continue,
     Use,
+    As,
     KSelf,
+    Super,
     Var,
     And,
     Or,
@@ -151,7 +176,9 @@ impl ToString for KeywordKind {
             KeywordKind::Break => "break",
             KeywordKind::Continue => "continue",
             KeywordKind::Use => "use",
+            KeywordKind::As => "as",
             KeywordKind::KSelf => "self",
+            KeywordKind::Super => "super",
             KeywordKind::Var => "var",
             KeywordKind::And => "and",
             KeywordKind::Or => "or",
@@ -184,7 +211,9 @@ impl FromStr for KeywordKind {
             "break" => Ok(KeywordKind::Break),
             "continue" => Ok(KeywordKind::Continue),
             "use" => Ok(KeywordKind::Use),
+            "as" => Ok(KeywordKind::As),
             "self" => Ok(KeywordKind::KSelf),
+            "super" => Ok(KeywordKind::Super),
             "var" => Ok(KeywordKind::Var),
             "and" => Ok(KeywordKind::And),
             "or" => Ok(KeywordKind::Or),
@@ -201,7 +230,15 @@ impl FromStr for KeywordKind {
     }
 }
 
-#[derive(Debug, Clone)]
+/// Part of an interpolated string literal
+#[derive(Debug, PartialEq, Clone)]
+pub enum StrPart {
+    Lit(String),
+    /// Tokens of an interpolated `{expr}`, ending with EOF
+    Expr(Vec<Token>),
+}
+
+#[derive(Debug, PartialEq, Clone)]
 pub struct Token {
     /// Kind of the token
     pub kind: TokenKind,
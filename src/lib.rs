@@ -0,0 +1,7 @@
+pub mod evaluator;
+pub mod lexer;
+pub mod parser;
+pub mod repl;
+pub mod reporter;
+pub mod src;
+pub mod vm;
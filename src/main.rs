@@ -1,21 +1,27 @@
 use clap::Parser as ClapParser;
-use std::path::PathBuf;
+use std::{cell::RefCell, path::PathBuf, rc::Rc};
 
-use crate::{
-    evaluator::{Evaluator, resolver::Resolver},
+use quetite::{
+    evaluator::{
+        DEFAULT_RECURSION_LIMIT, Evaluator, loader::Loader, optimizer::Optimizer,
+        resolver::Resolver,
+    },
     lexer::Lexer,
     parser::Parser,
     repl::Repl,
     reporter::Reporter,
     src::Src,
+    vm::Vm,
 };
 
-pub mod evaluator;
-pub mod lexer;
-pub mod parser;
-pub mod repl;
-pub mod reporter;
-pub mod src;
+/// Stack size of the interpreter thread, deep enough for the default recursion limit.
+const STACK_SIZE: usize = 1024 * 1024 * 1024;
+/// Native stack a single nested call may take up on the interpreter thread, with some headroom.
+/// Operator methods and natives calling closures on the VM start another VM, which takes up
+/// about twice as much, so it counts as a second call against the recursion limit.
+const CALL_STACK_SIZE: usize = 32 * 1024;
+/// Largest recursion limit the interpreter thread has stack for.
+const MAX_RECURSION_LIMIT: usize = STACK_SIZE / CALL_STACK_SIZE;
 
 #[derive(ClapParser, Debug)]
 #[command(
@@ -32,21 +38,57 @@ struct Args {
     #[arg(long, conflicts_with_all = ["dump_ast", "verbose"])]
     dump_tokens: bool,
 
-    /// Dump AST and exit
+    /// Dump AST and exit, optimized with -O
     #[arg(long, conflicts_with_all = ["dump_tokens", "verbose"])]
     dump_ast: bool,
 
     /// Dump tokens and AST, then execute
     #[arg(long)]
     verbose: bool,
+
+    /// Extra directory to search for `use` scripts (can be repeated)
+    #[arg(long = "lib-dir", value_name = "DIR")]
+    lib_dirs: Vec<PathBuf>,
+
+    /// Maximum depth of nested function calls (at most 32768)
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_RECURSION_LIMIT, value_parser = recursion_limit)]
+    recursion_limit: usize,
+
+    /// Compile the program to bytecode and run it on the VM instead of the tree-walker
+    #[arg(long)]
+    vm: bool,
+
+    /// Fold constant expressions and drop dead code before running
+    #[arg(short = 'O')]
+    optimize: bool,
 }
 
 fn main() {
     let args = Args::parse();
 
+    // run on a thread with a larger stack so deep recursion hits the recursion limit first
+    let interpreter = std::thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(|| run(args))
+        .expect("failed to spawn the interpreter thread");
+    if interpreter.join().is_err() {
+        std::process::exit(1);
+    }
+}
+
+/// Parses a recursion limit, rejecting limits the interpreter thread has no stack for.
+fn recursion_limit(arg: &str) -> Result<usize, String> {
+    let limit: usize = arg.parse().map_err(|e| format!("{e}"))?;
+    if limit > MAX_RECURSION_LIMIT {
+        return Err(format!("must be at most {MAX_RECURSION_LIMIT}"));
+    }
+    Ok(limit)
+}
+
+fn run(args: Args) {
     // Run REPL if no file provided
     if args.file.is_none() {
-        let mut repl = Repl::new();
+        let mut repl = Repl::new(args.lib_dirs, args.recursion_limit);
         repl.run();
         return;
     }
@@ -102,7 +144,8 @@ fn main() {
         }
     };
 
-    if args.dump_ast || args.verbose {
+    // the optimized AST is dumped once it is resolved
+    if (args.dump_ast || args.verbose) && !args.optimize {
         println!("== AST ==");
         dbg!(&src.ast);
         if args.dump_ast {
@@ -110,7 +153,7 @@ fn main() {
         }
     }
 
-    // 4) Resolve & Execute
+    // 4) Resolve, Optimize & Execute
     let mut resolver = Resolver::new(&src);
     let resolver_out = resolver.resolve();
     src.ast = match resolver_out.ast {
@@ -137,8 +180,29 @@ fn main() {
         }
     };
 
-    let mut evaluator = Evaluator::new(&src);
-    if evaluator.eval().is_err() {
+    if args.optimize {
+        src.ast = src.ast.map(Optimizer::optimize);
+
+        if args.dump_ast || args.verbose {
+            println!("== AST ==");
+            dbg!(&src.ast);
+            if args.dump_ast {
+                return;
+            }
+        }
+    }
+
+    let mut loader = Loader::new(args.lib_dirs);
+    loader.vm = args.vm;
+    loader.optimize = args.optimize;
+    let mut evaluator = Evaluator::with_loader(&src, Rc::new(RefCell::new(loader)));
+    evaluator.recursion_limit = args.recursion_limit;
+    let res = if args.vm {
+        Vm::eval(&mut evaluator)
+    } else {
+        evaluator.eval()
+    };
+    if res.is_err() {
         std::process::exit(1);
     }
 }
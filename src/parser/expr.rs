@@ -1,19 +1,25 @@
 use ordered_float::OrderedFloat;
-use std::cell::RefCell;
+use std::{cell::Cell, rc::Rc};
 
-use crate::lexer::{
-    cursor::Cursor,
-    token::{KeywordKind, TokenKind},
+use crate::{
+    evaluator::{cache::PropCache, int::Int, range::Range},
+    lexer::{
+        cursor::Cursor,
+        token::{KeywordKind, TokenKind},
+    },
+    parser::stmt::FnDecl,
 };
 
 #[derive(Debug, Clone)]
 pub enum ExprKind {
     Literal(LiteralType),
+    /// Interpolated string, the display strings of the parts are concatenated
+    Interp(Vec<Expr>),
     List(Vec<Expr>),
     Dict(Vec<(Expr, Expr)>),
     Range {
         start: Box<Expr>,
-        end: Box<Expr>,
+        end: Option<Box<Expr>>,
         inclusive: bool,
         step: Option<Box<Expr>>,
     },
@@ -35,7 +41,9 @@ pub enum ExprKind {
     Call {
         callee: Box<Expr>,
         args: Vec<Expr>,
+        named: Vec<NamedArg>,
     },
+    Lambda(Rc<FnDecl>),
     Grouping {
         expr: Box<Expr>,
     },
@@ -52,6 +60,8 @@ pub enum ExprKind {
     Get {
         obj: Box<Expr>,
         name: String,
+        /// Inline cache of the method lookup
+        cache: PropCache,
     },
     Set {
         obj: Box<Expr>,
@@ -70,6 +80,9 @@ pub enum ExprKind {
         val: Box<Expr>,
     },
     ESelf,
+    Super {
+        method: String,
+    },
 }
 
 #[derive(Debug, Clone)]
@@ -78,8 +91,10 @@ pub struct Expr {
     pub kind: ExprKind,
     /// Location of the expression as a Cursor
     pub cursor: Cursor,
-    /// Resolved distance
-    pub resolved_dist: RefCell<Option<usize>>,
+    /// Resolved scope distance and slot
+    pub resolved: Cell<Option<(usize, usize)>>,
+    /// Whether the variable is declared after this use, the use sees globals until it is
+    pub late: Cell<bool>,
 }
 
 impl Expr {
@@ -87,16 +102,27 @@ impl Expr {
         Self {
             kind,
             cursor,
-            resolved_dist: RefCell::new(None),
+            resolved: Cell::new(None),
+            late: Cell::new(false),
         }
     }
 
-    pub fn resolve(&mut self, dist: usize) {
-        *self.resolved_dist.borrow_mut() = Some(dist);
+    pub fn resolve(&self, dist: usize, slot: usize) {
+        self.resolved.set(Some((dist, slot)));
+    }
+
+    pub fn resolve_late(&self, dist: usize, slot: usize) {
+        self.resolve(dist, slot);
+        self.late.set(true);
+    }
+
+    pub fn get_resolved(&self) -> Option<(usize, usize)> {
+        self.resolved.get()
     }
 
-    pub fn get_resolved_dist(&self) -> Option<usize> {
-        *self.resolved_dist.borrow()
+    /// Whether the variable wasn't declared yet where it's used, or isn't a local at all.
+    pub fn is_late(&self) -> bool {
+        self.late.get() || self.resolved.get().is_none()
     }
 }
 
@@ -114,9 +140,12 @@ pub enum OpFromTokenError {
 #[derive(Debug, Clone)]
 pub enum LiteralType {
     Null,
+    Int(Int),
     Num(OrderedFloat<f64>),
     Str(String),
     Bool(bool),
+    /// Range with literal bounds, built by the optimizer
+    Range(Range),
 }
 
 #[derive(Debug, Clone)]
@@ -147,6 +176,7 @@ impl TryFrom<&TokenKind> for AssignOp {
 pub enum UnaryOp {
     Negate,
     Not,
+    BitNot,
 }
 
 impl TryFrom<&TokenKind> for UnaryOp {
@@ -156,6 +186,7 @@ impl TryFrom<&TokenKind> for UnaryOp {
         match t {
             TokenKind::Sub => Ok(UnaryOp::Negate), // e.g., prefix minus
             TokenKind::Not => Ok(UnaryOp::Not),
+            TokenKind::BitNot => Ok(UnaryOp::BitNot),
             _ => Err(OpFromTokenError::NotUnary("expected unary operator token")),
         }
     }
@@ -198,8 +229,15 @@ pub enum BinaryOp {
     Sub,
     Mult,
     Div,
+    FloorDiv,
     Mod,
     Pow,
+    // Bitwise
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
     // Boolean
     Equals,
     NotEquals,
@@ -220,8 +258,15 @@ impl TryFrom<&TokenKind> for BinaryOp {
             TokenKind::Sub => BinaryOp::Sub,
             TokenKind::Mult => BinaryOp::Mult,
             TokenKind::Div => BinaryOp::Div,
+            TokenKind::FloorDiv => BinaryOp::FloorDiv,
             TokenKind::Mod => BinaryOp::Mod,
             TokenKind::Pow => BinaryOp::Pow,
+            // Bitwise
+            TokenKind::BitAnd => BinaryOp::BitAnd,
+            TokenKind::Pipe => BinaryOp::BitOr,
+            TokenKind::BitXor => BinaryOp::BitXor,
+            TokenKind::ShiftLeft => BinaryOp::ShiftLeft,
+            TokenKind::ShiftRight => BinaryOp::ShiftRight,
             // Equality / comparison
             TokenKind::Equals => BinaryOp::Equals,
             TokenKind::NotEquals => BinaryOp::NotEquals,
@@ -239,3 +284,11 @@ impl TryFrom<&TokenKind> for BinaryOp {
         Ok(op)
     }
 }
+
+/// A `name: value` argument of a call
+#[derive(Debug, Clone)]
+pub struct NamedArg {
+    pub name: String,
+    pub val: Expr,
+    pub cursor: Cursor,
+}
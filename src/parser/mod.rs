@@ -1,16 +1,24 @@
 pub mod expr;
 pub mod parse_err;
+pub mod pattern;
 pub mod stmt;
 
+use std::rc::Rc;
+
 use ordered_float::OrderedFloat;
 use strum::IntoDiscriminant;
 
 use crate::{
-    lexer::token::{KeywordKind, Token, TokenKind, TokenKindDiscriminants},
+    evaluator::{cache::PropCache, int::Int},
+    lexer::{
+        cursor::Cursor,
+        token::{KeywordKind, StrPart, Token, TokenKind, TokenKindDiscriminants},
+    },
     parser::{
-        expr::{AssignOp, BinaryOp, Expr, ExprKind, LiteralType, LogicalOp, UnaryOp},
+        expr::{AssignOp, BinaryOp, Expr, ExprKind, LiteralType, LogicalOp, NamedArg, UnaryOp},
         parse_err::{ParseErr, ParseResult},
-        stmt::{Stmt, StmtKind},
+        pattern::{MatchArm, Pattern, PatternKind},
+        stmt::{CatchClause, FnDecl, Param, Stmt, StmtKind},
     },
     reporter::Reporter,
     src::Src,
@@ -55,6 +63,12 @@ pub struct Parser<'a> {
     curr: usize,
     /// Parser output
     out: ParserOutput,
+    /// Name of the function whose body is parsed and whether it is a bound method, returned
+    /// calls of it are tail calls outside of try statements
+    tail_fn: Option<(String, bool)>,
+    /// Whether a `|` ends the expression instead of being a bitwise or, it separates match
+    /// pattern alternatives and closes short lambda parameters
+    pipe_ends_expr: bool,
 }
 
 impl<'a> Parser<'a> {
@@ -64,6 +78,20 @@ impl<'a> Parser<'a> {
             tokens: src.tokens.as_ref().expect("ecpected tokens").clone(),
             curr: 0,
             out: ParserOutput::default(),
+            tail_fn: None,
+            pipe_ends_expr: false,
+        }
+    }
+
+    /// Parser over a token subset of the source, used for interpolated string expressions.
+    pub fn with_tokens(src: &'a Src, tokens: Vec<Token>) -> Self {
+        Self {
+            src,
+            tokens,
+            curr: 0,
+            out: ParserOutput::default(),
+            tail_fn: None,
+            pipe_ends_expr: false,
         }
     }
 
@@ -97,7 +125,8 @@ impl<'a> Parser<'a> {
         if self.match_keyword(KeywordKind::Var) {
             return self.var_declr(true);
         }
-        if self.match_keyword(KeywordKind::Fn) {
+        if self.check_keyword(KeywordKind::Fn) && self.peek().kind != TokenKind::LParen {
+            self.next();
             return self.fn_declr();
         }
         if self.match_keyword(KeywordKind::Obj) {
@@ -152,10 +181,38 @@ impl<'a> Parser<'a> {
             "expected '(' after function name",
         )?;
 
+        let (params, bound) = self.fn_params(TokenKindDiscriminants::RParen)?;
+
+        self.consume(
+            TokenKindDiscriminants::RParen,
+            "expected ')' after function parameters",
+        )?;
+
+        self.consume_keyword(KeywordKind::Do, "expected 'do' before function body")?;
+        let body = self.fn_body(Some((name.clone(), bound)))?;
+        Ok(Stmt::new(
+            StmtKind::Fn(Rc::new(FnDecl {
+                name,
+                params,
+                body,
+                bound,
+            })),
+            name_token.cursor,
+        ))
+    }
+
+    fn fn_body(&mut self, tail_fn: Option<(String, bool)>) -> ParseResult<Stmt> {
+        let tail_fn = std::mem::replace(&mut self.tail_fn, tail_fn);
+        let body = self.block_stmt();
+        self.tail_fn = tail_fn;
+        body
+    }
+
+    fn fn_params(&mut self, end: TokenKindDiscriminants) -> ParseResult<(Vec<Param>, bool)> {
         let mut bound = false;
 
-        let mut params: Vec<String> = vec![];
-        if !self.check(TokenKindDiscriminants::RParen) {
+        let mut params: Vec<Param> = vec![];
+        if !self.check(end) {
             loop {
                 if params.len() >= 255 {
                     self.out.add_err(ParseErr::new(
@@ -164,20 +221,27 @@ impl<'a> Parser<'a> {
                     ));
                 }
 
-                if let TokenKind::Keyword(keyword) = self.current().kind {
-                    if let KeywordKind::KSelf = keyword {
-                        bound = true;
-                        self.next();
-                    }
+                if let TokenKind::Keyword(KeywordKind::KSelf) = self.current().kind {
+                    bound = true;
+                    self.next();
                 } else {
-                    let ident = self.consume(
-                        TokenKindDiscriminants::Identifier,
-                        "expected parameter name",
-                    )?;
-
-                    if let TokenKind::Identifier(name) = ident.kind {
-                        params.push(name);
+                    let param = self.fn_param()?;
+                    if let Some(last) = params.last() {
+                        if last.variadic {
+                            return Err(ParseErr::new(
+                                "rest parameter must be the last parameter".into(),
+                                last.cursor,
+                            ));
+                        }
+                        if last.default.is_some() && param.default.is_none() && !param.variadic {
+                            return Err(ParseErr::new(
+                                "parameters without a default value can't follow parameters with one"
+                                    .into(),
+                                param.cursor,
+                            ));
+                        }
                     }
+                    params.push(param);
                 }
 
                 if !self.match_tokens(vec![TokenKindDiscriminants::Comma]) {
@@ -186,22 +250,38 @@ impl<'a> Parser<'a> {
             }
         }
 
-        self.consume(
-            TokenKindDiscriminants::RParen,
-            "expected ')' after function parameters",
+        Ok((params, bound))
+    }
+
+    fn fn_param(&mut self) -> ParseResult<Param> {
+        let variadic = self.match_tokens(vec![TokenKindDiscriminants::Ellipsis]);
+        let ident = self.consume(
+            TokenKindDiscriminants::Identifier,
+            "expected parameter name",
         )?;
+        let name = if let TokenKind::Identifier(name) = ident.kind {
+            name
+        } else {
+            unreachable!()
+        };
 
-        self.consume_keyword(KeywordKind::Do, "expected 'do' before function body")?;
-        let body = self.block_stmt()?;
-        Ok(Stmt::new(
-            StmtKind::Fn {
-                name,
-                params,
-                body: Box::new(body),
-                bound,
-            },
-            name_token.cursor,
-        ))
+        let mut default = None;
+        if self.match_tokens(vec![TokenKindDiscriminants::Assign]) {
+            if variadic {
+                return Err(ParseErr::new(
+                    "rest parameters can't have a default value".into(),
+                    self.previous().cursor,
+                ));
+            }
+            default = Some(self.expr()?);
+        }
+
+        Ok(Param {
+            name,
+            default,
+            variadic,
+            cursor: ident.cursor,
+        })
     }
 
     fn obj_declr(&mut self) -> ParseResult<Stmt> {
@@ -212,6 +292,17 @@ impl<'a> Parser<'a> {
             name = ident;
         }
 
+        let mut parent: Option<Expr> = None;
+        if self.match_tokens(vec![TokenKindDiscriminants::Lesser]) {
+            let parent_token = self.consume(
+                TokenKindDiscriminants::Identifier,
+                "expected parent object name after '<'",
+            )?;
+            if let TokenKind::Identifier(parent_name) = parent_token.kind {
+                parent = Some(Expr::new(ExprKind::Var(parent_name), parent_token.cursor));
+            }
+        }
+
         self.consume_keyword(KeywordKind::Do, "expected 'do' before object body")?;
         self.skip_eols();
 
@@ -224,7 +315,11 @@ impl<'a> Parser<'a> {
         self.consume_keyword(KeywordKind::End, "expected 'end' after object body")?;
 
         Ok(Stmt::new(
-            StmtKind::Obj { name, methods },
+            StmtKind::Obj {
+                name,
+                parent,
+                methods,
+            },
             name_token.cursor,
         ))
     }
@@ -299,15 +394,26 @@ impl<'a> Parser<'a> {
         let val = self.expr()?;
         self.consume_keyword(KeywordKind::Do, "expected do after match value")?;
 
-        let mut arms: Vec<(Expr, Stmt)> = Vec::new();
+        let mut arms: Vec<MatchArm> = Vec::new();
         self.skip_eols();
         while !self.check_keyword(KeywordKind::Else)
             && !self.check_keyword(KeywordKind::End)
             && !self.is_at_end()
         {
-            let match_val = self.expr()?;
-            let match_do = self.stmt()?;
-            arms.push((match_val, match_do));
+            let pattern = self.pattern(false)?;
+
+            let mut guard: Option<Expr> = None;
+            if self.match_keyword(KeywordKind::If) {
+                guard = Some(self.expr()?);
+            }
+
+            let body = self.stmt()?;
+            arms.push(MatchArm {
+                binds: pattern.bindings().len(),
+                pattern,
+                guard,
+                body,
+            });
             self.skip_eols();
         }
 
@@ -395,17 +501,60 @@ impl<'a> Parser<'a> {
     }
 
     fn try_stmt(&mut self) -> ParseResult<Stmt> {
+        // calls returned inside a try statement still have to run in it
+        let tail_fn = self.tail_fn.take();
+        let stmt = self.try_parts();
+        self.tail_fn = tail_fn;
+        stmt
+    }
+
+    fn try_parts(&mut self) -> ParseResult<Stmt> {
         let body = Box::new(self.stmt()?);
         self.consume_keyword(
             KeywordKind::Catch,
             "expected 'catch' arm after 'try' block".into(),
         )?;
 
-        let mut err_kind: Option<String> = None;
-        let mut err_val: Option<String> = None;
+        let mut catches = vec![self.catch_clause()?];
+        while self.match_keyword(KeywordKind::Catch) {
+            catches.push(self.catch_clause()?);
+        }
+
+        let mut ensure: Option<Box<Stmt>> = None;
+        if self.match_keyword(KeywordKind::Ensure) {
+            ensure = Some(Box::new(self.stmt()?));
+        }
+
+        Ok(Stmt::new(
+            StmtKind::Try {
+                body,
+                catches,
+                ensure,
+            },
+            self.previous().cursor,
+        ))
+    }
+
+    fn catch_clause(&mut self) -> ParseResult<CatchClause> {
+        let cursor = self.previous().cursor;
+
+        // capitalized names filter the caught errors by kind or object name
+        let mut kinds: Vec<String> = vec![];
+        while let TokenKind::Identifier(name) = self.current().kind
+            && name.starts_with(|c: char| c.is_uppercase())
+        {
+            self.next();
+            kinds.push(name);
+            if !self.match_tokens(vec![TokenKindDiscriminants::Pipe]) {
+                break;
+            }
+        }
+
+        let mut err: Option<String> = None;
+        let mut val: Option<String> = None;
         if self.match_tokens(vec![TokenKindDiscriminants::Identifier]) {
-            let kind_ident = self.previous();
-            err_kind = if let TokenKind::Identifier(name) = kind_ident.kind {
+            let err_ident = self.previous();
+            err = if let TokenKind::Identifier(name) = err_ident.kind {
                 Some(name)
             } else {
                 unreachable!()
@@ -416,7 +565,7 @@ impl<'a> Parser<'a> {
                     TokenKindDiscriminants::Identifier,
                     "expected error value identifier after ','",
                 )?;
-                err_val = if let TokenKind::Identifier(name) = val_ident.kind {
+                val = if let TokenKind::Identifier(name) = val_ident.kind {
                     Some(name)
                 } else {
                     unreachable!()
@@ -424,23 +573,15 @@ impl<'a> Parser<'a> {
             }
         }
 
-        let catch = Box::new(self.stmt()?);
-
-        let mut ensure: Option<Box<Stmt>> = None;
-        if self.match_keyword(KeywordKind::Ensure) {
-            ensure = Some(Box::new(self.stmt()?));
-        }
+        let body = Box::new(self.stmt()?);
 
-        Ok(Stmt::new(
-            StmtKind::Try {
-                body,
-                err_kind,
-                err_val,
-                catch,
-                ensure,
-            },
-            self.previous().cursor,
-        ))
+        Ok(CatchClause {
+            kinds,
+            err,
+            val,
+            body,
+            cursor,
+        })
     }
 
     fn throw_stmt(&mut self) -> ParseResult<Stmt> {
@@ -455,12 +596,45 @@ impl<'a> Parser<'a> {
 
     fn use_stmt(&mut self) -> ParseResult<Stmt> {
         let cursor = self.current().cursor.clone();
-        let val = self.expr()?;
+        let path = self.expr()?;
+
+        let mut alias: Option<String> = None;
+        let mut names: Option<Vec<String>> = None;
+        if self.match_keyword(KeywordKind::As) {
+            let ident = self.consume(
+                TokenKindDiscriminants::Identifier,
+                "expected module name after 'as'",
+            )?;
+            if let TokenKind::Identifier(name) = ident.kind {
+                alias = Some(name);
+            }
+        } else if self.match_tokens(vec![TokenKindDiscriminants::LBrace]) {
+            let mut idents = vec![];
+            self.skip_eols();
+            loop {
+                let ident =
+                    self.consume(TokenKindDiscriminants::Identifier, "expected imported name")?;
+                if let TokenKind::Identifier(name) = ident.kind {
+                    idents.push(name);
+                }
+                self.skip_eols();
+                if !self.match_tokens(vec![TokenKindDiscriminants::Comma]) {
+                    break;
+                }
+                self.skip_eols();
+            }
+            self.consume(
+                TokenKindDiscriminants::RBrace,
+                "expected '}' after imported names",
+            )?;
+            names = Some(idents);
+        }
+
         self.consume(
             TokenKindDiscriminants::EOL,
             "expected '\\n' after script path",
         )?;
-        Ok(Stmt::new(StmtKind::Use(val), cursor))
+        Ok(Stmt::new(StmtKind::Use { path, alias, names }, cursor))
     }
 
     fn return_stmt(&mut self) -> ParseResult<Stmt> {
@@ -474,7 +648,27 @@ impl<'a> Parser<'a> {
             TokenKindDiscriminants::EOL,
             "expected '\\n' after return value",
         )?;
-        Ok(Stmt::new(StmtKind::Return(val), self.previous().cursor))
+        let tail = val.as_ref().is_some_and(|val| self.is_self_call(val));
+        Ok(Stmt::new(
+            StmtKind::Return { val, tail },
+            self.previous().cursor,
+        ))
+    }
+
+    /// Whether an expression calls the function being parsed, by its name or through `self` in
+    /// bound methods. Only these calls are tail calls so tracebacks keep the other frames.
+    fn is_self_call(&self, expr: &Expr) -> bool {
+        let (Some((name, bound)), ExprKind::Call { callee, .. }) = (&self.tail_fn, &expr.kind)
+        else {
+            return false;
+        };
+        match &callee.kind {
+            ExprKind::Var(callee) => !bound && callee == name,
+            ExprKind::Get {
+                obj, name: callee, ..
+            } => *bound && matches!(obj.kind, ExprKind::ESelf) && callee == name,
+            _ => false,
+        }
     }
 
     fn break_stmt(&mut self) -> ParseResult<Stmt> {
@@ -514,12 +708,158 @@ impl<'a> Parser<'a> {
         ))
     }
 
+    // Patterns
+
+    /// Parses a match pattern with optional `|` alternatives. Bare identifiers only bind values
+    /// when `nested` inside a list or dict pattern, at the top level they're compared by value.
+    fn pattern(&mut self, nested: bool) -> ParseResult<Pattern> {
+        let cursor = self.current().cursor;
+        let first = self.single_pattern(nested)?;
+        if !self.check(TokenKindDiscriminants::Pipe) {
+            return Ok(first);
+        }
+
+        let mut alts = vec![first];
+        while self.match_tokens(vec![TokenKindDiscriminants::Pipe]) {
+            alts.push(self.single_pattern(nested)?);
+        }
+        Ok(Pattern::new(PatternKind::Or(alts), cursor))
+    }
+
+    fn single_pattern(&mut self, nested: bool) -> ParseResult<Pattern> {
+        let cursor = self.current().cursor;
+
+        if let TokenKind::Identifier(name) = self.current().kind {
+            if name == "_" {
+                self.next();
+                return Ok(Pattern::new(PatternKind::Wildcard, cursor));
+            }
+
+            // type patterns: `Num n`, `Str _`
+            let type_name = name.starts_with(|c: char| c.is_uppercase());
+            if type_name && let TokenKind::Identifier(bind) = self.peek().kind {
+                self.next();
+                self.next();
+                let bind = if bind == "_" { None } else { Some(bind) };
+                return Ok(Pattern::new(PatternKind::Type { name, bind }, cursor));
+            }
+
+            if nested
+                && [
+                    TokenKindDiscriminants::Comma,
+                    TokenKindDiscriminants::RBracket,
+                    TokenKindDiscriminants::RBrace,
+                    TokenKindDiscriminants::Pipe,
+                ]
+                .contains(&self.peek().kind.discriminant())
+            {
+                self.next();
+                return Ok(Pattern::new(PatternKind::Bind(name), cursor));
+            }
+        }
+
+        if self.match_tokens(vec![TokenKindDiscriminants::LBracket]) {
+            return self.list_pattern(cursor);
+        }
+
+        if self.match_tokens(vec![TokenKindDiscriminants::LBrace]) {
+            return self.dict_pattern(cursor);
+        }
+
+        Ok(Pattern::new(
+            PatternKind::Value(self.expr_before_pipe()?),
+            cursor,
+        ))
+    }
+
+    fn list_pattern(&mut self, cursor: Cursor) -> ParseResult<Pattern> {
+        let mut items: Vec<Pattern> = vec![];
+        let mut rest: Option<Box<Pattern>> = None;
+
+        self.skip_eols();
+        while !self.check(TokenKindDiscriminants::RBracket) && !self.is_at_end() {
+            if self.match_tokens(vec![TokenKindDiscriminants::Ellipsis]) {
+                let rest_cursor = self.previous().cursor;
+                let kind = match self.current().kind {
+                    TokenKind::Identifier(name) if name != "_" => {
+                        self.next();
+                        PatternKind::Bind(name)
+                    }
+                    TokenKind::Identifier(_) => {
+                        self.next();
+                        PatternKind::Wildcard
+                    }
+                    _ => PatternKind::Wildcard,
+                };
+                rest = Some(Box::new(Pattern::new(kind, rest_cursor)));
+                self.match_tokens(vec![TokenKindDiscriminants::Comma]);
+                self.skip_eols();
+                break;
+            }
+
+            items.push(self.pattern(true)?);
+            self.skip_eols();
+            if !self.match_tokens(vec![TokenKindDiscriminants::Comma]) {
+                break;
+            }
+            self.skip_eols();
+        }
+
+        self.consume(
+            TokenKindDiscriminants::RBracket,
+            "expected ']' after list pattern",
+        )?;
+        Ok(Pattern::new(PatternKind::List { items, rest }, cursor))
+    }
+
+    fn dict_pattern(&mut self, cursor: Cursor) -> ParseResult<Pattern> {
+        let mut entries: Vec<(Expr, Pattern)> = vec![];
+
+        self.skip_eols();
+        while !self.check(TokenKindDiscriminants::RBrace) && !self.is_at_end() {
+            let key = self.expr()?;
+            self.consume(
+                TokenKindDiscriminants::Colon,
+                "expected ':' after dict pattern key",
+            )?;
+            self.skip_eols();
+            entries.push((key, self.pattern(true)?));
+            self.skip_eols();
+            if !self.match_tokens(vec![TokenKindDiscriminants::Comma]) {
+                break;
+            }
+            self.skip_eols();
+        }
+
+        self.consume(
+            TokenKindDiscriminants::RBrace,
+            "expected '}' after dict pattern",
+        )?;
+        Ok(Pattern::new(PatternKind::Dict(entries), cursor))
+    }
+
     // Expressions
 
     fn expr(&mut self) -> ParseResult<Expr> {
         self.assignment()
     }
 
+    /// Parses an expression ending before the first `|` outside of brackets.
+    fn expr_before_pipe(&mut self) -> ParseResult<Expr> {
+        let pipe_ends_expr = std::mem::replace(&mut self.pipe_ends_expr, true);
+        let expr = self.expr();
+        self.pipe_ends_expr = pipe_ends_expr;
+        expr
+    }
+
+    /// Parses an expression inside brackets, where `|` is a bitwise or again.
+    fn bracketed_expr(&mut self) -> ParseResult<Expr> {
+        let pipe_ends_expr = std::mem::replace(&mut self.pipe_ends_expr, false);
+        let expr = self.expr();
+        self.pipe_ends_expr = pipe_ends_expr;
+        expr
+    }
+
     fn assignment(&mut self) -> ParseResult<Expr> {
         let expr = self.ternary()?;
 
@@ -532,7 +872,7 @@ impl<'a> Parser<'a> {
         ]) {
             let op = AssignOp::try_from(&self.previous().kind).unwrap();
             let mut val = Expr::new(
-                ExprKind::Literal(LiteralType::Num(OrderedFloat(1.0))),
+                ExprKind::Literal(LiteralType::Int(Int::Small(1))),
                 self.current().cursor,
             );
             if self.previous().kind != TokenKind::Incr && self.previous().kind != TokenKind::Decr {
@@ -550,7 +890,7 @@ impl<'a> Parser<'a> {
                 ));
             }
 
-            if let ExprKind::Get { obj, name } = expr.kind {
+            if let ExprKind::Get { obj, name, .. } = expr.kind {
                 return Ok(Expr::new(
                     ExprKind::Set {
                         obj,
@@ -658,13 +998,84 @@ impl<'a> Parser<'a> {
     }
 
     fn comparison(&mut self) -> ParseResult<Expr> {
-        let mut expr = self.term()?;
+        let mut expr = self.bit_or()?;
 
         while self.match_tokens(vec![
             TokenKindDiscriminants::Greater,
             TokenKindDiscriminants::GreaterEquals,
             TokenKindDiscriminants::Lesser,
             TokenKindDiscriminants::LesserEquals,
+        ]) {
+            let op = BinaryOp::try_from(&self.previous().kind).unwrap();
+            let right = self.bit_or()?;
+            expr.kind = ExprKind::Binary {
+                left: Box::new(expr.clone()),
+                op,
+                right: Box::new(right),
+            };
+            expr.cursor = self.previous().cursor;
+        }
+
+        Ok(expr)
+    }
+
+    fn bit_or(&mut self) -> ParseResult<Expr> {
+        let mut expr = self.bit_xor()?;
+
+        while !self.pipe_ends_expr && self.match_tokens(vec![TokenKindDiscriminants::Pipe]) {
+            let op = BinaryOp::try_from(&self.previous().kind).unwrap();
+            let right = self.bit_xor()?;
+            expr.kind = ExprKind::Binary {
+                left: Box::new(expr.clone()),
+                op,
+                right: Box::new(right),
+            };
+            expr.cursor = self.previous().cursor;
+        }
+
+        Ok(expr)
+    }
+
+    fn bit_xor(&mut self) -> ParseResult<Expr> {
+        let mut expr = self.bit_and()?;
+
+        while self.match_tokens(vec![TokenKindDiscriminants::BitXor]) {
+            let op = BinaryOp::try_from(&self.previous().kind).unwrap();
+            let right = self.bit_and()?;
+            expr.kind = ExprKind::Binary {
+                left: Box::new(expr.clone()),
+                op,
+                right: Box::new(right),
+            };
+            expr.cursor = self.previous().cursor;
+        }
+
+        Ok(expr)
+    }
+
+    fn bit_and(&mut self) -> ParseResult<Expr> {
+        let mut expr = self.shift()?;
+
+        while self.match_tokens(vec![TokenKindDiscriminants::BitAnd]) {
+            let op = BinaryOp::try_from(&self.previous().kind).unwrap();
+            let right = self.shift()?;
+            expr.kind = ExprKind::Binary {
+                left: Box::new(expr.clone()),
+                op,
+                right: Box::new(right),
+            };
+            expr.cursor = self.previous().cursor;
+        }
+
+        Ok(expr)
+    }
+
+    fn shift(&mut self) -> ParseResult<Expr> {
+        let mut expr = self.term()?;
+
+        while self.match_tokens(vec![
+            TokenKindDiscriminants::ShiftLeft,
+            TokenKindDiscriminants::ShiftRight,
         ]) {
             let op = BinaryOp::try_from(&self.previous().kind).unwrap();
             let right = self.term()?;
@@ -704,6 +1115,7 @@ impl<'a> Parser<'a> {
 
         while self.match_tokens(vec![
             TokenKindDiscriminants::Div,
+            TokenKindDiscriminants::FloorDiv,
             TokenKindDiscriminants::Mult,
             TokenKindDiscriminants::Mod,
             TokenKindDiscriminants::Pow,
@@ -726,6 +1138,7 @@ impl<'a> Parser<'a> {
         while self.match_tokens(vec![
             TokenKindDiscriminants::Not,
             TokenKindDiscriminants::Sub,
+            TokenKindDiscriminants::BitNot,
         ]) {
             let op = UnaryOp::try_from(&self.previous().kind).unwrap();
             let right = self.unary()?;
@@ -748,7 +1161,7 @@ impl<'a> Parser<'a> {
             if self.match_tokens(vec![TokenKindDiscriminants::LParen]) {
                 expr = self.finish_call(expr)?;
             } else if self.match_tokens(vec![TokenKindDiscriminants::LBracket]) {
-                let index_expr = self.expr()?;
+                let index_expr = self.bracketed_expr()?;
                 self.consume(TokenKindDiscriminants::RBracket, "expected ']' after index")?;
 
                 expr = Expr::new(
@@ -768,6 +1181,7 @@ impl<'a> Parser<'a> {
                         ExprKind::Get {
                             obj: Box::new(expr),
                             name,
+                            cache: PropCache::default(),
                         },
                         self.current().cursor,
                     );
@@ -782,17 +1196,33 @@ impl<'a> Parser<'a> {
 
     fn finish_call(&mut self, callee: Expr) -> ParseResult<Expr> {
         let mut args: Vec<Expr> = vec![];
+        let mut named: Vec<NamedArg> = vec![];
 
         if !self.check(TokenKindDiscriminants::RParen) {
             loop {
-                if args.len() >= 255 {
+                if args.len() + named.len() >= 255 {
                     self.out.add_err(ParseErr::new(
                         "functions cannot have more than 255 arguments".into(),
                         callee.cursor,
                     ));
                 }
 
-                args.push(self.expr()?);
+                // `name: value` passes the argument by parameter name
+                if let TokenKind::Identifier(name) = self.current().kind
+                    && self.peek().kind == TokenKind::Colon
+                {
+                    let cursor = self.next().cursor;
+                    self.next();
+                    let val = self.bracketed_expr()?;
+                    named.push(NamedArg { name, val, cursor });
+                } else if let Some(arg) = named.last() {
+                    return Err(ParseErr::new(
+                        "positional arguments can't follow named arguments".into(),
+                        arg.cursor,
+                    ));
+                } else {
+                    args.push(self.bracketed_expr()?);
+                }
 
                 if !self.match_tokens(vec![TokenKindDiscriminants::Comma]) {
                     break;
@@ -808,6 +1238,7 @@ impl<'a> Parser<'a> {
             ExprKind::Call {
                 callee: Box::new(callee),
                 args,
+                named,
             },
             rparen.cursor,
         ))
@@ -820,8 +1251,35 @@ impl<'a> Parser<'a> {
             TokenKindDiscriminants::Range,
             TokenKindDiscriminants::RangeEq,
         ]) {
+            // errors about the range point at its operator
+            let cursor = self.previous().cursor;
             let inclusive = self.previous().kind == TokenKind::RangeEq;
-            let end = self.expr()?;
+
+            // ranges without an end are open-ended
+            let open = self.is_at_end()
+                || self.check_keyword(KeywordKind::Do)
+                || self.check_keyword(KeywordKind::Step)
+                || [
+                    TokenKindDiscriminants::EOL,
+                    TokenKindDiscriminants::RParen,
+                    TokenKindDiscriminants::RBracket,
+                    TokenKindDiscriminants::RBrace,
+                    TokenKindDiscriminants::Comma,
+                ]
+                .into_iter()
+                .any(|t| self.check(t));
+
+            let end = if open {
+                if inclusive {
+                    return Err(ParseErr::new(
+                        "expected range end after '..='".into(),
+                        self.current().cursor,
+                    ));
+                }
+                None
+            } else {
+                Some(Box::new(self.expr()?))
+            };
 
             let mut step: Option<Box<Expr>> = None;
             if self.match_keyword(KeywordKind::Step) {
@@ -831,11 +1289,11 @@ impl<'a> Parser<'a> {
             return Ok(Expr::new(
                 ExprKind::Range {
                     start: Box::new(start),
-                    end: Box::new(end),
+                    end,
                     inclusive,
                     step,
                 },
-                self.current().cursor,
+                cursor,
             ));
         }
 
@@ -849,7 +1307,7 @@ impl<'a> Parser<'a> {
             if !self.check(TokenKindDiscriminants::RBracket) {
                 loop {
                     self.skip_eols();
-                    elements.push(self.expr()?);
+                    elements.push(self.bracketed_expr()?);
 
                     if !self.match_tokens(vec![TokenKindDiscriminants::Comma]) {
                         break;
@@ -873,16 +1331,17 @@ impl<'a> Parser<'a> {
         if self.match_tokens(vec![TokenKindDiscriminants::LBrace]) {
             let mut map: Vec<(Expr, Expr)> = vec![];
 
-            if !self.check(TokenKindDiscriminants::RBracket) {
+            self.skip_eols();
+            if !self.check(TokenKindDiscriminants::RBrace) {
                 loop {
                     self.skip_eols();
 
-                    let key = self.expr()?;
+                    let key = self.bracketed_expr()?;
                     self.consume(
                         TokenKindDiscriminants::Colon,
                         "expected ':' to seperate dict key and value",
                     )?;
-                    let value = self.expr()?;
+                    let value = self.bracketed_expr()?;
 
                     map.push((key, value));
 
@@ -919,6 +1378,17 @@ impl<'a> Parser<'a> {
                 self.previous().cursor,
             ));
         }
+        if self.match_tokens(vec![TokenKindDiscriminants::Int])
+            && let TokenKind::Int(s) = self.previous().kind
+        {
+            return Ok(Expr::new(
+                ExprKind::Literal(LiteralType::Int(
+                    Int::from_literal(&s)
+                        .map_err(|err| ParseErr::from(err).msg("invalid int literal".into()))?,
+                )),
+                self.previous().cursor,
+            ));
+        }
         if self.match_tokens(vec![TokenKindDiscriminants::Num]) {
             if let TokenKind::Num(s) = self.previous().kind {
                 return Ok(Expr::new(
@@ -938,8 +1408,26 @@ impl<'a> Parser<'a> {
                 ));
             }
         }
+        if self.match_tokens(vec![TokenKindDiscriminants::InterpStr])
+            && let TokenKind::InterpStr(parts) = self.previous().kind
+        {
+            let cursor = self.previous().cursor;
+            let mut exprs = Vec::with_capacity(parts.len());
+            for part in parts {
+                match part {
+                    StrPart::Lit(s) => {
+                        exprs.push(Expr::new(ExprKind::Literal(LiteralType::Str(s)), cursor))
+                    }
+                    StrPart::Expr(tokens) => {
+                        let mut parser = Parser::with_tokens(self.src, tokens);
+                        exprs.push(parser.interpolation()?);
+                    }
+                }
+            }
+            return Ok(Expr::new(ExprKind::Interp(exprs), cursor));
+        }
         if self.match_tokens(vec![TokenKindDiscriminants::LParen]) {
-            let expr = self.expr()?;
+            let expr = self.bracketed_expr()?;
             self.consume(
                 TokenKindDiscriminants::RParen,
                 "expected ')' after expression".into(),
@@ -959,6 +1447,23 @@ impl<'a> Parser<'a> {
         if self.match_keyword(KeywordKind::KSelf) {
             return Ok(Expr::new(ExprKind::ESelf, self.previous().cursor));
         }
+        if self.match_keyword(KeywordKind::Super) {
+            let cursor = self.previous().cursor;
+            self.consume(TokenKindDiscriminants::Dot, "expected '.' after 'super'")?;
+            let ident = self.consume(
+                TokenKindDiscriminants::Identifier,
+                "expected parent method name after 'super.'",
+            )?;
+            if let TokenKind::Identifier(method) = ident.kind {
+                return Ok(Expr::new(ExprKind::Super { method }, cursor));
+            }
+        }
+        if self.match_keyword(KeywordKind::Fn) {
+            return self.lambda();
+        }
+        if self.match_tokens(vec![TokenKindDiscriminants::Pipe]) {
+            return self.short_lambda();
+        }
 
         Err(ParseErr::new(
             "expected expression".into(),
@@ -966,6 +1471,90 @@ impl<'a> Parser<'a> {
         ))
     }
 
+    fn lambda(&mut self) -> ParseResult<Expr> {
+        let cursor = self.previous().cursor;
+        self.consume(TokenKindDiscriminants::LParen, "expected '(' after 'fn'")?;
+        let (params, bound) = self.fn_params(TokenKindDiscriminants::RParen)?;
+        if bound {
+            return Err(ParseErr::new(
+                "lambdas cannot take 'self' as a parameter".into(),
+                self.previous().cursor,
+            ));
+        }
+        self.consume(
+            TokenKindDiscriminants::RParen,
+            "expected ')' after lambda parameters",
+        )?;
+
+        self.consume_keyword(KeywordKind::Do, "expected 'do' before lambda body")?;
+        let body = self.fn_body(None)?;
+        Ok(Expr::new(
+            ExprKind::Lambda(Rc::new(FnDecl {
+                name: "lambda".into(),
+                params,
+                body,
+                bound: false,
+            })),
+            cursor,
+        ))
+    }
+
+    fn short_lambda(&mut self) -> ParseResult<Expr> {
+        let cursor = self.previous().cursor;
+        // a `|` after a default value closes the parameters
+        let pipe_ends_expr = std::mem::replace(&mut self.pipe_ends_expr, true);
+        let params = self.fn_params(TokenKindDiscriminants::Pipe);
+        self.pipe_ends_expr = pipe_ends_expr;
+        let (params, bound) = params?;
+        if bound {
+            return Err(ParseErr::new(
+                "lambdas cannot take 'self' as a parameter".into(),
+                self.previous().cursor,
+            ));
+        }
+        self.consume(
+            TokenKindDiscriminants::Pipe,
+            "expected '|' after lambda parameters",
+        )?;
+
+        // `|x| expr` is sugar for a lambda whose body returns expr
+        let val = self.expr()?;
+        let val_cursor = val.cursor;
+        let body = Stmt::new(
+            StmtKind::Block(vec![Stmt::new(
+                StmtKind::Return {
+                    val: Some(val),
+                    tail: false,
+                },
+                val_cursor,
+            )]),
+            val_cursor,
+        );
+        Ok(Expr::new(
+            ExprKind::Lambda(Rc::new(FnDecl {
+                name: "lambda".into(),
+                params,
+                body,
+                bound: false,
+            })),
+            cursor,
+        ))
+    }
+
+    /// Parses the single expression of an interpolated string part.
+    fn interpolation(&mut self) -> ParseResult<Expr> {
+        self.skip_eols();
+        let expr = self.expr()?;
+        self.skip_eols();
+        if !self.is_at_end() {
+            return Err(ParseErr::new(
+                "expected '}' after interpolated expression".into(),
+                self.current().cursor,
+            ));
+        }
+        Ok(expr)
+    }
+
     // Util functions
 
     fn match_tokens(&mut self, tokens: Vec<TokenKindDiscriminants>) -> bool {
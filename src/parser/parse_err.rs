@@ -4,6 +4,8 @@ use std::{
     num::{ParseFloatError, ParseIntError},
 };
 
+use num_bigint::ParseBigIntError;
+
 use crate::lexer::cursor::Cursor;
 
 pub type ParseResult<T> = std::result::Result<T, ParseErr>;
@@ -88,6 +90,12 @@ impl From<ParseIntError> for ParseErr {
     }
 }
 
+impl From<ParseBigIntError> for ParseErr {
+    fn from(_value: ParseBigIntError) -> Self {
+        Self::new("".into(), Cursor::new())
+    }
+}
+
 impl From<ParseFloatError> for ParseErr {
     fn from(_value: ParseFloatError) -> Self {
         Self::new("".into(), Cursor::new())
@@ -0,0 +1,92 @@
+use std::cell::Cell;
+
+use crate::{
+    lexer::cursor::Cursor,
+    parser::{expr::Expr, stmt::Stmt},
+};
+
+#[derive(Debug, Clone)]
+pub enum PatternKind {
+    /// `_`, matches anything
+    Wildcard,
+    /// Literal, expression or range, compared against the value
+    Value(Expr),
+    /// Bare identifier inside a list or dict pattern, binds the value
+    Bind(String),
+    /// `Num n`, matches by type and optionally binds the value
+    Type { name: String, bind: Option<String> },
+    /// `[a, b, ...rest]`
+    List {
+        items: Vec<Pattern>,
+        rest: Option<Box<Pattern>>,
+    },
+    /// `{"key": pattern}`, matches dicts containing at least the given keys
+    Dict(Vec<(Expr, Pattern)>),
+    /// `a | b`
+    Or(Vec<Pattern>),
+}
+
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    pub kind: PatternKind,
+    pub cursor: Cursor,
+    /// Slot of the name bound by a bind or type pattern in the arm env, set by the resolver
+    pub slot: Cell<usize>,
+}
+
+impl Pattern {
+    pub fn new(kind: PatternKind, cursor: Cursor) -> Self {
+        Self {
+            kind,
+            cursor,
+            slot: Cell::new(0),
+        }
+    }
+
+    /// Names bound by the pattern with their locations, each name is only listed once.
+    pub fn bindings(&self) -> Vec<(String, Cursor)> {
+        let mut names = Vec::new();
+        self.collect_bindings(&mut names);
+        names
+    }
+
+    fn collect_bindings(&self, names: &mut Vec<(String, Cursor)>) {
+        match &self.kind {
+            PatternKind::Bind(name)
+            | PatternKind::Type {
+                bind: Some(name), ..
+            } if !names.iter().any(|(n, _)| n == name) => {
+                names.push((name.clone(), self.cursor));
+            }
+            PatternKind::List { items, rest } => {
+                for item in items {
+                    item.collect_bindings(names);
+                }
+                if let Some(rest) = rest {
+                    rest.collect_bindings(names);
+                }
+            }
+            PatternKind::Dict(entries) => {
+                for (_, pattern) in entries {
+                    pattern.collect_bindings(names);
+                }
+            }
+            PatternKind::Or(alts) => {
+                for alt in alts {
+                    alt.collect_bindings(names);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A single `match` arm: `pattern (if guard)? body`
+#[derive(Debug, Clone)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    /// Number of names the pattern binds
+    pub binds: usize,
+    pub guard: Option<Expr>,
+    pub body: Stmt,
+}
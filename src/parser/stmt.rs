@@ -1,11 +1,24 @@
-use crate::{lexer::cursor::Cursor, parser::expr::Expr};
+use std::rc::Rc;
+
+use crate::{
+    lexer::cursor::Cursor,
+    parser::{expr::Expr, pattern::MatchArm},
+};
 
 #[derive(Debug, Clone)]
 pub enum StmtKind {
     Expr(Expr),
-    Return(Option<Expr>),
+    /// `tail` marks a returned call that can reuse the frame of the returning function
+    Return {
+        val: Option<Expr>,
+        tail: bool,
+    },
     Throw(Expr),
-    Use(Expr),
+    Use {
+        path: Expr,
+        alias: Option<String>,
+        names: Option<Vec<String>>,
+    },
     Break,
     Continue,
     Var {
@@ -20,7 +33,7 @@ pub enum StmtKind {
     },
     Match {
         val: Expr,
-        arms: Vec<(Expr, Stmt)>,
+        arms: Vec<MatchArm>,
         else_branch: Option<Box<Stmt>>,
     },
     While {
@@ -37,19 +50,13 @@ pub enum StmtKind {
     },
     Try {
         body: Box<Stmt>,
-        err_kind: Option<String>,
-        err_val: Option<String>,
-        catch: Box<Stmt>,
+        catches: Vec<CatchClause>,
         ensure: Option<Box<Stmt>>,
     },
-    Fn {
-        name: String,
-        params: Vec<String>,
-        body: Box<Stmt>,
-        bound: bool,
-    },
+    Fn(Rc<FnDecl>),
     Obj {
         name: String,
+        parent: Option<Expr>,
         methods: Vec<Stmt>,
     },
 }
@@ -65,3 +72,36 @@ impl Stmt {
         Self { kind, cursor }
     }
 }
+
+/// A function declaration, functions created from it share it instead of copying the body.
+#[derive(Debug, Clone)]
+pub struct FnDecl {
+    pub name: String,
+    pub params: Vec<Param>,
+    pub body: Stmt,
+    pub bound: bool,
+}
+
+/// A function parameter: `name`, `name = default` or `...name`
+#[derive(Debug, Clone)]
+pub struct Param {
+    pub name: String,
+    /// Evaluated on each call that doesn't pass the argument
+    pub default: Option<Expr>,
+    /// Collects the remaining arguments into a List
+    pub variadic: bool,
+    pub cursor: Cursor,
+}
+
+/// A `catch` clause: `catch (Kind ("|" Kind)*)? (err ("," val)?)? body`
+#[derive(Debug, Clone)]
+pub struct CatchClause {
+    /// Error kinds or object names caught by the clause, empty to catch everything
+    pub kinds: Vec<String>,
+    /// Bound to the structured error (or the thrown instance when caught by its object name)
+    pub err: Option<String>,
+    /// Bound to the thrown value, the message for internal errors
+    pub val: Option<String>,
+    pub body: Box<Stmt>,
+    pub cursor: Cursor,
+}
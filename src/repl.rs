@@ -1,9 +1,9 @@
-use std::{borrow::Cow, cell::RefCell, rc::Rc};
+use std::{borrow::Cow, cell::RefCell, path::PathBuf, rc::Rc};
 
 use crate::{
     evaluator::{
         Evaluator,
-        env::EnvPtr,
+        env::{Env, EnvPtr},
         loader::{Loader, LoaderPtr},
         natives::Natives,
         resolver::Resolver,
@@ -30,20 +30,22 @@ pub struct Repl {
     globals: EnvPtr,
     loader: LoaderPtr,
     src: Src,
+    recursion_limit: usize,
     help: Option<HelpIndex>,
     api_help: Option<HelpIndex>,
 }
 
 impl Repl {
-    pub fn new() -> Self {
-        let globals = Natives::get_natives();
+    pub fn new(lib_dirs: Vec<PathBuf>, recursion_limit: usize) -> Self {
+        let globals = Env::globals(Natives::get_natives());
         let help = HelpIndex::from_str(include_str!("../REFERENCE.md"));
         let api_help = HelpIndex::from_str(include_str!("../API.md"));
 
         Self {
             globals,
-            loader: Rc::new(RefCell::new(Loader::default())),
+            loader: Rc::new(RefCell::new(Loader::new(lib_dirs))),
             src: Src::repl("<repl>"),
+            recursion_limit,
             help,
             api_help,
         }
@@ -92,6 +94,7 @@ impl Repl {
                             self.globals.clone(),
                             self.loader.clone(),
                         );
+                        evaluator.recursion_limit = self.recursion_limit;
                         match evaluator.eval_with_result() {
                             Ok(res) => {
                                 self.globals = evaluator.env;
@@ -24,6 +24,9 @@ impl Display for ReportType {
     }
 }
 
+/// Number of calls printed by `Reporter::traceback`.
+const TRACEBACK_LIMIT: usize = 20;
+
 pub struct Reporter;
 
 impl Reporter {
@@ -87,6 +90,30 @@ impl Reporter {
         println!();
     }
 
+    /// Prints the calls leading to an error, outermost first, as `(function, file, cursor,
+    /// source line)` entries.
+    pub fn traceback(entries: &[(String, String, Cursor, Option<String>)]) {
+        let _ = crossterm::terminal::disable_raw_mode();
+
+        println!("{}", "Traceback (most recent call last):".bold());
+        // deep call stacks only show the calls closest to the error
+        let hidden = entries.len().saturating_sub(TRACEBACK_LIMIT);
+        if hidden > 0 {
+            println!("  ... {} earlier calls", hidden);
+        }
+        for (name, file, cursor, line) in &entries[hidden..] {
+            println!(
+                "  File {}, line {}, in {}",
+                format!("\"{}\"", file).blue(),
+                (cursor.line + 1).to_string().blue(),
+                name.bold()
+            );
+            if let Some(line) = line {
+                println!("    {}", line.trim());
+            }
+        }
+    }
+
     pub fn info_at(msg: &str, src: &Src, cursor: Cursor) {
         Reporter::report_at(ReportType::Info, None, msg, src, cursor, None, None);
     }
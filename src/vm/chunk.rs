@@ -0,0 +1,277 @@
+use std::{path::PathBuf, rc::Rc};
+
+use ordered_float::OrderedFloat;
+
+use crate::{
+    evaluator::{cache::PropCache, int::Int, string::Str},
+    lexer::cursor::Cursor,
+};
+
+/// A single VM instruction. Operands index the pools of the chunk or the slots of the frame,
+/// jump targets are instruction indices.
+#[derive(Debug, Clone, Copy)]
+pub enum Op {
+    Null,
+    True,
+    False,
+    /// Pushes an Int from the constant pool
+    Int(u32),
+    /// Pushes a Num from the constant pool
+    Num(u32),
+    /// Pushes a Str from the string pool, `+=` only grows the shared buffer past the constant
+    Str(u32),
+    Pop,
+    Dup,
+
+    // Variables, stores leave the value on the stack
+    LoadLocal(u16),
+    StoreLocal(u16),
+    /// Pops a value into a new cell, captured locals live in cells shared with closures
+    NewCell(u16),
+    LoadCell(u16),
+    StoreCell(u16),
+    /// Creates an empty cell for a local declared after closures referring to it
+    HoistCell(u16),
+    LoadUpval(u16),
+    StoreUpval(u16),
+    /// Loads the upvalue of a variable declared after the closure, the global of that name is
+    /// used until the declaration ran
+    LoadLateUpval {
+        upval: u16,
+        name: u32,
+    },
+    LoadGlobal(u32),
+    StoreGlobal(u32),
+    /// Pops a value into a new global
+    DefineGlobal(u32),
+
+    // Operators
+    Add,
+    Sub,
+    Mul,
+    Div,
+    FloorDiv,
+    Mod,
+    Pow,
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
+    Equals,
+    NotEquals,
+    Greater,
+    GreaterEquals,
+    Lesser,
+    LesserEquals,
+    Nullish,
+    Neg,
+    Not,
+    BitNot,
+    /// `+=` on `[rhs, current]`
+    AddAssign,
+    /// `-=` on `[rhs, current]`
+    SubAssign,
+
+    // Control flow
+    Jump(u32),
+    /// Pops the condition
+    JumpIfFalse(u32),
+    /// Jumps keeping the value if it is falsy, pops it otherwise
+    JumpIfFalseKeep(u32),
+    /// Jumps keeping the value if it is truthy, pops it otherwise
+    JumpIfTrueKeep(u32),
+    /// Skips the default value of a parameter that was passed
+    JumpIfPassed {
+        param: u16,
+        target: u32,
+    },
+
+    // Values
+    /// Converts the top value to its display string
+    ToStr,
+    /// Pops that many Str values and pushes their concatenation
+    Concat(u16),
+    List(u32),
+    /// Pops that many key, value pairs
+    Dict(u32),
+    /// Pops the start, end and step of a range, the span is the location of the step
+    Range {
+        end: bool,
+        step: bool,
+        inclusive: bool,
+        span: u32,
+    },
+    /// Pops the base and index, the span is the location of the index
+    Index(u32),
+    /// Pops the base, index and value
+    IndexSet(u32),
+    /// Pops the base, start, end, step and value of an assignment to a range index, slices
+    /// are assigned unless the base is an instance
+    SliceSet {
+        end: bool,
+        step: bool,
+        inclusive: bool,
+        span: u32,
+    },
+    /// Gets a property, methods are looked up through the inline cache at the given index
+    GetProp {
+        name: u32,
+        cache: u16,
+    },
+    /// Pops the instance and value
+    SetProp(u32),
+    SetPropAdd(u32),
+    SetPropSub(u32),
+    /// Pops the parent object and the receiver inside bound methods
+    Super {
+        name: u32,
+        bound: bool,
+    },
+
+    // Calls
+    Call(u8),
+    /// Call with named arguments, the names are at the given index of the call pool
+    CallNamed {
+        argc: u8,
+        names: u16,
+    },
+    /// Call in tail position, user functions run in place of the calling one
+    TailCall(u8),
+    TailCallNamed {
+        argc: u8,
+        names: u16,
+    },
+    Closure(u16),
+    Return,
+    /// Pops the checked parent if there is one and the methods, pushes the new object
+    Obj {
+        name: u32,
+        methods: u16,
+        parent: bool,
+    },
+    /// Checks that the top value can be inherited from by the named object
+    Parent(u32),
+    Throw,
+    /// Pops a `use` path and binds the module to a name
+    UseModule(u32),
+    /// Pops a `use` path and pushes the members named at the given index of the name list pool
+    UseNames(u16),
+    /// Pops a `use` path and merges the module into the globals
+    UseAll,
+
+    // Iteration
+    /// Pops the iterated value into the iterator slot
+    IterInit(u16),
+    /// Pushes the next item and index, or jumps once the iterator is exhausted
+    IterNext {
+        iter: u16,
+        target: u32,
+    },
+
+    // Errors
+    /// Catches errors raised until the matching `PopHandler` into the error slot
+    PushHandler {
+        slot: u16,
+        target: u32,
+    },
+    PopHandler,
+    /// Raises the error of the slot again
+    Rethrow(u16),
+    /// Pushes whether the error of the slot is caught by the kinds at the given pool index
+    CatchMatch {
+        slot: u16,
+        kinds: u16,
+    },
+    /// Pushes the value bound to the error name of a catch clause
+    CatchErr {
+        slot: u16,
+        kinds: u16,
+    },
+    /// Pushes the value bound to the value name of a catch clause
+    CatchVal(u16),
+
+    // Patterns, matched against values stored in local slots
+    /// Pops the value and the pattern value, pushes whether they match
+    MatchValue,
+    /// Pops a value, pushes whether it has the type at the given name index
+    IsType(u32),
+    /// Pops a value, pushes whether it is a List of that many items, or at least that many
+    /// with a rest
+    IsList {
+        len: u16,
+        rest: bool,
+    },
+    IsDict,
+    /// Pops a List and pushes its item
+    ListItem(u16),
+    /// Pops a List and pushes its items after the given index
+    ListRest(u16),
+    /// Pops the key and dict, stores the entry in the local slot and pushes whether it exists
+    DictEntry(u16),
+}
+
+/// Instructions of a function with the pools they refer to.
+#[derive(Debug, Default)]
+pub struct Chunk {
+    pub code: Vec<Op>,
+    /// Location of every instruction
+    pub cursors: Vec<Cursor>,
+    /// Extra locations referred to by instructions
+    pub spans: Vec<Cursor>,
+    pub ints: Vec<Int>,
+    pub nums: Vec<OrderedFloat<f64>>,
+    pub strs: Vec<Str>,
+    pub names: Vec<String>,
+    pub protos: Vec<Rc<FnProto>>,
+    /// Names and locations of the named arguments of calls
+    pub calls: Vec<Vec<(String, Cursor)>>,
+    /// Error kinds of catch clauses
+    pub catches: Vec<Vec<String>>,
+    /// Names bound by `use "path" for names`
+    pub uses: Vec<Vec<String>>,
+    /// Inline caches of property accesses
+    pub caches: Vec<PropCache>,
+}
+
+impl Chunk {
+    pub fn emit(&mut self, op: Op, cursor: Cursor) -> usize {
+        self.code.push(op);
+        self.cursors.push(cursor);
+        self.code.len() - 1
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ParamProto {
+    pub name: String,
+    pub default: bool,
+    pub variadic: bool,
+}
+
+/// Where a closure finds a captured variable when it is created.
+#[derive(Debug, Clone, Copy)]
+pub enum Upval {
+    /// A cell of the creating frame
+    Cell(u16),
+    /// An upvalue of the creating closure
+    Upval(u16),
+}
+
+/// A compiled function.
+#[derive(Debug)]
+pub struct FnProto {
+    pub name: String,
+    pub params: Vec<ParamProto>,
+    /// Methods called with an instance take it in the first slot, before the parameters
+    pub has_self: bool,
+    pub chunk: Chunk,
+    pub upvals: Vec<Upval>,
+    pub slots: usize,
+    pub cells: usize,
+    pub iters: usize,
+    pub errs: usize,
+    /// Script the function was declared in
+    pub file: Rc<PathBuf>,
+    pub cursor: Cursor,
+}
@@ -0,0 +1,88 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    evaluator::{
+        Evaluator,
+        env::EnvPtr,
+        runtime_err::EvalResult,
+        value::{Arity, Callable, Value},
+    },
+    lexer::cursor::Cursor,
+    vm::{Vm, chunk::FnProto},
+};
+
+/// A captured variable, shared between the declaring frame and its closures. Hoisted cells stay
+/// empty until their variable is declared.
+pub type Cell = Rc<RefCell<Option<Value>>>;
+
+/// A compiled function with the variables it captured.
+#[derive(Debug, Clone)]
+pub struct Closure {
+    pub proto: Rc<FnProto>,
+    pub upvals: Rc<[Cell]>,
+    /// Globals of the script the function was declared in
+    pub globals: EnvPtr,
+}
+
+impl Closure {
+    pub fn new(proto: Rc<FnProto>, upvals: Rc<[Cell]>, globals: EnvPtr) -> Self {
+        Self {
+            proto,
+            upvals,
+            globals,
+        }
+    }
+}
+
+impl Callable for Closure {
+    fn name(&self) -> &str {
+        &self.proto.name
+    }
+
+    fn arity(&self) -> Arity {
+        let params = &self.proto.params;
+        let min = params.iter().filter(|p| !p.default && !p.variadic).count();
+        if params.last().is_some_and(|p| p.variadic) {
+            return Arity::new(min, None);
+        }
+        Arity::new(min, Some(params.len()))
+    }
+
+    fn call(
+        &self,
+        evaluator: &mut Evaluator,
+        args: Vec<Value>,
+        cursor: Cursor,
+    ) -> EvalResult<Value> {
+        self.call_named(evaluator, args.into_iter().map(Some).collect(), cursor)
+    }
+
+    fn as_closure(&self) -> Option<(&Closure, Option<&Value>)> {
+        Some((self, None))
+    }
+
+    fn param_names(&self) -> Vec<String> {
+        // rest parameters only collect positional arguments
+        self.proto
+            .params
+            .iter()
+            .filter(|p| !p.variadic)
+            .map(|p| p.name.clone())
+            .collect()
+    }
+
+    /// Methods taking `self` get the instance as the first argument.
+    fn call_named(
+        &self,
+        evaluator: &mut Evaluator,
+        mut args: Vec<Option<Value>>,
+        cursor: Cursor,
+    ) -> EvalResult<Value> {
+        let receiver = if self.proto.has_self && !args.is_empty() {
+            args.remove(0)
+        } else {
+            None
+        };
+        Vm::call(evaluator, self, receiver, args, cursor)
+    }
+}
@@ -0,0 +1,1678 @@
+use std::{collections::HashSet, path::PathBuf, rc::Rc};
+
+use ordered_float::OrderedFloat;
+
+use crate::{
+    evaluator::{
+        cache::PropCache,
+        int::Int,
+        range::{Bounds, Range},
+        runtime_err::{ErrKind, EvalResult, RuntimeEvent},
+        string::Str,
+    },
+    lexer::{cursor::Cursor, token::KeywordKind},
+    parser::{
+        expr::{AssignOp, BinaryOp, Expr, ExprKind, LiteralType, LogicalOp, UnaryOp},
+        pattern::{Pattern, PatternKind},
+        stmt::{FnDecl, Stmt, StmtKind},
+    },
+    vm::chunk::{Chunk, FnProto, Op, ParamProto, Upval},
+};
+
+/// Where a local variable lives in its frame.
+#[derive(Debug, Clone, Copy)]
+enum Loc {
+    Slot(u16),
+    Cell(u16),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Var {
+    Local(Loc),
+    Upval(u16),
+    /// Upvalue of a variable declared after the closure referring to it
+    LateUpval(u16),
+    Global(u32),
+}
+
+#[derive(Debug)]
+struct Local {
+    name: String,
+    loc: Loc,
+    /// Declared later in its scope, only nested functions can see it
+    pending: bool,
+}
+
+/// Enclosing parts of a `try` statement that have to be left by `return`, `break` and
+/// `continue`: handlers are popped and ensure blocks are run.
+#[derive(Debug, Clone)]
+enum TryCtx {
+    Handler,
+    Ensure(Box<Stmt>),
+}
+
+#[derive(Debug)]
+struct Loop {
+    /// Length of the try contexts when the loop was entered
+    ctx_len: usize,
+    /// Start of the loop for `continue`, unknown until the step of a `while` loop is compiled
+    start: Option<usize>,
+    breaks: Vec<usize>,
+    continues: Vec<usize>,
+}
+
+/// Compilation state of a single function.
+#[derive(Debug)]
+struct FnState {
+    name: String,
+    params: Vec<ParamProto>,
+    has_self: bool,
+    cursor: Cursor,
+    chunk: Chunk,
+    scopes: Vec<Vec<Local>>,
+    /// Slot and cell counts when each scope was entered
+    marks: Vec<(usize, usize)>,
+    upvals: Vec<(String, Upval)>,
+    /// Names used by nested functions, locals with these names are kept in cells
+    captured: HashSet<String>,
+    /// Names nested functions use before any declaration of them, locals with these names are
+    /// hoisted to the start of their block
+    late: HashSet<String>,
+    slots: usize,
+    cells: usize,
+    iters: usize,
+    errs: usize,
+    max_slots: usize,
+    max_cells: usize,
+    max_iters: usize,
+    max_errs: usize,
+    /// First table that outgrew its operands, the function is too big to be compiled
+    too_big: Option<&'static str>,
+    loops: Vec<Loop>,
+    ctx: Vec<TryCtx>,
+}
+
+impl FnState {
+    fn new(name: String, has_self: bool, names: Names, cursor: Cursor) -> Self {
+        Self {
+            name,
+            params: Vec::new(),
+            has_self,
+            cursor,
+            chunk: Chunk::default(),
+            scopes: Vec::new(),
+            marks: Vec::new(),
+            upvals: Vec::new(),
+            captured: names.used,
+            late: names.late,
+            slots: 0,
+            cells: 0,
+            iters: 0,
+            errs: 0,
+            max_slots: 0,
+            max_cells: 0,
+            max_iters: 0,
+            max_errs: 0,
+            too_big: None,
+            loops: Vec::new(),
+            ctx: Vec::new(),
+        }
+    }
+
+    fn find_local(&self, name: &str) -> Option<Loc> {
+        self.scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|local| local.name == name && !local.pending)
+            .map(|local| local.loc)
+    }
+
+    /// Finds a local for a nested function, `late` references the resolver left unresolved
+    /// can also see the ones declared after the function.
+    fn find_captured(&self, name: &str, late: bool) -> Option<Loc> {
+        self.scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|local| local.name == name && (late || !local.pending))
+            .map(|local| local.loc)
+    }
+
+    /// Converts an index or count to an instruction operand, remembers the first table too big
+    /// for its operands.
+    fn operand<T: TryFrom<usize> + Default>(&mut self, n: usize, what: &'static str) -> T {
+        T::try_from(n).unwrap_or_else(|_| {
+            self.too_big.get_or_insert(what);
+            T::default()
+        })
+    }
+
+    fn add_upval(&mut self, name: &str, upval: Upval) -> u16 {
+        let i = match self.upvals.iter().position(|(n, _)| n == name) {
+            Some(i) => i,
+            None => {
+                self.upvals.push((name.to_string(), upval));
+                self.upvals.len() - 1
+            }
+        };
+        self.operand(i, "captured variables")
+    }
+
+    fn alloc_slot(&mut self) -> u16 {
+        self.slots += 1;
+        self.max_slots = self.max_slots.max(self.slots);
+        self.operand(self.slots - 1, "local variables")
+    }
+
+    /// Location for a new local, captured names get a cell.
+    fn alloc_loc(&mut self, name: &str) -> Loc {
+        if self.captured.contains(name) {
+            self.cells += 1;
+            self.max_cells = self.max_cells.max(self.cells);
+            Loc::Cell(self.operand(self.cells - 1, "captured variables"))
+        } else {
+            Loc::Slot(self.alloc_slot())
+        }
+    }
+
+    fn finish(self, file: Rc<PathBuf>) -> FnProto {
+        FnProto {
+            name: self.name,
+            params: self.params,
+            has_self: self.has_self,
+            chunk: self.chunk,
+            upvals: self.upvals.into_iter().map(|(_, upval)| upval).collect(),
+            slots: self.max_slots,
+            cells: self.max_cells,
+            iters: self.max_iters,
+            errs: self.max_errs,
+            file,
+            cursor: self.cursor,
+        }
+    }
+}
+
+/// Compiles a resolved AST into bytecode, the script itself is compiled to a function without
+/// parameters. Top level declarations are globals, everything else lives in frame slots.
+pub struct Compiler {
+    fns: Vec<FnState>,
+    file: Rc<PathBuf>,
+    /// Error of the first function too big for the operands of its instructions
+    err: Option<RuntimeEvent>,
+}
+
+impl Compiler {
+    pub fn compile(ast: &[Stmt], file: Rc<PathBuf>) -> EvalResult<Rc<FnProto>> {
+        let mut names = Names::default();
+        for stmt in ast {
+            names.stmt(stmt, false);
+        }
+
+        let mut compiler = Self {
+            fns: vec![FnState::new("<script>".into(), false, names, Cursor::new())],
+            file,
+            err: None,
+        };
+        for stmt in ast {
+            compiler.stmt(stmt);
+        }
+        compiler.emit(Op::Null, Cursor::new());
+        compiler.emit(Op::Return, Cursor::new());
+
+        let script = compiler.fns.pop().expect("expected the script function");
+        compiler.check_size(&script);
+        match compiler.err {
+            Some(err) => Err(err),
+            None => Ok(Rc::new(script.finish(compiler.file))),
+        }
+    }
+
+    fn check_size(&mut self, state: &FnState) {
+        if let Some(what) = state.too_big
+            && self.err.is_none()
+        {
+            self.err = Some(RuntimeEvent::error(
+                ErrKind::Value,
+                format!(
+                    "function {} has too many {} to be compiled",
+                    state.name, what
+                ),
+                state.cursor,
+            ));
+        }
+    }
+
+    // Emitting
+
+    fn state(&mut self) -> &mut FnState {
+        self.fns.last_mut().expect("expected a function")
+    }
+
+    fn chunk(&mut self) -> &mut Chunk {
+        &mut self.state().chunk
+    }
+
+    fn emit(&mut self, op: Op, cursor: Cursor) -> usize {
+        self.chunk().emit(op, cursor)
+    }
+
+    fn here(&mut self) -> usize {
+        self.chunk().code.len()
+    }
+
+    /// Points the jump at `at` to the next instruction.
+    fn patch(&mut self, at: usize) {
+        let target = self.here();
+        self.patch_to(at, target);
+    }
+
+    fn patch_to(&mut self, at: usize, target: usize) {
+        let target = target as u32;
+        let op = &mut self.chunk().code[at];
+        match op {
+            Op::Jump(t)
+            | Op::JumpIfFalse(t)
+            | Op::JumpIfFalseKeep(t)
+            | Op::JumpIfTrueKeep(t)
+            | Op::JumpIfPassed { target: t, .. }
+            | Op::IterNext { target: t, .. }
+            | Op::PushHandler { target: t, .. } => *t = target,
+            _ => unreachable!("only jumps can be patched"),
+        }
+    }
+
+    fn int(&mut self, n: Int) -> u32 {
+        let ints = &mut self.chunk().ints;
+        match ints.iter().position(|m| *m == n) {
+            Some(i) => i as u32,
+            None => {
+                ints.push(n);
+                (ints.len() - 1) as u32
+            }
+        }
+    }
+
+    fn num(&mut self, n: OrderedFloat<f64>) -> u32 {
+        let nums = &mut self.chunk().nums;
+        match nums.iter().position(|m| *m == n) {
+            Some(i) => i as u32,
+            None => {
+                nums.push(n);
+                (nums.len() - 1) as u32
+            }
+        }
+    }
+
+    fn str(&mut self, s: &str) -> u32 {
+        let strs = &mut self.chunk().strs;
+        match strs.iter().position(|t| *t.borrow() == *s) {
+            Some(i) => i as u32,
+            None => {
+                strs.push(Str::from(s));
+                (strs.len() - 1) as u32
+            }
+        }
+    }
+
+    fn name(&mut self, s: &str) -> u32 {
+        let names = &mut self.chunk().names;
+        match names.iter().position(|t| t == s) {
+            Some(i) => i as u32,
+            None => {
+                names.push(s.to_string());
+                (names.len() - 1) as u32
+            }
+        }
+    }
+
+    fn span(&mut self, cursor: Cursor) -> u32 {
+        let spans = &mut self.chunk().spans;
+        spans.push(cursor);
+        (spans.len() - 1) as u32
+    }
+
+    // Scopes and variables
+
+    fn begin_scope(&mut self) {
+        let state = self.state();
+        state.marks.push((state.slots, state.cells));
+        state.scopes.push(Vec::new());
+    }
+
+    fn end_scope(&mut self) {
+        let state = self.state();
+        state.scopes.pop();
+        let (slots, cells) = state.marks.pop().expect("expected a scope");
+        state.slots = slots;
+        state.cells = cells;
+    }
+
+    /// Declarations at the top level of the script define globals.
+    fn is_global(&self) -> bool {
+        self.fns.len() == 1 && self.fns[0].scopes.is_empty()
+    }
+
+    /// Resolves a name like the resolver did, `late` is set for references it left unresolved,
+    /// which the tree-walker looks up by name when they run.
+    fn resolve(&mut self, name: &str, late: bool) -> Var {
+        let depth = self.fns.len() - 1;
+        if let Some(loc) = self.fns[depth].find_local(name) {
+            return Var::Local(loc);
+        }
+        match self.resolve_upval(depth, name, late) {
+            Some(i) if late => Var::LateUpval(i),
+            Some(i) => Var::Upval(i),
+            None => Var::Global(self.name(name)),
+        }
+    }
+
+    fn resolve_upval(&mut self, depth: usize, name: &str, late: bool) -> Option<u16> {
+        if depth == 0 {
+            return None;
+        }
+        let upval = match self.fns[depth - 1].find_captured(name, late) {
+            Some(Loc::Cell(k)) => Upval::Cell(k),
+            Some(Loc::Slot(_)) => unreachable!("locals used by nested functions live in cells"),
+            None => Upval::Upval(self.resolve_upval(depth - 1, name, late)?),
+        };
+        Some(self.fns[depth].add_upval(name, upval))
+    }
+
+    fn load_var(&mut self, name: &str, late: bool, cursor: Cursor) {
+        let op = match self.resolve(name, late) {
+            Var::Local(Loc::Slot(s)) => Op::LoadLocal(s),
+            Var::Local(Loc::Cell(k)) => Op::LoadCell(k),
+            Var::Upval(i) => Op::LoadUpval(i),
+            Var::LateUpval(upval) => Op::LoadLateUpval {
+                upval,
+                name: self.name(name),
+            },
+            Var::Global(n) => Op::LoadGlobal(n),
+        };
+        self.emit(op, cursor);
+    }
+
+    /// Assignments the resolver left unresolved go to the globals, like in the tree-walker.
+    fn store_var(&mut self, name: &str, cursor: Cursor) {
+        let op = match self.resolve(name, false) {
+            Var::Local(loc) => return self.store_loc(loc, cursor),
+            Var::Upval(i) => Op::StoreUpval(i),
+            Var::LateUpval(_) => unreachable!("only late loads see hoisted cells"),
+            Var::Global(n) => Op::StoreGlobal(n),
+        };
+        self.emit(op, cursor);
+    }
+
+    fn store_loc(&mut self, loc: Loc, cursor: Cursor) {
+        let op = match loc {
+            Loc::Slot(s) => Op::StoreLocal(s),
+            Loc::Cell(k) => Op::StoreCell(k),
+        };
+        self.emit(op, cursor);
+    }
+
+    /// Pops the value on top of the stack into a new variable.
+    fn declare_var(&mut self, name: &str, cursor: Cursor) {
+        if self.is_global() {
+            let n = self.name(name);
+            self.emit(Op::DefineGlobal(n), cursor);
+            return;
+        }
+        let pending = self
+            .state()
+            .scopes
+            .last_mut()
+            .expect("expected a scope")
+            .iter_mut()
+            .find(|local| local.name == name && local.pending);
+        if let Some(local) = pending {
+            local.pending = false;
+            if let Loc::Cell(k) = local.loc {
+                self.emit(Op::StoreCell(k), cursor);
+                self.emit(Op::Pop, cursor);
+            }
+            return;
+        }
+        let loc = self.state().alloc_loc(name);
+        self.init_loc(loc, cursor);
+        self.add_local(name, loc);
+    }
+
+    /// Creates empty cells for the names a block declares after nested functions referring to
+    /// them, so functions declared first can call the ones declared after them.
+    fn hoist(&mut self, stmts: &[Stmt]) {
+        if self.is_global() {
+            return;
+        }
+        for stmt in stmts {
+            let names: Vec<&String> = match &stmt.kind {
+                StmtKind::Var { name, .. } | StmtKind::Obj { name, .. } => vec![name],
+                StmtKind::Fn(declr) => vec![&declr.name],
+                StmtKind::Use { alias, names, .. } => {
+                    alias.iter().chain(names.iter().flatten()).collect()
+                }
+                _ => continue,
+            };
+            for name in names {
+                let state = self.state();
+                let declared = state
+                    .scopes
+                    .last()
+                    .is_some_and(|scope| scope.iter().any(|local| &local.name == name));
+                if !state.late.contains(name) || declared {
+                    continue;
+                }
+                let Loc::Cell(k) = state.alloc_loc(name) else {
+                    unreachable!("names used by nested functions live in cells")
+                };
+                self.emit(Op::HoistCell(k), stmt.cursor);
+                self.state()
+                    .scopes
+                    .last_mut()
+                    .expect("expected a scope")
+                    .push(Local {
+                        name: name.clone(),
+                        loc: Loc::Cell(k),
+                        pending: true,
+                    });
+            }
+        }
+    }
+
+    /// Pops the value on top of the stack into a location, cells are created anew so closures
+    /// keep the variable they captured.
+    fn init_loc(&mut self, loc: Loc, cursor: Cursor) {
+        match loc {
+            Loc::Slot(s) => {
+                self.emit(Op::StoreLocal(s), cursor);
+                self.emit(Op::Pop, cursor);
+            }
+            Loc::Cell(k) => {
+                self.emit(Op::NewCell(k), cursor);
+            }
+        }
+    }
+
+    fn add_local(&mut self, name: &str, loc: Loc) {
+        self.state()
+            .scopes
+            .last_mut()
+            .expect("expected a scope")
+            .push(Local {
+                name: name.to_string(),
+                loc,
+                pending: false,
+            });
+    }
+
+    /// Leaves the try contexts entered after `to`, ensure blocks are compiled in place.
+    fn leave_ctx(&mut self, to: usize, cursor: Cursor) {
+        let ctx = self.state().ctx.clone();
+        for i in (to..ctx.len()).rev() {
+            match &ctx[i] {
+                TryCtx::Handler => {
+                    self.emit(Op::PopHandler, cursor);
+                }
+                TryCtx::Ensure(stmt) => {
+                    let saved = std::mem::replace(&mut self.state().ctx, ctx[..i].to_vec());
+                    self.stmt(stmt);
+                    self.state().ctx = saved;
+                }
+            }
+        }
+    }
+
+    // Functions
+
+    /// Compiles a function into the pool of the current one, returns its index.
+    fn function(&mut self, declr: &FnDecl, has_self: bool, cursor: Cursor) -> u16 {
+        let FnDecl {
+            name, params, body, ..
+        } = declr;
+        let mut names = Names::default();
+        names.function(declr, false);
+
+        let mut state = FnState::new(name.clone(), has_self, names, cursor);
+        state.params = params
+            .iter()
+            .map(|p| ParamProto {
+                name: p.name.clone(),
+                default: p.default.is_some(),
+                variadic: p.variadic,
+            })
+            .collect();
+        self.fns.push(state);
+        self.begin_scope();
+
+        // the receiver and arguments are placed in the first slots by the caller
+        let self_slot = has_self.then(|| self.state().alloc_slot());
+        let param_slots: Vec<u16> = params.iter().map(|_| self.state().alloc_slot()).collect();
+
+        if let Some(slot) = self_slot {
+            self.param_local(&KeywordKind::KSelf.to_string(), slot, cursor);
+        }
+        for (i, (param, slot)) in params.iter().zip(param_slots).enumerate() {
+            // defaults are evaluated on each call that skips the argument
+            if let Some(default) = &param.default {
+                let index = self.state().operand(i, "parameters");
+                let jump = self.emit(
+                    Op::JumpIfPassed {
+                        param: index,
+                        target: 0,
+                    },
+                    param.cursor,
+                );
+                self.expr(default);
+                self.emit(Op::StoreLocal(slot), param.cursor);
+                self.emit(Op::Pop, param.cursor);
+                self.patch(jump);
+            }
+            self.param_local(&param.name, slot, param.cursor);
+        }
+
+        self.block(body);
+        self.emit(Op::Null, body.cursor);
+        self.emit(Op::Return, body.cursor);
+
+        self.end_scope();
+        let state = self.fns.pop().expect("expected a function");
+        self.check_size(&state);
+        let proto = Rc::new(state.finish(self.file.clone()));
+
+        let protos = &mut self.chunk().protos;
+        protos.push(proto);
+        let i = protos.len() - 1;
+        self.state().operand(i, "nested functions")
+    }
+
+    /// Makes a parameter visible, captured parameters are moved into a cell.
+    fn param_local(&mut self, name: &str, slot: u16, cursor: Cursor) {
+        let loc = if self.state().captured.contains(name) {
+            self.emit(Op::LoadLocal(slot), cursor);
+            let loc = self.state().alloc_loc(name);
+            self.init_loc(loc, cursor);
+            loc
+        } else {
+            Loc::Slot(slot)
+        };
+        self.add_local(name, loc);
+    }
+
+    // Statements
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        let cursor = stmt.cursor;
+        match &stmt.kind {
+            StmtKind::Expr(expr) => {
+                self.expr(expr);
+                self.emit(Op::Pop, cursor);
+            }
+            StmtKind::Throw(expr) => {
+                self.expr(expr);
+                self.emit(Op::Throw, cursor);
+            }
+            StmtKind::Use { path, alias, names } => {
+                self.expr(path);
+                if let Some(alias) = alias {
+                    let n = self.name(alias);
+                    self.emit(Op::UseModule(n), cursor);
+                    self.declare_var(alias, cursor);
+                } else if let Some(names) = names {
+                    let uses = &mut self.chunk().uses;
+                    uses.push(names.clone());
+                    let i = uses.len() - 1;
+                    let i = self.state().operand(i, "use statements");
+                    self.emit(Op::UseNames(i), cursor);
+                    for name in names.iter().rev() {
+                        self.declare_var(name, cursor);
+                    }
+                } else {
+                    self.emit(Op::UseAll, cursor);
+                }
+            }
+            StmtKind::Return { val, tail } => self.stmt_return(val.as_ref(), *tail, cursor),
+            StmtKind::Break | StmtKind::Continue => {
+                let is_break = matches!(stmt.kind, StmtKind::Break);
+                let Some(ctx_len) = self.state().loops.last().map(|l| l.ctx_len) else {
+                    // outside of loops the event leaves the function
+                    self.stmt_return(None, false, cursor);
+                    return;
+                };
+                self.leave_ctx(ctx_len, cursor);
+                let jump = self.emit(Op::Jump(0), cursor);
+                let current = self.state().loops.last_mut().expect("expected a loop");
+                match (is_break, current.start) {
+                    (true, _) => current.breaks.push(jump),
+                    (false, Some(start)) => self.patch_to(jump, start),
+                    (false, None) => current.continues.push(jump),
+                }
+            }
+            StmtKind::Var { name, init } => {
+                match init {
+                    Some(init) => self.expr(init),
+                    None => {
+                        self.emit(Op::Null, cursor);
+                    }
+                }
+                self.declare_var(name, cursor);
+            }
+            StmtKind::Block(_) => {
+                self.begin_scope();
+                self.block(stmt);
+                self.end_scope();
+            }
+            StmtKind::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.expr(condition);
+                let to_else = self.emit(Op::JumpIfFalse(0), cursor);
+                self.stmt(then_branch);
+                match else_branch {
+                    Some(else_branch) => {
+                        let to_end = self.emit(Op::Jump(0), cursor);
+                        self.patch(to_else);
+                        self.stmt(else_branch);
+                        self.patch(to_end);
+                    }
+                    None => self.patch(to_else),
+                }
+            }
+            StmtKind::Match { .. } => self.stmt_match(stmt),
+            StmtKind::While {
+                declr,
+                condition,
+                step,
+                body,
+            } => {
+                if let Some(declr) = declr {
+                    self.stmt(declr);
+                }
+                let start = self.here();
+                self.expr(condition);
+                let exit = self.emit(Op::JumpIfFalse(0), cursor);
+
+                self.begin_loop(None);
+                self.stmt(body);
+                let continues = std::mem::take(
+                    &mut self
+                        .state()
+                        .loops
+                        .last_mut()
+                        .expect("expected a loop")
+                        .continues,
+                );
+                for jump in continues {
+                    self.patch(jump);
+                }
+                if let Some(step) = step {
+                    self.expr(step);
+                    self.emit(Op::Pop, cursor);
+                }
+                let back = self.emit(Op::Jump(0), cursor);
+                self.patch_to(back, start);
+                self.patch(exit);
+                self.end_loop();
+            }
+            StmtKind::For {
+                item,
+                index,
+                iter,
+                body,
+            } => {
+                self.expr(iter);
+                let state = self.state();
+                state.iters += 1;
+                state.max_iters = state.max_iters.max(state.iters);
+                let k = state.operand(state.iters - 1, "nested for loops");
+                self.emit(Op::IterInit(k), cursor);
+
+                let start = self.emit(Op::IterNext { iter: k, target: 0 }, cursor);
+                self.begin_loop(Some(start));
+                self.begin_scope();
+                match index {
+                    Some(index) => self.declare_var(index, cursor),
+                    None => {
+                        self.emit(Op::Pop, cursor);
+                    }
+                }
+                self.declare_var(item, cursor);
+                self.block(body);
+                self.end_scope();
+                let back = self.emit(Op::Jump(0), cursor);
+                self.patch_to(back, start);
+                self.patch(start);
+                self.end_loop();
+
+                self.state().iters -= 1;
+            }
+            StmtKind::Try { .. } => self.stmt_try(stmt),
+            StmtKind::Fn(declr) => {
+                let name = &declr.name;
+                if self.is_global() {
+                    let proto = self.function(declr, false, cursor);
+                    self.emit(Op::Closure(proto), cursor);
+                    let n = self.name(name);
+                    self.emit(Op::DefineGlobal(n), cursor);
+                    return;
+                }
+                // declared first so the function can call itself
+                self.emit(Op::Null, cursor);
+                self.declare_var(name, cursor);
+                let proto = self.function(declr, false, cursor);
+                self.emit(Op::Closure(proto), cursor);
+                self.store_var(name, cursor);
+                self.emit(Op::Pop, cursor);
+            }
+            StmtKind::Obj {
+                name,
+                parent,
+                methods,
+            } => {
+                // declared first so methods can refer to the object
+                self.emit(Op::Null, cursor);
+                self.declare_var(name, cursor);
+
+                self.begin_scope();
+                if let Some(parent) = parent {
+                    self.expr(parent);
+                    let n = self.name(name);
+                    self.emit(Op::Parent(n), parent.cursor);
+                    self.emit(Op::Dup, cursor);
+                    self.declare_var(&KeywordKind::Super.to_string(), cursor);
+                }
+                for method in methods {
+                    if let StmtKind::Fn(declr) = &method.kind {
+                        let has_self = declr.bound || declr.name == "init";
+                        let proto = self.function(declr, has_self, method.cursor);
+                        self.emit(Op::Closure(proto), method.cursor);
+                    }
+                }
+                let n = self.name(name);
+                let count = self.state().operand(methods.len(), "methods");
+                self.emit(
+                    Op::Obj {
+                        name: n,
+                        methods: count,
+                        parent: parent.is_some(),
+                    },
+                    cursor,
+                );
+                self.end_scope();
+
+                self.store_var(name, cursor);
+                self.emit(Op::Pop, cursor);
+            }
+        }
+    }
+
+    /// Compiles the statements of a block in the current scope.
+    fn block(&mut self, stmt: &Stmt) {
+        match &stmt.kind {
+            StmtKind::Block(stmts) => {
+                self.hoist(stmts);
+                for stmt in stmts {
+                    self.stmt(stmt);
+                }
+            }
+            _ => self.stmt(stmt),
+        }
+    }
+
+    fn begin_loop(&mut self, start: Option<usize>) {
+        let ctx_len = self.state().ctx.len();
+        self.state().loops.push(Loop {
+            ctx_len,
+            start,
+            breaks: Vec::new(),
+            continues: Vec::new(),
+        });
+    }
+
+    fn end_loop(&mut self) {
+        let current = self.state().loops.pop().expect("expected a loop");
+        for jump in current.breaks {
+            self.patch(jump);
+        }
+    }
+
+    fn stmt_return(&mut self, val: Option<&Expr>, tail: bool, cursor: Cursor) {
+        let in_try = !self.state().ctx.is_empty();
+        match val {
+            Some(Expr {
+                kind:
+                    ExprKind::Call {
+                        callee,
+                        args,
+                        named,
+                    },
+                cursor,
+                ..
+            }) if tail && !in_try => {
+                self.expr(callee);
+                for arg in args {
+                    self.expr(arg);
+                }
+                for arg in named {
+                    self.expr(&arg.val);
+                }
+                let op = self.call_op(args.len(), named, true);
+                self.emit(op, *cursor);
+                return;
+            }
+            Some(val) => self.expr(val),
+            None => {
+                self.emit(Op::Null, cursor);
+            }
+        }
+        self.leave_ctx(0, cursor);
+        self.emit(Op::Return, cursor);
+    }
+
+    fn stmt_match(&mut self, stmt: &Stmt) {
+        let StmtKind::Match {
+            val,
+            arms,
+            else_branch,
+        } = &stmt.kind
+        else {
+            unreachable!("Non-match statement passed to Compiler::stmt_match");
+        };
+        let cursor = stmt.cursor;
+
+        self.begin_scope();
+        self.expr(val);
+        let src = self.state().alloc_slot();
+        self.emit(Op::StoreLocal(src), cursor);
+        self.emit(Op::Pop, cursor);
+
+        let mut to_end = Vec::new();
+        for arm in arms {
+            self.begin_scope();
+
+            // every name of the pattern is bound, names of unmatched alternatives stay Null
+            let binds: Vec<(String, Loc)> = arm
+                .pattern
+                .bindings()
+                .into_iter()
+                .map(|(name, _)| {
+                    let loc = self.state().alloc_loc(&name);
+                    (name, loc)
+                })
+                .collect();
+            for (_, loc) in &binds {
+                self.emit(Op::Null, arm.pattern.cursor);
+                self.init_loc(*loc, arm.pattern.cursor);
+            }
+
+            // pattern values are evaluated before the bound names are visible
+            let mut fails = Vec::new();
+            self.pattern(&arm.pattern, src, &binds, &mut fails);
+            for (name, loc) in &binds {
+                self.add_local(name, *loc);
+            }
+
+            if let Some(guard) = &arm.guard {
+                self.expr(guard);
+                fails.push(self.emit(Op::JumpIfFalse(0), guard.cursor));
+            }
+            self.stmt(&arm.body);
+            to_end.push(self.emit(Op::Jump(0), cursor));
+            self.end_scope();
+
+            for jump in fails {
+                self.patch(jump);
+            }
+        }
+
+        if let Some(else_branch) = else_branch {
+            self.stmt(else_branch);
+        }
+        for jump in to_end {
+            self.patch(jump);
+        }
+        self.end_scope();
+    }
+
+    /// Matches the value in slot `src` against a pattern, mismatches jump to the `fails`.
+    fn pattern(
+        &mut self,
+        pattern: &Pattern,
+        src: u16,
+        binds: &[(String, Loc)],
+        fails: &mut Vec<usize>,
+    ) {
+        let cursor = pattern.cursor;
+        let bind = |this: &mut Self, name: &str| {
+            let (_, loc) = binds
+                .iter()
+                .find(|(n, _)| n == name)
+                .expect("expected a pattern binding");
+            this.emit(Op::LoadLocal(src), cursor);
+            this.store_loc(*loc, cursor);
+            this.emit(Op::Pop, cursor);
+        };
+
+        match &pattern.kind {
+            PatternKind::Wildcard => {}
+            PatternKind::Bind(name) => bind(self, name),
+            PatternKind::Value(expr) => {
+                self.emit(Op::LoadLocal(src), cursor);
+                self.expr(expr);
+                self.emit(Op::MatchValue, expr.cursor);
+                fails.push(self.emit(Op::JumpIfFalse(0), cursor));
+            }
+            PatternKind::Type { name, bind: var } => {
+                self.emit(Op::LoadLocal(src), cursor);
+                let n = self.name(name);
+                self.emit(Op::IsType(n), cursor);
+                fails.push(self.emit(Op::JumpIfFalse(0), cursor));
+                if let Some(var) = var {
+                    bind(self, var);
+                }
+            }
+            PatternKind::List { items, rest } => {
+                self.emit(Op::LoadLocal(src), cursor);
+                let len = self.state().operand(items.len(), "list pattern items");
+                self.emit(
+                    Op::IsList {
+                        len,
+                        rest: rest.is_some(),
+                    },
+                    cursor,
+                );
+                fails.push(self.emit(Op::JumpIfFalse(0), cursor));
+
+                for (i, item) in items.iter().enumerate() {
+                    if let PatternKind::Wildcard = item.kind {
+                        continue;
+                    }
+                    let slot = self.state().alloc_slot();
+                    self.emit(Op::LoadLocal(src), item.cursor);
+                    let i = self.state().operand(i, "list pattern items");
+                    self.emit(Op::ListItem(i), item.cursor);
+                    self.emit(Op::StoreLocal(slot), item.cursor);
+                    self.emit(Op::Pop, item.cursor);
+                    self.pattern(item, slot, binds, fails);
+                }
+                if let Some(rest) = rest {
+                    let slot = self.state().alloc_slot();
+                    self.emit(Op::LoadLocal(src), rest.cursor);
+                    let i = self.state().operand(items.len(), "list pattern items");
+                    self.emit(Op::ListRest(i), rest.cursor);
+                    self.emit(Op::StoreLocal(slot), rest.cursor);
+                    self.emit(Op::Pop, rest.cursor);
+                    self.pattern(rest, slot, binds, fails);
+                }
+            }
+            PatternKind::Dict(entries) => {
+                self.emit(Op::LoadLocal(src), cursor);
+                self.emit(Op::IsDict, cursor);
+                fails.push(self.emit(Op::JumpIfFalse(0), cursor));
+
+                for (key, value) in entries {
+                    let slot = self.state().alloc_slot();
+                    self.emit(Op::LoadLocal(src), key.cursor);
+                    self.expr(key);
+                    self.emit(Op::DictEntry(slot), key.cursor);
+                    fails.push(self.emit(Op::JumpIfFalse(0), key.cursor));
+                    self.pattern(value, slot, binds, fails);
+                }
+            }
+            PatternKind::Or(alts) => {
+                let mut matched = Vec::new();
+                for (i, alt) in alts.iter().enumerate() {
+                    if i == alts.len() - 1 {
+                        self.pattern(alt, src, binds, fails);
+                        break;
+                    }
+                    let mut alt_fails = Vec::new();
+                    self.pattern(alt, src, binds, &mut alt_fails);
+                    matched.push(self.emit(Op::Jump(0), alt.cursor));
+                    for jump in alt_fails {
+                        self.patch(jump);
+                    }
+                    // names bound before the alternative failed are unbound again
+                    for (name, _) in alt.bindings() {
+                        if let Some((_, loc)) = binds.iter().find(|(n, _)| *n == name) {
+                            self.emit(Op::Null, alt.cursor);
+                            self.store_loc(*loc, alt.cursor);
+                            self.emit(Op::Pop, alt.cursor);
+                        }
+                    }
+                }
+                for jump in matched {
+                    self.patch(jump);
+                }
+            }
+        }
+    }
+
+    fn stmt_try(&mut self, stmt: &Stmt) {
+        let StmtKind::Try {
+            body,
+            catches,
+            ensure,
+        } = &stmt.kind
+        else {
+            unreachable!("Non-try statement passed to Compiler::stmt_try");
+        };
+        let cursor = stmt.cursor;
+
+        let slot = self.alloc_err();
+        if let Some(ensure) = ensure {
+            self.state().ctx.push(TryCtx::Ensure(ensure.clone()));
+        }
+
+        let handler = self.emit(Op::PushHandler { slot, target: 0 }, cursor);
+        self.state().ctx.push(TryCtx::Handler);
+        self.stmt(body);
+        self.state().ctx.pop();
+        self.emit(Op::PopHandler, cursor);
+        let to_after = self.emit(Op::Jump(0), cursor);
+
+        // errors left by the catch clauses still run the ensure block
+        self.patch(handler);
+        let ensure_handler = match ensure {
+            Some(_) => {
+                let ensure_slot = self.alloc_err();
+                let jump = self.emit(
+                    Op::PushHandler {
+                        slot: ensure_slot,
+                        target: 0,
+                    },
+                    cursor,
+                );
+                self.state().ctx.push(TryCtx::Handler);
+                Some((jump, ensure_slot))
+            }
+            None => None,
+        };
+
+        let mut caught = Vec::new();
+        for clause in catches {
+            let kinds = &mut self.chunk().catches;
+            kinds.push(clause.kinds.clone());
+            let kinds = kinds.len() - 1;
+            let kinds = self.state().operand(kinds, "catch clauses");
+
+            let next = if clause.kinds.is_empty() {
+                None
+            } else {
+                self.emit(Op::CatchMatch { slot, kinds }, clause.cursor);
+                Some(self.emit(Op::JumpIfFalse(0), clause.cursor))
+            };
+
+            self.begin_scope();
+            if let Some(name) = &clause.err {
+                self.emit(Op::CatchErr { slot, kinds }, clause.cursor);
+                self.declare_var(name, clause.cursor);
+            }
+            if let Some(name) = &clause.val {
+                self.emit(Op::CatchVal(slot), clause.cursor);
+                self.declare_var(name, clause.cursor);
+            }
+            self.block(&clause.body);
+            self.end_scope();
+            caught.push(self.emit(Op::Jump(0), clause.cursor));
+
+            if let Some(next) = next {
+                self.patch(next);
+            }
+        }
+        self.emit(Op::Rethrow(slot), cursor);
+        for jump in caught {
+            self.patch(jump);
+        }
+
+        if let Some((jump, ensure_slot)) = ensure_handler {
+            self.state().ctx.pop();
+            self.emit(Op::PopHandler, cursor);
+            self.state().ctx.pop();
+
+            let ensure = ensure.as_ref().expect("expected an ensure block");
+            self.patch(to_after);
+            self.stmt(ensure);
+            let to_end = self.emit(Op::Jump(0), cursor);
+
+            self.patch(jump);
+            self.stmt(ensure);
+            self.emit(Op::Rethrow(ensure_slot), cursor);
+            self.patch(to_end);
+            self.state().errs -= 1;
+        } else {
+            self.patch(to_after);
+        }
+        self.state().errs -= 1;
+    }
+
+    fn alloc_err(&mut self) -> u16 {
+        let state = self.state();
+        state.errs += 1;
+        state.max_errs = state.max_errs.max(state.errs);
+        state.operand(state.errs - 1, "nested try statements")
+    }
+
+    // Expressions
+
+    /// Builds a range precomputed by the optimizer again from its bounds, its step already has
+    /// the direction of its end.
+    fn range_literal(&mut self, range: &Range, cursor: Cursor) {
+        let has_end = match &range.bounds {
+            Bounds::Int { start, end, step } => {
+                for n in [Some(start), end.as_ref(), Some(step)]
+                    .into_iter()
+                    .flatten()
+                {
+                    let op = Op::Int(self.int(n.clone()));
+                    self.emit(op, cursor);
+                }
+                end.is_some()
+            }
+            Bounds::Num { start, end, step } => {
+                for n in [Some(start), end.as_ref(), Some(step)]
+                    .into_iter()
+                    .flatten()
+                {
+                    let op = Op::Num(self.num(OrderedFloat(*n)));
+                    self.emit(op, cursor);
+                }
+                end.is_some()
+            }
+        };
+        let span = self.span(cursor);
+        self.emit(
+            Op::Range {
+                end: has_end,
+                step: true,
+                inclusive: range.inclusive,
+                span,
+            },
+            cursor,
+        );
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        let cursor = expr.cursor;
+        match &expr.kind {
+            ExprKind::Literal(literal) => {
+                let op = match literal {
+                    LiteralType::Null => Op::Null,
+                    LiteralType::Bool(true) => Op::True,
+                    LiteralType::Bool(false) => Op::False,
+                    LiteralType::Int(n) => Op::Int(self.int(n.clone())),
+                    LiteralType::Num(n) => Op::Num(self.num(*n)),
+                    LiteralType::Str(s) => Op::Str(self.str(s)),
+                    LiteralType::Range(range) => return self.range_literal(range, cursor),
+                };
+                self.emit(op, cursor);
+            }
+            ExprKind::Interp(parts) => {
+                for part in parts {
+                    self.expr(part);
+                    self.emit(Op::ToStr, part.cursor);
+                }
+                let n = self.state().operand(parts.len(), "interpolated values");
+                self.emit(Op::Concat(n), cursor);
+            }
+            ExprKind::List(items) => {
+                for item in items {
+                    self.expr(item);
+                }
+                self.emit(Op::List(items.len() as u32), cursor);
+            }
+            ExprKind::Dict(entries) => {
+                for (key, val) in entries {
+                    self.expr(key);
+                    self.expr(val);
+                }
+                self.emit(Op::Dict(entries.len() as u32), cursor);
+            }
+            ExprKind::Range {
+                start,
+                end,
+                inclusive,
+                step,
+            } => {
+                self.expr(start);
+                if let Some(end) = end {
+                    self.expr(end);
+                }
+                if let Some(step) = step {
+                    self.expr(step);
+                }
+                let span = self.span(step.as_ref().map_or(cursor, |step| step.cursor));
+                self.emit(
+                    Op::Range {
+                        end: end.is_some(),
+                        step: step.is_some(),
+                        inclusive: *inclusive,
+                        span,
+                    },
+                    cursor,
+                );
+            }
+            ExprKind::Assign { name, op, val } => {
+                let late = expr.is_late();
+                self.expr(val);
+                match op {
+                    AssignOp::Value => {}
+                    AssignOp::Add => {
+                        self.load_var(name, late, cursor);
+                        self.emit(Op::AddAssign, cursor);
+                    }
+                    AssignOp::Sub => {
+                        self.load_var(name, late, cursor);
+                        self.emit(Op::SubAssign, cursor);
+                    }
+                }
+                self.store_var(name, cursor);
+            }
+            ExprKind::Binary { left, op, right } => {
+                self.expr(left);
+                self.expr(right);
+                let op = match op {
+                    BinaryOp::Add => Op::Add,
+                    BinaryOp::Sub => Op::Sub,
+                    BinaryOp::Mult => Op::Mul,
+                    BinaryOp::Div => Op::Div,
+                    BinaryOp::FloorDiv => Op::FloorDiv,
+                    BinaryOp::Mod => Op::Mod,
+                    BinaryOp::Pow => Op::Pow,
+                    BinaryOp::BitAnd => Op::BitAnd,
+                    BinaryOp::BitOr => Op::BitOr,
+                    BinaryOp::BitXor => Op::BitXor,
+                    BinaryOp::ShiftLeft => Op::ShiftLeft,
+                    BinaryOp::ShiftRight => Op::ShiftRight,
+                    BinaryOp::Equals => Op::Equals,
+                    BinaryOp::NotEquals => Op::NotEquals,
+                    BinaryOp::Greater => Op::Greater,
+                    BinaryOp::GreaterEquals => Op::GreaterEquals,
+                    BinaryOp::Lesser => Op::Lesser,
+                    BinaryOp::LesserEquals => Op::LesserEquals,
+                    BinaryOp::Nullish => Op::Nullish,
+                };
+                self.emit(op, cursor);
+            }
+            ExprKind::Ternary {
+                condition,
+                true_branch,
+                false_branch,
+            } => {
+                self.expr(condition);
+                let to_false = self.emit(Op::JumpIfFalse(0), cursor);
+                self.expr(true_branch);
+                let to_end = self.emit(Op::Jump(0), cursor);
+                self.patch(to_false);
+                self.expr(false_branch);
+                self.patch(to_end);
+            }
+            ExprKind::Call {
+                callee,
+                args,
+                named,
+            } => {
+                self.expr(callee);
+                for arg in args {
+                    self.expr(arg);
+                }
+                for arg in named {
+                    self.expr(&arg.val);
+                }
+                let op = self.call_op(args.len(), named, false);
+                self.emit(op, cursor);
+            }
+            ExprKind::Lambda(declr) => {
+                let proto = self.function(declr, false, cursor);
+                self.emit(Op::Closure(proto), cursor);
+            }
+            ExprKind::Grouping { expr } => self.expr(expr),
+            ExprKind::Unary { op, right } => {
+                self.expr(right);
+                let op = match op {
+                    UnaryOp::Negate => Op::Neg,
+                    UnaryOp::Not => Op::Not,
+                    UnaryOp::BitNot => Op::BitNot,
+                };
+                self.emit(op, cursor);
+            }
+            ExprKind::Logical { left, op, right } => {
+                self.expr(left);
+                let jump = match op {
+                    LogicalOp::And => Op::JumpIfFalseKeep(0),
+                    LogicalOp::Or => Op::JumpIfTrueKeep(0),
+                };
+                let jump = self.emit(jump, cursor);
+                self.expr(right);
+                self.patch(jump);
+            }
+            ExprKind::Var(name) => self.load_var(name, expr.is_late(), cursor),
+            ExprKind::Get { obj, name, .. } => {
+                self.expr(obj);
+                let name = self.name(name);
+                let caches = &mut self.chunk().caches;
+                caches.push(PropCache::default());
+                let cache = caches.len() - 1;
+                let cache = self.state().operand(cache, "property accesses");
+                self.emit(Op::GetProp { name, cache }, cursor);
+            }
+            ExprKind::Set { obj, name, op, val } => {
+                self.expr(obj);
+                self.expr(val);
+                let n = self.name(name);
+                let op = match op {
+                    AssignOp::Value => Op::SetProp(n),
+                    AssignOp::Add => Op::SetPropAdd(n),
+                    AssignOp::Sub => Op::SetPropSub(n),
+                };
+                self.emit(op, cursor);
+            }
+            ExprKind::Index { obj, index } => {
+                self.expr(obj);
+                self.expr(index);
+                let span = self.span(index.cursor);
+                self.emit(Op::Index(span), cursor);
+            }
+            ExprKind::IndexSet {
+                obj, index, val, ..
+            } => {
+                self.expr(obj);
+                let span = self.span(index.cursor);
+                // range literals assign slices, unless the value is an instance
+                if let ExprKind::Range {
+                    start,
+                    end,
+                    inclusive,
+                    step,
+                } = &index.kind
+                {
+                    self.expr(start);
+                    if let Some(end) = end {
+                        self.expr(end);
+                    }
+                    if let Some(step) = step {
+                        self.expr(step);
+                    }
+                    self.expr(val);
+                    self.emit(
+                        Op::SliceSet {
+                            end: end.is_some(),
+                            step: step.is_some(),
+                            inclusive: *inclusive,
+                            span,
+                        },
+                        cursor,
+                    );
+                    return;
+                }
+                self.expr(index);
+                self.expr(val);
+                self.emit(Op::IndexSet(span), cursor);
+            }
+            ExprKind::ESelf => self.load_var(&KeywordKind::KSelf.to_string(), false, cursor),
+            ExprKind::Super { method } => {
+                self.load_var(&KeywordKind::Super.to_string(), false, cursor);
+                // inside bound methods the parent method is bound to `self`
+                let self_name = KeywordKind::KSelf.to_string();
+                let bound = !matches!(self.resolve(&self_name, false), Var::Global(_));
+                if bound {
+                    self.load_var(&self_name, false, cursor);
+                }
+                let name = self.name(method);
+                self.emit(Op::Super { name, bound }, cursor);
+            }
+        }
+    }
+
+    fn call_op(&mut self, argc: usize, named: &[crate::parser::expr::NamedArg], tail: bool) -> Op {
+        let argc = self.state().operand(argc, "arguments in a call");
+        if named.is_empty() {
+            return if tail {
+                Op::TailCall(argc)
+            } else {
+                Op::Call(argc)
+            };
+        }
+
+        let calls = &mut self.chunk().calls;
+        calls.push(named.iter().map(|a| (a.name.clone(), a.cursor)).collect());
+        let names = calls.len() - 1;
+        let names = self.state().operand(names, "calls with named arguments");
+        if tail {
+            Op::TailCallNamed { argc, names }
+        } else {
+            Op::CallNamed { argc, names }
+        }
+    }
+}
+
+/// Collects the names used by the functions nested in a function, the compiler keeps locals
+/// with those names in cells.
+#[derive(Default)]
+struct Names {
+    used: HashSet<String>,
+    /// Used names the resolver didn't resolve to a declaration before the use
+    late: HashSet<String>,
+}
+
+impl Names {
+    fn function(&mut self, declr: &FnDecl, nested: bool) {
+        for param in &declr.params {
+            if let Some(default) = &param.default {
+                self.expr(default, nested);
+            }
+        }
+        self.stmt(&declr.body, nested);
+    }
+
+    fn use_name(&mut self, name: &str, late: bool, nested: bool) {
+        if nested {
+            self.used.insert(name.to_string());
+            if late {
+                self.late.insert(name.to_string());
+            }
+        }
+    }
+
+    fn stmt(&mut self, stmt: &Stmt, nested: bool) {
+        match &stmt.kind {
+            StmtKind::Expr(expr) | StmtKind::Throw(expr) => self.expr(expr, nested),
+            StmtKind::Use { path, .. } => self.expr(path, nested),
+            StmtKind::Return { val, .. } => {
+                if let Some(val) = val {
+                    self.expr(val, nested);
+                }
+            }
+            StmtKind::Break | StmtKind::Continue => {}
+            StmtKind::Var { init, .. } => {
+                if let Some(init) = init {
+                    self.expr(init, nested);
+                }
+            }
+            StmtKind::Block(stmts) => {
+                for stmt in stmts {
+                    self.stmt(stmt, nested);
+                }
+            }
+            StmtKind::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.expr(condition, nested);
+                self.stmt(then_branch, nested);
+                if let Some(else_branch) = else_branch {
+                    self.stmt(else_branch, nested);
+                }
+            }
+            StmtKind::Match {
+                val,
+                arms,
+                else_branch,
+            } => {
+                self.expr(val, nested);
+                for arm in arms {
+                    self.pattern(&arm.pattern, nested);
+                    if let Some(guard) = &arm.guard {
+                        self.expr(guard, nested);
+                    }
+                    self.stmt(&arm.body, nested);
+                }
+                if let Some(else_branch) = else_branch {
+                    self.stmt(else_branch, nested);
+                }
+            }
+            StmtKind::While {
+                declr,
+                condition,
+                step,
+                body,
+            } => {
+                if let Some(declr) = declr {
+                    self.stmt(declr, nested);
+                }
+                self.expr(condition, nested);
+                if let Some(step) = step {
+                    self.expr(step, nested);
+                }
+                self.stmt(body, nested);
+            }
+            StmtKind::For { iter, body, .. } => {
+                self.expr(iter, nested);
+                self.stmt(body, nested);
+            }
+            StmtKind::Try {
+                body,
+                catches,
+                ensure,
+            } => {
+                self.stmt(body, nested);
+                for clause in catches {
+                    self.stmt(&clause.body, nested);
+                }
+                if let Some(ensure) = ensure {
+                    self.stmt(ensure, nested);
+                }
+            }
+            StmtKind::Fn(declr) => self.function(declr, true),
+            StmtKind::Obj {
+                parent, methods, ..
+            } => {
+                if let Some(parent) = parent {
+                    self.expr(parent, nested);
+                }
+                for method in methods {
+                    self.stmt(method, nested);
+                }
+            }
+        }
+    }
+
+    fn pattern(&mut self, pattern: &Pattern, nested: bool) {
+        match &pattern.kind {
+            PatternKind::Value(expr) => self.expr(expr, nested),
+            PatternKind::List { items, rest } => {
+                for item in items {
+                    self.pattern(item, nested);
+                }
+                if let Some(rest) = rest {
+                    self.pattern(rest, nested);
+                }
+            }
+            PatternKind::Dict(entries) => {
+                for (key, value) in entries {
+                    self.expr(key, nested);
+                    self.pattern(value, nested);
+                }
+            }
+            PatternKind::Or(alts) => {
+                for alt in alts {
+                    self.pattern(alt, nested);
+                }
+            }
+            PatternKind::Wildcard | PatternKind::Bind(_) | PatternKind::Type { .. } => {}
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr, nested: bool) {
+        match &expr.kind {
+            ExprKind::Literal(_) => {}
+            ExprKind::Interp(items) | ExprKind::List(items) => {
+                for item in items {
+                    self.expr(item, nested);
+                }
+            }
+            ExprKind::Dict(entries) => {
+                for (key, val) in entries {
+                    self.expr(key, nested);
+                    self.expr(val, nested);
+                }
+            }
+            ExprKind::Range {
+                start, end, step, ..
+            } => {
+                self.expr(start, nested);
+                for expr in [end, step].into_iter().flatten() {
+                    self.expr(expr, nested);
+                }
+            }
+            ExprKind::Assign { name, val, .. } => {
+                self.use_name(name, expr.is_late(), nested);
+                self.expr(val, nested);
+            }
+            ExprKind::Binary { left, right, .. } | ExprKind::Logical { left, right, .. } => {
+                self.expr(left, nested);
+                self.expr(right, nested);
+            }
+            ExprKind::Ternary {
+                condition,
+                true_branch,
+                false_branch,
+            } => {
+                self.expr(condition, nested);
+                self.expr(true_branch, nested);
+                self.expr(false_branch, nested);
+            }
+            ExprKind::Call {
+                callee,
+                args,
+                named,
+            } => {
+                self.expr(callee, nested);
+                for arg in args {
+                    self.expr(arg, nested);
+                }
+                for arg in named {
+                    self.expr(&arg.val, nested);
+                }
+            }
+            ExprKind::Lambda(declr) => self.function(declr, true),
+            ExprKind::Grouping { expr } => self.expr(expr, nested),
+            ExprKind::Unary { right, .. } => self.expr(right, nested),
+            ExprKind::Var(name) => self.use_name(name, expr.is_late(), nested),
+            ExprKind::Get { obj, .. } => self.expr(obj, nested),
+            ExprKind::Set { obj, val, .. } => {
+                self.expr(obj, nested);
+                self.expr(val, nested);
+            }
+            ExprKind::Index { obj, index } => {
+                self.expr(obj, nested);
+                self.expr(index, nested);
+            }
+            ExprKind::IndexSet {
+                obj, index, val, ..
+            } => {
+                self.expr(obj, nested);
+                self.expr(index, nested);
+                self.expr(val, nested);
+            }
+            ExprKind::ESelf => self.use_name(&KeywordKind::KSelf.to_string(), false, nested),
+            ExprKind::Super { .. } => {
+                self.use_name(&KeywordKind::Super.to_string(), false, nested);
+                self.use_name(&KeywordKind::KSelf.to_string(), false, nested);
+            }
+        }
+    }
+}
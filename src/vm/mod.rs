@@ -0,0 +1,892 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use crate::{
+    evaluator::{
+        Evaluator,
+        env::EnvPtr,
+        iter::ValueIter,
+        loader::Module,
+        object::{Method, NativeMethod, Object},
+        runtime_err::{ErrKind, EvalResult, RuntimeEvent},
+        string::Str,
+        value::Value,
+    },
+    lexer::cursor::Cursor,
+    parser::expr::{AssignOp, BinaryOp, UnaryOp},
+    vm::{
+        chunk::{FnProto, Op, Upval},
+        closure::{Cell, Closure},
+        compiler::Compiler,
+    },
+};
+
+pub mod chunk;
+pub mod closure;
+pub mod compiler;
+
+/// A call of a closure running on the VM.
+struct CallFrame {
+    closure: Closure,
+    ip: usize,
+    /// Stack index of the first slot, the result of the call replaces everything above it
+    base: usize,
+    cells: Vec<Option<Cell>>,
+    iters: Vec<Option<ValueIter>>,
+    errs: Vec<Option<RuntimeEvent>>,
+    /// Whether each parameter got an argument
+    passed: Vec<bool>,
+    /// Whether the VM pushed the evaluator frame of the call, the outermost frame's is pushed by
+    /// the caller of the VM
+    traced: bool,
+}
+
+impl CallFrame {
+    fn proto(&self) -> &FnProto {
+        &self.closure.proto
+    }
+}
+
+/// A `try` body being run, errors jump to the catch clauses of the handler's frame.
+struct Handler {
+    frame: usize,
+    target: usize,
+    slot: u16,
+    stack_len: usize,
+}
+
+/// Stack VM running bytecode compiled from the resolved AST. Values, natives and errors are the
+/// ones of the tree-walking evaluator, which still runs the natives and the parts of the
+/// language both backends share.
+pub struct Vm<'e, 'a> {
+    evaluator: &'e mut Evaluator<'a>,
+    stack: Vec<Value>,
+    frames: Vec<CallFrame>,
+    handlers: Vec<Handler>,
+}
+
+impl<'e, 'a> Vm<'e, 'a> {
+    fn new(evaluator: &'e mut Evaluator<'a>) -> Self {
+        Self {
+            evaluator,
+            stack: Vec::new(),
+            frames: Vec::new(),
+            handlers: Vec::new(),
+        }
+    }
+
+    /// Compiles and runs the script of the evaluator, errors are reported like with
+    /// `Evaluator::eval`.
+    pub fn eval(evaluator: &mut Evaluator) -> EvalResult<()> {
+        let src = evaluator.src;
+        let ast = src.ast.as_deref().expect("expected ast");
+        let proto = match Compiler::compile(ast, evaluator.file.clone()) {
+            Ok(proto) => proto,
+            Err(err) => {
+                evaluator.report_err(&err);
+                return Err(err);
+            }
+        };
+        let script = Closure::new(proto, Rc::from([]), evaluator.env.clone());
+
+        let res = Vm::new(evaluator).run_closure(&script, None, Vec::new());
+        if let Err(err) = &res {
+            evaluator.report_err(err);
+        }
+        res.map(|_| ())
+    }
+
+    /// Runs a closure called from outside the VM, `receiver` is passed to methods taking
+    /// `self`. Errors leaving it record the call stack before the caller's script is restored.
+    /// The new VM runs on the native stack of the caller, so it counts against the recursion
+    /// limit like a call does.
+    pub fn call(
+        evaluator: &mut Evaluator,
+        closure: &Closure,
+        receiver: Option<Value>,
+        args: Vec<Option<Value>>,
+        cursor: Cursor,
+    ) -> EvalResult<Value> {
+        evaluator.enter_vm(cursor)?;
+        let caller_file = evaluator.file.clone();
+        let res = Vm::new(evaluator).run_closure(closure, receiver, args);
+        let res = res.map_err(|err| evaluator.trace_err(err));
+        evaluator.file = caller_file;
+        evaluator.leave_vm();
+        res
+    }
+
+    fn run_closure(
+        &mut self,
+        closure: &Closure,
+        receiver: Option<Value>,
+        args: Vec<Option<Value>>,
+    ) -> EvalResult<Value> {
+        let base = self.stack.len();
+        self.enter(closure.clone(), receiver, args, base, false);
+        self.run()
+    }
+
+    /// Pushes a frame for a closure with its arguments in the first slots, skipped arguments
+    /// (`None`) take their default value.
+    fn enter(
+        &mut self,
+        closure: Closure,
+        receiver: Option<Value>,
+        args: Vec<Option<Value>>,
+        base: usize,
+        traced: bool,
+    ) {
+        let proto = closure.proto.clone();
+        if proto.has_self {
+            self.stack.push(receiver.unwrap_or(Value::Null));
+        }
+
+        let mut args = args.into_iter();
+        let mut passed = Vec::with_capacity(proto.params.len());
+        for param in &proto.params {
+            let val = if param.variadic {
+                Some(Value::List(Rc::new(RefCell::new(
+                    args.by_ref().flatten().collect(),
+                ))))
+            } else {
+                args.next().flatten()
+            };
+            passed.push(val.is_some());
+            self.stack.push(val.unwrap_or(Value::Null));
+        }
+        self.stack.resize(base + proto.slots, Value::Null);
+
+        self.evaluator.file = proto.file.clone();
+        self.frames.push(CallFrame {
+            closure,
+            ip: 0,
+            base,
+            cells: vec![None; proto.cells],
+            iters: (0..proto.iters).map(|_| None).collect(),
+            errs: (0..proto.errs).map(|_| None).collect(),
+            passed,
+            traced,
+        });
+    }
+
+    /// Pops the innermost frame, errors leaving it record the call stack first.
+    fn leave(&mut self, res: EvalResult<()>) -> EvalResult<()> {
+        let frame = self.frames.pop().expect("expected a frame");
+        self.stack.truncate(frame.base);
+        self.handlers.retain(|h| h.frame < self.frames.len());
+
+        let res = if frame.traced {
+            self.evaluator.pop_frame(res)
+        } else {
+            res
+        };
+        if let Some(caller) = self.frames.last() {
+            self.evaluator.file = caller.proto().file.clone();
+        }
+        res
+    }
+
+    /// Runs until the outermost frame returns, errors are caught by the innermost handler.
+    fn run(&mut self) -> EvalResult<Value> {
+        loop {
+            let err = match self.exec() {
+                Ok(val) => return Ok(val),
+                Err(err) => err,
+            };
+
+            let catchable = matches!(err, RuntimeEvent::Err(_) | RuntimeEvent::UserErr { .. });
+            let Some(handler) = self.handlers.pop().filter(|_| catchable) else {
+                let mut err = err;
+                while !self.frames.is_empty() {
+                    err = self.leave(Err(err)).unwrap_err();
+                }
+                return Err(err);
+            };
+
+            let mut err = err;
+            while self.frames.len() > handler.frame + 1 {
+                err = self.leave(Err(err)).unwrap_err();
+            }
+            self.stack.truncate(handler.stack_len);
+            let frame = self.frame_mut();
+            frame.errs[handler.slot as usize] = Some(err);
+            frame.ip = handler.target;
+        }
+    }
+
+    fn frame(&self) -> &CallFrame {
+        self.frames.last().expect("expected a frame")
+    }
+
+    fn frame_mut(&mut self) -> &mut CallFrame {
+        self.frames.last_mut().expect("expected a frame")
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack.pop().expect("expected a value on the stack")
+    }
+
+    fn pop_n(&mut self, n: usize) -> Vec<Value> {
+        let at = self.stack.len() - n;
+        self.stack.split_off(at)
+    }
+
+    fn top(&self) -> &Value {
+        self.stack.last().expect("expected a value on the stack")
+    }
+
+    fn slot(&self, slot: u16) -> usize {
+        self.frame().base + slot as usize
+    }
+
+    fn cell(&self, k: u16) -> &Cell {
+        self.frame().cells[k as usize]
+            .as_ref()
+            .expect("expected an initialized cell")
+    }
+
+    /// Value of a cell only reachable once its variable is declared.
+    fn declared(cell: &Cell) -> Value {
+        cell.borrow().clone().expect("expected a declared variable")
+    }
+
+    fn name(&self, n: u32) -> &str {
+        &self.frame().proto().chunk.names[n as usize]
+    }
+
+    fn span(&self, span: u32) -> Cursor {
+        self.frame().proto().chunk.spans[span as usize]
+    }
+
+    fn str_val(s: String) -> Value {
+        Value::Str(Str::new(s))
+    }
+
+    fn exec(&mut self) -> EvalResult<Value> {
+        loop {
+            let frame = self.frames.last_mut().expect("expected a frame");
+            let op = frame.closure.proto.chunk.code[frame.ip];
+            let cursor = frame.closure.proto.chunk.cursors[frame.ip];
+            frame.ip += 1;
+
+            match op {
+                Op::Null => self.stack.push(Value::Null),
+                Op::True => self.stack.push(Value::Bool(true)),
+                Op::False => self.stack.push(Value::Bool(false)),
+                Op::Int(i) => {
+                    let n = self.frame().proto().chunk.ints[i as usize].clone();
+                    self.stack.push(Value::Int(n));
+                }
+                Op::Num(i) => {
+                    let n = self.frame().proto().chunk.nums[i as usize];
+                    self.stack.push(Value::Num(n));
+                }
+                Op::Str(i) => {
+                    let s = self.frame().proto().chunk.strs[i as usize].clone();
+                    self.stack.push(Value::Str(s));
+                }
+                Op::Pop => {
+                    self.pop();
+                }
+                Op::Dup => self.stack.push(self.top().clone()),
+
+                Op::LoadLocal(s) => self.stack.push(self.stack[self.slot(s)].clone()),
+                Op::StoreLocal(s) => {
+                    let at = self.slot(s);
+                    self.stack[at] = self.top().clone();
+                }
+                Op::NewCell(k) => {
+                    let val = self.pop();
+                    self.frame_mut().cells[k as usize] = Some(Rc::new(RefCell::new(Some(val))));
+                }
+                Op::HoistCell(k) => {
+                    self.frame_mut().cells[k as usize] = Some(Rc::new(RefCell::new(None)));
+                }
+                Op::LoadCell(k) => {
+                    let val = Self::declared(self.cell(k));
+                    self.stack.push(val);
+                }
+                Op::StoreCell(k) => *self.cell(k).borrow_mut() = Some(self.top().clone()),
+                Op::LoadUpval(i) => {
+                    let val = Self::declared(&self.frame().closure.upvals[i as usize]);
+                    self.stack.push(val);
+                }
+                Op::StoreUpval(i) => {
+                    *self.frame().closure.upvals[i as usize].borrow_mut() = Some(self.top().clone())
+                }
+                Op::LoadLateUpval { upval, name } => {
+                    let val = self.frame().closure.upvals[upval as usize].borrow().clone();
+                    let val = match val {
+                        Some(val) => val,
+                        None => self.globals().borrow().get(self.name(name), cursor)?,
+                    };
+                    self.stack.push(val);
+                }
+                Op::LoadGlobal(n) => {
+                    let val = self.globals().borrow().get(self.name(n), cursor)?;
+                    self.stack.push(val);
+                }
+                Op::StoreGlobal(n) => {
+                    let val = self.top().clone();
+                    self.globals()
+                        .borrow_mut()
+                        .assign(self.name(n), val, cursor)?;
+                }
+                Op::DefineGlobal(n) => {
+                    let val = self.pop();
+                    self.globals()
+                        .borrow_mut()
+                        .define(self.name(n).to_string(), val);
+                }
+
+                Op::Add => self.binary(BinaryOp::Add, cursor)?,
+                Op::Sub => self.binary(BinaryOp::Sub, cursor)?,
+                Op::Mul => self.binary(BinaryOp::Mult, cursor)?,
+                Op::Div => self.binary(BinaryOp::Div, cursor)?,
+                Op::FloorDiv => self.binary(BinaryOp::FloorDiv, cursor)?,
+                Op::Mod => self.binary(BinaryOp::Mod, cursor)?,
+                Op::Pow => self.binary(BinaryOp::Pow, cursor)?,
+                Op::BitAnd => self.binary(BinaryOp::BitAnd, cursor)?,
+                Op::BitOr => self.binary(BinaryOp::BitOr, cursor)?,
+                Op::BitXor => self.binary(BinaryOp::BitXor, cursor)?,
+                Op::ShiftLeft => self.binary(BinaryOp::ShiftLeft, cursor)?,
+                Op::ShiftRight => self.binary(BinaryOp::ShiftRight, cursor)?,
+                Op::Equals => self.binary(BinaryOp::Equals, cursor)?,
+                Op::NotEquals => self.binary(BinaryOp::NotEquals, cursor)?,
+                Op::Greater => self.binary(BinaryOp::Greater, cursor)?,
+                Op::GreaterEquals => self.binary(BinaryOp::GreaterEquals, cursor)?,
+                Op::Lesser => self.binary(BinaryOp::Lesser, cursor)?,
+                Op::LesserEquals => self.binary(BinaryOp::LesserEquals, cursor)?,
+                Op::Nullish => self.binary(BinaryOp::Nullish, cursor)?,
+                Op::Neg => {
+                    let right = self.pop();
+                    let val = match right {
+                        Value::Int(n) => Value::Int(-&n),
+                        Value::Num(n) => Value::Num(-n),
+                        right => self.evaluator.unary_op(&UnaryOp::Negate, right, cursor)?,
+                    };
+                    self.stack.push(val);
+                }
+                Op::Not => {
+                    let right = self.pop();
+                    self.stack.push(Value::Bool(!right.is_truthy()));
+                }
+                Op::BitNot => {
+                    let right = self.pop();
+                    let val = match right {
+                        Value::Int(n) => Value::Int(!&n),
+                        right => self.evaluator.unary_op(&UnaryOp::BitNot, right, cursor)?,
+                    };
+                    self.stack.push(val);
+                }
+                Op::AddAssign | Op::SubAssign => {
+                    let current = self.pop();
+                    let rhs = self.pop();
+                    let val = match op {
+                        Op::AddAssign => current.add_assign(self.evaluator, rhs, cursor)?,
+                        _ => current.sub_assign(self.evaluator, rhs, cursor)?,
+                    };
+                    self.stack.push(val);
+                }
+
+                Op::Jump(target) => self.frame_mut().ip = target as usize,
+                Op::JumpIfFalse(target) => {
+                    if !self.pop().is_truthy() {
+                        self.frame_mut().ip = target as usize;
+                    }
+                }
+                Op::JumpIfFalseKeep(target) => {
+                    if self.top().is_truthy() {
+                        self.pop();
+                    } else {
+                        self.frame_mut().ip = target as usize;
+                    }
+                }
+                Op::JumpIfTrueKeep(target) => {
+                    if self.top().is_truthy() {
+                        self.frame_mut().ip = target as usize;
+                    } else {
+                        self.pop();
+                    }
+                }
+                Op::JumpIfPassed { param, target } => {
+                    let frame = self.frame_mut();
+                    if frame.passed[param as usize] {
+                        frame.ip = target as usize;
+                    }
+                }
+
+                Op::ToStr => {
+                    let val = self.pop();
+                    let s = self.evaluator.stringify(&val, cursor)?;
+                    self.stack.push(Self::str_val(s));
+                }
+                Op::Concat(n) => {
+                    let parts = self.pop_n(n as usize);
+                    let mut out = String::new();
+                    for part in parts {
+                        if let Value::Str(s) = part {
+                            out.push_str(&s.borrow());
+                        }
+                    }
+                    self.stack.push(Self::str_val(out));
+                }
+                Op::List(n) => {
+                    let items = self.pop_n(n as usize);
+                    self.stack.push(Value::List(Rc::new(RefCell::new(items))));
+                }
+                Op::Dict(n) => {
+                    let entries = self.pop_n(2 * n as usize);
+                    let mut map = HashMap::new();
+                    let mut entries = entries.into_iter();
+                    while let (Some(key), Some(val)) = (entries.next(), entries.next()) {
+                        map.insert(Evaluator::dict_key(&key, cursor)?, val);
+                    }
+                    self.stack.push(Value::Dict(Rc::new(RefCell::new(map))));
+                }
+                Op::Range {
+                    end,
+                    step,
+                    inclusive,
+                    span,
+                } => {
+                    let step = step.then(|| self.pop());
+                    let end = end.then(|| self.pop());
+                    let start = self.pop();
+                    let step_cursor = self.span(span);
+                    let range =
+                        Evaluator::range_value(start, end, step, inclusive, cursor, step_cursor)?;
+                    self.stack.push(range);
+                }
+                Op::Index(span) => {
+                    let index = self.pop();
+                    let base = self.pop();
+                    let val = self
+                        .evaluator
+                        .index_value(base, index, cursor, self.span(span))?;
+                    self.stack.push(val);
+                }
+                Op::IndexSet(span) => {
+                    let val = self.pop();
+                    let index = self.pop();
+                    let base = self.pop();
+                    let index_cursor = self.span(span);
+                    let val =
+                        self.evaluator
+                            .set_index(base, index, |_| Ok(val), cursor, index_cursor)?;
+                    self.stack.push(val);
+                }
+                Op::SliceSet {
+                    end,
+                    step,
+                    inclusive,
+                    span,
+                } => {
+                    let val = self.pop();
+                    let step = step.then(|| self.pop());
+                    let end = end.then(|| self.pop());
+                    let start = self.pop();
+                    let base = self.pop();
+                    let index_cursor = self.span(span);
+
+                    let val = if let Value::ObjInstance(_) = base {
+                        let range = Evaluator::range_value(
+                            start,
+                            end,
+                            step,
+                            inclusive,
+                            index_cursor,
+                            index_cursor,
+                        )?;
+                        self.evaluator
+                            .set_index(base, range, |_| Ok(val), cursor, index_cursor)?
+                    } else if step.is_some() {
+                        return Err(RuntimeEvent::error(
+                            ErrKind::Value,
+                            "range step not supported in slice assignment".into(),
+                            index_cursor,
+                        ));
+                    } else {
+                        self.evaluator.set_slice(
+                            base,
+                            (start, end, inclusive),
+                            |_| Ok(val),
+                            cursor,
+                            index_cursor,
+                        )?
+                    };
+                    self.stack.push(val);
+                }
+                Op::GetProp { name, cache } => {
+                    let obj = self.pop();
+                    let proto = self.frame().closure.proto.clone();
+                    let val = self.evaluator.get_property(
+                        obj,
+                        &proto.chunk.names[name as usize],
+                        Some(&proto.chunk.caches[cache as usize]),
+                        cursor,
+                    )?;
+                    self.stack.push(val);
+                }
+                Op::SetProp(n) | Op::SetPropAdd(n) | Op::SetPropSub(n) => {
+                    let assign_op = match op {
+                        Op::SetProp(_) => AssignOp::Value,
+                        Op::SetPropAdd(_) => AssignOp::Add,
+                        _ => AssignOp::Sub,
+                    };
+                    let val = self.pop();
+                    let obj = self.pop();
+                    let proto = self.frame().closure.proto.clone();
+                    let name = &proto.chunk.names[n as usize];
+                    let val =
+                        self.evaluator
+                            .set_property(obj, name, &assign_op, |_| Ok(val), cursor)?;
+                    self.stack.push(val);
+                }
+                Op::Super { name, bound } => {
+                    let receiver = bound.then(|| self.pop());
+                    let Value::Obj(parent) = self.pop() else {
+                        unreachable!("super should always be bound to an Obj");
+                    };
+                    let val =
+                        self.evaluator
+                            .super_method(&parent, receiver, self.name(name), cursor)?;
+                    self.stack.push(val);
+                }
+
+                Op::Call(argc) => {
+                    self.call_value(argc as usize, None, cursor, false)?;
+                }
+                Op::CallNamed { argc, names } => {
+                    self.call_value(argc as usize, Some(names), cursor, false)?;
+                }
+                Op::TailCall(argc) => {
+                    if !self.call_value(argc as usize, None, cursor, true)?
+                        && let Some(val) = self.ret()?
+                    {
+                        return Ok(val);
+                    }
+                }
+                Op::TailCallNamed { argc, names } => {
+                    if !self.call_value(argc as usize, Some(names), cursor, true)?
+                        && let Some(val) = self.ret()?
+                    {
+                        return Ok(val);
+                    }
+                }
+                Op::Closure(i) => {
+                    let frame = self.frame();
+                    let proto = frame.proto().chunk.protos[i as usize].clone();
+                    let upvals: Rc<[Cell]> = proto
+                        .upvals
+                        .iter()
+                        .map(|upval| match upval {
+                            Upval::Cell(k) => frame.cells[*k as usize]
+                                .clone()
+                                .expect("expected an initialized cell"),
+                            Upval::Upval(i) => frame.closure.upvals[*i as usize].clone(),
+                        })
+                        .collect();
+                    let closure = Closure::new(proto, upvals, frame.closure.globals.clone());
+                    self.stack.push(Value::Callable(Rc::new(closure)));
+                }
+                Op::Return => {
+                    if let Some(val) = self.ret()? {
+                        return Ok(val);
+                    }
+                }
+                Op::Obj {
+                    name,
+                    methods,
+                    parent,
+                } => {
+                    let methods = self.pop_n(methods as usize);
+                    let parent = parent.then(|| match self.pop() {
+                        Value::Obj(obj) => obj,
+                        _ => unreachable!("parents are checked before the methods"),
+                    });
+
+                    let mut obj_methods: HashMap<String, Method> = HashMap::new();
+                    for method in methods {
+                        let Value::Callable(callable) = method else {
+                            unreachable!("methods are compiled to closures");
+                        };
+                        let has_self = callable
+                            .as_closure()
+                            .is_some_and(|(closure, _)| closure.proto.has_self);
+                        obj_methods.insert(
+                            callable.name().to_string(),
+                            Method::Native(NativeMethod::new(callable, has_self)),
+                        );
+                    }
+
+                    let name = self.name(name).to_string();
+                    let obj = match parent {
+                        Some(parent) => Object::with_parent(name, obj_methods, parent),
+                        None => Object::new(name, obj_methods),
+                    };
+                    self.stack.push(Value::Obj(Rc::new(obj)));
+                }
+                Op::Parent(n) => {
+                    let parent = self.pop();
+                    let parent = Evaluator::parent_obj(self.name(n), parent, cursor)?;
+                    self.stack.push(Value::Obj(parent));
+                }
+                Op::Throw => {
+                    let val = self.pop();
+                    return Err(RuntimeEvent::user_err(val, cursor));
+                }
+                Op::UseModule(n) => {
+                    let path = self.pop();
+                    let (_, env) = self.evaluator.load_use(&path, cursor)?;
+                    let module = Module::new(self.name(n).to_string(), env);
+                    self.stack.push(Value::Module(Rc::new(module)));
+                }
+                Op::UseNames(i) => {
+                    let path = self.pop();
+                    let (path_str, env) = self.evaluator.load_use(&path, cursor)?;
+                    let module = Module::new(path_str, env);
+                    let names = self.frame().proto().chunk.uses[i as usize].clone();
+                    for name in names {
+                        self.stack.push(module.get(&name, cursor)?);
+                    }
+                }
+                Op::UseAll => {
+                    let path = self.pop();
+                    let (_, env) = self.evaluator.load_use(&path, cursor)?;
+                    // imported globals are merged into our globals
+                    for (name, value) in env.borrow().entries() {
+                        self.globals().borrow_mut().define(name, value);
+                    }
+                }
+
+                Op::IterInit(k) => {
+                    let val = self.pop();
+                    let iter = ValueIter::new(self.evaluator, val, cursor)?;
+                    self.frame_mut().iters[k as usize] = Some(iter);
+                }
+                Op::IterNext { iter, target } => {
+                    let mut it = self.frame_mut().iters[iter as usize]
+                        .take()
+                        .expect("expected an iterator");
+                    let next = it.next(self.evaluator, cursor);
+                    self.frame_mut().iters[iter as usize] = Some(it);
+                    match next? {
+                        Some((item, index)) => {
+                            self.stack.push(item);
+                            self.stack.push(index);
+                        }
+                        None => self.frame_mut().ip = target as usize,
+                    }
+                }
+
+                Op::PushHandler { slot, target } => {
+                    let handler = Handler {
+                        frame: self.frames.len() - 1,
+                        target: target as usize,
+                        slot,
+                        stack_len: self.stack.len(),
+                    };
+                    self.handlers.push(handler);
+                }
+                Op::PopHandler => {
+                    self.handlers.pop();
+                }
+                Op::Rethrow(slot) => {
+                    return Err(self.take_err(slot));
+                }
+                Op::CatchMatch { slot, kinds } => {
+                    let frame = self.frame();
+                    let kinds = &frame.proto().chunk.catches[kinds as usize];
+                    let err = frame.errs[slot as usize]
+                        .as_ref()
+                        .expect("expected a caught error");
+                    let matches = Evaluator::catch_matches(kinds, err).is_some();
+                    self.stack.push(Value::Bool(matches));
+                }
+                Op::CatchErr { slot, kinds } => {
+                    let err = self.take_err(slot);
+                    let kinds = &self.frame().proto().chunk.catches[kinds as usize];
+                    let by_obj = Evaluator::catch_matches(kinds, &err) == Some(true);
+                    let val = self.evaluator.caught_err(&err, by_obj);
+                    self.frame_mut().errs[slot as usize] = Some(err);
+                    self.stack.push(val?);
+                }
+                Op::CatchVal(slot) => {
+                    let err = self.frame().errs[slot as usize]
+                        .as_ref()
+                        .expect("expected a caught error");
+                    self.stack.push(Evaluator::caught_val(err));
+                }
+
+                Op::MatchValue => {
+                    let pattern_val = self.pop();
+                    let val = self.pop();
+                    let matches = self.evaluator.value_matches(&val, &pattern_val, cursor)?;
+                    self.stack.push(Value::Bool(matches));
+                }
+                Op::IsType(n) => {
+                    let val = self.pop();
+                    let is_type = val.is_type(self.name(n));
+                    self.stack.push(Value::Bool(is_type));
+                }
+                Op::IsList { len, rest } => {
+                    let matches = match self.pop() {
+                        Value::List(list) => {
+                            let n = list.borrow().len();
+                            n == len as usize || (rest && n > len as usize)
+                        }
+                        _ => false,
+                    };
+                    self.stack.push(Value::Bool(matches));
+                }
+                Op::IsDict => {
+                    let is_dict = matches!(self.pop(), Value::Dict(_));
+                    self.stack.push(Value::Bool(is_dict));
+                }
+                Op::ListItem(i) => {
+                    let Value::List(list) = self.pop() else {
+                        unreachable!("list patterns check the type first");
+                    };
+                    let item = list.borrow().get(i as usize).cloned();
+                    self.stack.push(item.unwrap_or(Value::Null));
+                }
+                Op::ListRest(i) => {
+                    let Value::List(list) = self.pop() else {
+                        unreachable!("list patterns check the type first");
+                    };
+                    let rest = list.borrow().get(i as usize..).unwrap_or(&[]).to_vec();
+                    self.stack.push(Value::List(Rc::new(RefCell::new(rest))));
+                }
+                Op::DictEntry(slot) => {
+                    let key = self.pop();
+                    let Value::Dict(dict) = self.pop() else {
+                        unreachable!("dict patterns check the type first");
+                    };
+                    let key = Evaluator::pattern_key(&key, cursor)?;
+                    let entry = dict.borrow().get(&key).cloned();
+                    let found = entry.is_some();
+                    if let Some(entry) = entry {
+                        let at = self.slot(slot);
+                        self.stack[at] = entry;
+                    }
+                    self.stack.push(Value::Bool(found));
+                }
+            }
+        }
+    }
+
+    fn globals(&self) -> &EnvPtr {
+        &self.frame().closure.globals
+    }
+
+    fn take_err(&mut self, slot: u16) -> RuntimeEvent {
+        self.frame_mut().errs[slot as usize]
+            .take()
+            .expect("expected a caught error")
+    }
+
+    /// Operators on two numbers are run in place, everything else goes through the evaluator.
+    fn binary(&mut self, op: BinaryOp, cursor: Cursor) -> EvalResult<()> {
+        let right = self.pop();
+        let left = self.pop();
+        let val = match left.num_op(&op, &right, cursor) {
+            Some(res) => res?,
+            None => self.evaluator.binary_op(left, &op, right, cursor)?,
+        };
+        self.stack.push(val);
+        Ok(())
+    }
+
+    /// Returns the value on top of the stack from the innermost frame, the value is handed back
+    /// once the outermost frame returns.
+    fn ret(&mut self) -> EvalResult<Option<Value>> {
+        let val = self.pop();
+        self.leave(Ok(()))?;
+        if self.frames.is_empty() {
+            return Ok(Some(val));
+        }
+        self.stack.push(val);
+        Ok(None)
+    }
+
+    /// Calls the value below the arguments. Closures get a new frame, or take over the frame of
+    /// the caller in tail calls, and `true` is returned. Other callables are run by the
+    /// evaluator and their result is pushed.
+    fn call_value(
+        &mut self,
+        argc: usize,
+        names: Option<u16>,
+        cursor: Cursor,
+        tail: bool,
+    ) -> EvalResult<bool> {
+        let names = names
+            .map(|i| self.frame().proto().chunk.calls[i as usize].clone())
+            .unwrap_or_default();
+        let named_vals = self.pop_n(names.len());
+        let args = self.pop_n(argc);
+        let callee = self.pop();
+        let named: Vec<_> = names
+            .into_iter()
+            .zip(named_vals)
+            .map(|((name, cursor), val)| (name, cursor, val))
+            .collect();
+
+        let Value::Callable(callable) = &callee else {
+            let val = self.evaluator.call_value(callee, args, named, cursor)?;
+            self.stack.push(val);
+            return Ok(false);
+        };
+        let Some((closure, receiver)) = callable
+            .as_closure()
+            .map(|(closure, receiver)| (closure.clone(), receiver.cloned()))
+        else {
+            let val = self.evaluator.call_value(callee, args, named, cursor)?;
+            self.stack.push(val);
+            return Ok(false);
+        };
+
+        if named.is_empty() && !callable.arity().accepts(args.len()) {
+            return Err(RuntimeEvent::error(
+                ErrKind::Arity,
+                format!(
+                    "function expects {} arguments but got {}",
+                    callable.arity(),
+                    args.len()
+                ),
+                cursor,
+            ));
+        }
+
+        // tail calls run in place of the body that returned them, reusing the frame
+        if tail {
+            let args = if named.is_empty() {
+                args.into_iter().map(Some).collect()
+            } else {
+                self.evaluator
+                    .match_named_args(callable.as_ref(), args, named, cursor)?
+            };
+            self.evaluator.replace_frame(callable.name());
+            let frame = self.frames.pop().expect("expected a frame");
+            self.stack.truncate(frame.base);
+            self.enter(closure, receiver, args, frame.base, frame.traced);
+            return Ok(true);
+        }
+
+        self.evaluator.push_frame(callable.name(), cursor)?;
+        let args = if named.is_empty() {
+            args.into_iter().map(Some).collect()
+        } else {
+            match self
+                .evaluator
+                .match_named_args(callable.as_ref(), args, named, cursor)
+            {
+                Ok(args) => args,
+                Err(err) => return self.evaluator.pop_frame(Err(err)),
+            }
+        };
+        let base = self.stack.len();
+        self.enter(closure, receiver, args, base, true);
+        Ok(true)
+    }
+}
@@ -0,0 +1,66 @@
+//! Runs unbounded recursion at the largest `--recursion-limit`, it has to raise a `RecursionErr`
+//! on the tree-walker and on the VM instead of overflowing the native stack.
+
+use std::{
+    path::Path,
+    process::{Command, Output},
+};
+
+/// Recurses through plain calls
+const CALLS: &str = "tests/scripts/recursion.qte";
+/// Recurses through operator methods, which the VM runs on the native stack
+const OPERATORS: &str = "tests/scripts/recursion_operator.qte";
+
+/// Runs a script of the crate with the given recursion limit.
+fn run(script: &str, limit: &str, vm: bool) -> Output {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_quetite"));
+    if vm {
+        cmd.arg("--vm");
+    }
+    cmd.args(["--recursion-limit", limit])
+        .arg(Path::new(env!("CARGO_MANIFEST_DIR")).join(script))
+        .output()
+        .expect("failed to run the interpreter")
+}
+
+fn assert_recursion_err(script: &str, vm: bool) {
+    let out = run(script, "32768", vm);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    // a native stack overflow aborts the process without an exit code
+    assert_eq!(
+        out.status.code(),
+        Some(1),
+        "{script} didn't exit with an error:\n{stdout}"
+    );
+    assert!(
+        stdout.contains("(RecursionErr) maximum recursion depth of 32768 exceeded"),
+        "{script} didn't raise a RecursionErr:\n{stdout}"
+    );
+}
+
+#[test]
+fn deep_recursion_raises_recursion_err() {
+    assert_recursion_err(CALLS, false);
+}
+
+#[test]
+fn deep_recursion_raises_recursion_err_on_vm() {
+    assert_recursion_err(CALLS, true);
+}
+
+#[test]
+fn operator_recursion_raises_recursion_err() {
+    assert_recursion_err(OPERATORS, false);
+}
+
+#[test]
+fn operator_recursion_raises_recursion_err_on_vm() {
+    assert_recursion_err(OPERATORS, true);
+}
+
+#[test]
+fn recursion_limit_above_stack_is_rejected() {
+    let out = run(CALLS, "10000000", false);
+    assert_eq!(out.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("must be at most 32768"));
+}
@@ -0,0 +1,73 @@
+//! Runs scripts on the tree-walker and on the VM (`--vm`), both have to print the same output.
+
+use std::{
+    io::Write,
+    path::Path,
+    process::{Command, Output, Stdio},
+};
+
+/// Runs a script of the crate with the interpreter, `input` is written to its stdin.
+fn run(script: &str, input: &str, vm: bool) -> Output {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_quetite"));
+    if vm {
+        cmd.arg("--vm");
+    }
+    let mut child = cmd
+        .arg(Path::new(env!("CARGO_MANIFEST_DIR")).join(script))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to start the interpreter");
+    child
+        .stdin
+        .take()
+        .expect("expected a piped stdin")
+        .write_all(input.as_bytes())
+        .expect("failed to write the script input");
+    child
+        .wait_with_output()
+        .expect("failed to wait for the interpreter")
+}
+
+fn assert_same_output(script: &str, input: &str) {
+    let evaluator = run(script, input, false);
+    let vm = run(script, input, true);
+
+    let evaluator_out = String::from_utf8_lossy(&evaluator.stdout);
+    assert!(
+        evaluator.status.success(),
+        "{script} failed on the tree-walker:\n{evaluator_out}"
+    );
+    assert_eq!(
+        evaluator_out,
+        String::from_utf8_lossy(&vm.stdout),
+        "{script} printed different output on the VM"
+    );
+    assert_eq!(evaluator.status.code(), vm.status.code());
+}
+
+#[test]
+fn hello_world() {
+    assert_same_output("examples/hello_world.qte", "");
+}
+
+#[test]
+fn fizzbuzz() {
+    assert_same_output("examples/fizzbuzz.qte", "");
+}
+
+#[test]
+fn fibonacci() {
+    assert_same_output("examples/fibonacci.qte", "15\n");
+}
+
+#[test]
+fn closures_match_try() {
+    assert_same_output("tests/scripts/closures.qte", "");
+}
+
+#[test]
+fn module_functions_write_their_globals() {
+    assert_same_output("tests/scripts/modules.qte", "");
+}